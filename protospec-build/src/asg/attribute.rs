@@ -0,0 +1,62 @@
+use super::*;
+
+/// An `@name(arguments)` attribute of a type or container field, interpreted by the backends, which ignore attributes they do not know
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub arguments: Option<Vec<AttributeArgument>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum AttributeArgument {
+    /// `name` or `name(arguments)`
+    Attribute(Attribute),
+    /// `name = literal`
+    Value(String, AttributeLiteral),
+    Literal(AttributeLiteral),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeLiteral {
+    Int(u64),
+    Str(Vec<u8>),
+    Bool(bool),
+}
+
+impl Attribute {
+    /// The argument of an attribute taking a single string, such as `@rust_name("name")`
+    pub fn string_argument(&self) -> Option<String> {
+        match self.arguments.as_deref() {
+            Some([AttributeArgument::Literal(AttributeLiteral::Str(value))]) => {
+                String::from_utf8(value.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The names given as arguments, as with `@derive(Hash, Ord)`, or `None` if any argument is not a bare name
+    pub fn name_arguments(&self) -> Option<Vec<&str>> {
+        self.arguments
+            .as_ref()?
+            .iter()
+            .map(|argument| match argument {
+                AttributeArgument::Attribute(Attribute {
+                    name,
+                    arguments: None,
+                    ..
+                }) => Some(&**name),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Field {
+    /// The attributes of the field named `name`
+    pub fn attributes_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Attribute> {
+        self.attributes
+            .iter()
+            .filter(move |attribute| attribute.name == name)
+    }
+}
@@ -3,17 +3,32 @@ use super::*;
 #[derive(PartialEq, Clone, Debug)]
 pub struct MemberExpression {
     pub target: Box<Expression>,
-    //todo: this might need to be more general in the future
-    pub member: Arc<Const>,
+    pub member: Member,
     pub span: Span,
 }
 
+#[derive(PartialEq, Clone, Debug)]
+pub enum Member {
+    /// A flag of a bitfield
+    Flag(Arc<Const>),
+    /// A field of a container, i.e. a decoded array element
+    Field(Arc<Field>),
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        match self {
+            Member::Flag(flag) => &flag.name,
+            Member::Field(field) => &field.name,
+        }
+    }
+}
+
 impl AsgExpression for MemberExpression {
     fn get_type(&self) -> Option<Type> {
-        let type_ = self.target.get_type()?.resolved().into_owned();
-        match &*type_.resolved() {
-            Type::Bitfield(_) => Some(Type::Bool),
-            _ => None,
+        match &self.member {
+            Member::Flag(_) => Some(Type::Bool),
+            Member::Field(field) => Some(field.type_.borrow().clone()),
         }
     }
 }
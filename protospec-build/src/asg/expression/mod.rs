@@ -30,6 +30,12 @@ pub use int::*;
 mod member;
 pub use member::*;
 
+mod tag;
+pub use tag::*;
+
+mod size_of;
+pub use size_of::*;
+
 pub trait AsgExpression {
     fn get_type(&self) -> Option<Type>;
 }
@@ -50,6 +56,8 @@ pub enum Expression {
     Bool(bool),
     Call(CallExpression),
     Member(MemberExpression),
+    Tag(TagExpression),
+    SizeOf(SizeOfExpression),
 }
 
 impl AsgExpression for Expression {
@@ -77,12 +85,86 @@ impl AsgExpression for Expression {
                         type_: ScalarType::U64,
                         span: e.span,
                     })),
+                    until: None,
+                    bytes: false,
+                    terminator: TerminatorPolicy::default(),
                 },
+                map: None,
             }))),
             Ternary(e) => e.get_type(),
             Bool(_) => Some(Type::Bool),
             Call(ffi) => ffi.get_type(),
             Member(e) => e.get_type(),
+            Tag(e) => e.get_type(),
+            SizeOf(e) => e.get_type(),
+        }
+    }
+}
+
+impl Expression {
+    /// The fields referenced anywhere in this expression
+    pub fn referenced_fields(&self) -> Vec<Arc<Field>> {
+        let mut out = vec![];
+        self.extract_referenced_fields(&mut out);
+        out
+    }
+
+    /// Whether the function `name` is called anywhere in this expression
+    pub fn calls_function(&self, name: &str) -> bool {
+        match self {
+            Expression::Binary(expr) => {
+                expr.left.calls_function(name) || expr.right.calls_function(name)
+            }
+            Expression::Unary(expr) => expr.inner.calls_function(name),
+            Expression::Cast(expr) => expr.inner.calls_function(name),
+            Expression::ArrayIndex(expr) => {
+                expr.array.calls_function(name) || expr.index.calls_function(name)
+            }
+            Expression::Ternary(expr) => {
+                expr.condition.calls_function(name)
+                    || expr.if_true.calls_function(name)
+                    || expr.if_false.calls_function(name)
+            }
+            Expression::Call(expr) => {
+                expr.function.name == name || expr.arguments.iter().any(|x| x.calls_function(name))
+            }
+            Expression::Member(expr) => expr.target.calls_function(name),
+            _ => false,
+        }
+    }
+
+    fn extract_referenced_fields(&self, output: &mut Vec<Arc<Field>>) {
+        match self {
+            Expression::Binary(expr) => {
+                expr.left.extract_referenced_fields(output);
+                expr.right.extract_referenced_fields(output);
+            }
+            Expression::Unary(expr) => expr.inner.extract_referenced_fields(output),
+            Expression::Cast(expr) => expr.inner.extract_referenced_fields(output),
+            Expression::ArrayIndex(expr) => {
+                expr.array.extract_referenced_fields(output);
+                expr.index.extract_referenced_fields(output);
+            }
+            Expression::Ternary(expr) => {
+                expr.condition.extract_referenced_fields(output);
+                expr.if_true.extract_referenced_fields(output);
+                expr.if_false.extract_referenced_fields(output);
+            }
+            Expression::Call(expr) => {
+                for expr in &expr.arguments {
+                    expr.extract_referenced_fields(output);
+                }
+            }
+            Expression::Member(expr) => expr.target.extract_referenced_fields(output),
+            Expression::FieldRef(field) => output.push(field.clone()),
+            Expression::Tag(expr) => output.push(expr.target.clone()),
+            Expression::EnumAccess(_)
+            | Expression::Int(_)
+            | Expression::ConstRef(_)
+            | Expression::InputRef(_)
+            | Expression::Str(_)
+            | Expression::Bool(_)
+            | Expression::SizeOf(_) => (),
         }
     }
 }
@@ -104,6 +186,8 @@ impl fmt::Debug for Expression {
             Self::Bool(arg0) => f.debug_tuple("Bool").field(arg0).finish(),
             Self::Call(arg0) => f.debug_tuple("Call").field(arg0).finish(),
             Self::Member(arg0) => f.debug_tuple("Member").field(arg0).finish(),
+            Self::Tag(arg0) => f.debug_tuple("Tag").field(&arg0.target.name).finish(),
+            Self::SizeOf(arg0) => f.debug_tuple("SizeOf").field(&arg0.target.name).finish(),
         }
     }
 }
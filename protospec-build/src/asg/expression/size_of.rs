@@ -0,0 +1,14 @@
+use super::*;
+
+/// `sizeof(target)`, the encoded size of a top level type, which must be fixed
+#[derive(PartialEq, Clone)]
+pub struct SizeOfExpression {
+    pub target: Arc<Field>,
+    pub span: Span,
+}
+
+impl AsgExpression for SizeOfExpression {
+    fn get_type(&self) -> Option<Type> {
+        Some(Type::Scalar(ScalarType::U64.into()))
+    }
+}
@@ -0,0 +1,15 @@
+use super::*;
+
+/// The `match` discriminant selecting the variant held by `target`, a field of a `match` container type
+#[derive(PartialEq, Clone)]
+pub struct TagExpression {
+    pub target: Arc<Field>,
+    pub type_: Type,
+    pub span: Span,
+}
+
+impl AsgExpression for TagExpression {
+    fn get_type(&self) -> Option<Type> {
+        Some(self.type_.clone())
+    }
+}
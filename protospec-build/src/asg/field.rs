@@ -6,6 +6,10 @@ pub struct TypeArgument {
     pub type_: Type,
     pub default_value: Option<Expression>,
     pub can_resolve_auto: bool,
+    /// Whether the type is a `match` container over this argument, so that it can be derived from the variant
+    pub is_discriminant: bool,
+    /// Whether the type is a `match` container over an expression reading this argument, which cannot be derived
+    pub in_discriminant: bool,
 }
 
 #[derive(Debug)]
@@ -22,11 +26,24 @@ pub struct Field {
     pub span: Span,
     pub type_: RefCell<Type>,
     pub calculated: RefCell<Option<Expression>>,
+    pub constraint: RefCell<Option<Expression>>,
     pub condition: RefCell<Option<Expression>>,
+    // absolute offset the field is stored at, relative to the start of the outermost decoded type
+    pub pointer: RefCell<Option<Expression>>,
     pub transforms: RefCell<Vec<TypeTransform>>,
     pub toplevel: bool,
     pub is_maybe_cyclical: Cell<bool>,
+    // set on fields holding a type which holds the field's own type, and so are stored behind a `Box`
+    pub is_boxed: Cell<bool>,
     pub is_pad: Cell<bool>,
+    pub is_fixed: Cell<bool>,
+    // set on types which contain pointer fields, directly or through references, and so need a seekable stream
+    pub is_seekable: Cell<bool>,
+    // the discriminant held by an `+unknown` variant, evaluated rather than decoded and not encoded
+    pub is_tag: Cell<bool>,
+    // computed by `Field::encoded_size`
+    pub encoded_size: Cell<Option<EncodedSize>>,
+    pub attributes: Vec<Attribute>,
 }
 
 impl Field {
@@ -39,6 +56,9 @@ impl Field {
 impl fmt::Display for Field {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}: {}", self.name, self.type_.borrow())?;
+        if let Some(pointer) = &*self.pointer.borrow() {
+            write!(f, " @ ({})", pointer)?;
+        }
         if let Some(condition) = &*self.condition.borrow() {
             write!(f, " {{ {} }}", condition)?;
         }
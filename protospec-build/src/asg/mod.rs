@@ -21,6 +21,9 @@ pub use program::*;
 mod field;
 pub use field::*;
 
+mod attribute;
+pub use attribute::*;
+
 mod types;
 pub use types::*;
 
@@ -18,4 +18,17 @@ impl Program {
             }
         }
     }
+
+    pub fn scan_seekable(&self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for field in self.types.values() {
+                if !field.is_seekable.get() && field.type_.borrow().is_seekable() {
+                    field.is_seekable.set(true);
+                    changed = true;
+                }
+            }
+        }
+    }
 }
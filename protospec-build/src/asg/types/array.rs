@@ -1,25 +1,126 @@
 use super::*;
+use crate::StreamIntrinsic;
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct ArrayType {
     pub element: Box<Type>,
     pub length: LengthConstraint,
+    pub map: Option<MapKey>,
+}
+
+/// Collects the elements of an array into a map, keyed by a field of each element
+#[derive(PartialEq, Clone, Debug)]
+pub struct MapKey {
+    pub key: String,
+    pub duplicates: DuplicateKeys,
+}
+
+/// How elements with the key of an earlier element are decoded
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum DuplicateKeys {
+    Error,
+    First,
+    Last,
+}
+
+impl ArrayType {
+    /// The field of the elements keying a map, if it exists
+    pub fn map_key_field(&self) -> Option<Arc<Field>> {
+        let map = self.map.as_ref()?;
+        let element = match &*self.element {
+            Type::Ref(type_ref) => type_ref.target.clone(),
+            _ => return None,
+        };
+        let type_ = element.type_.borrow();
+        match &*type_ {
+            Type::Container(container) if !container.is_enum.get() => {
+                container.items.get(&map.key).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the length is read from the stream position, which leaves it unchecked when encoding, as the encoder cannot see what follows
+    pub fn reads_remaining(&self) -> bool {
+        matches!(&self.length.value, Some(x) if x
+            .stream_intrinsics()
+            .iter()
+            .any(|x| matches!(x, StreamIntrinsic::Remaining | StreamIntrinsic::Eof)))
+    }
+
+    /// The number of elements, if constant, in which case the array is represented as a Rust array
+    pub fn fixed_length(&self) -> Option<usize> {
+        if self.length.expandable || self.length.bytes || self.map.is_some() {
+            return None;
+        }
+        match crate::eval_const_expression(self.length.value.as_ref()?)? {
+            crate::ConstValue::Int(value) => match value.cast_to(ScalarType::U64) {
+                ConstInt::U64(value) => std::convert::TryFrom::try_from(value).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct LengthConstraint {
     pub expandable: bool,
     pub value: Option<Expression>,
+    pub until: Option<ArrayUntil>,
+    // whether `value` is a length in bytes, the array holding as many elements as fit
+    pub bytes: bool,
+    pub terminator: TerminatorPolicy,
+}
+
+/// Further terminators accepted after the first, and how the matched terminator is handled
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct TerminatorPolicy {
+    pub alternatives: Vec<Expression>,
+    // whether the terminator is left in the stream for the next field
+    pub leave: bool,
+    // whether the end of the stream also ends the array
+    pub eof: bool,
+}
+
+/// Ends an expandable array at the first element matching `predicate`, which reads the element as `element`
+#[derive(PartialEq, Clone, Debug)]
+pub struct ArrayUntil {
+    pub element: Arc<Input>,
+    pub predicate: Expression,
+    // whether the matching element is kept in the array
+    pub inclusive: bool,
 }
 
 impl fmt::Display for LengthConstraint {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(until) = &self.until {
+            return write!(
+                f,
+                "{} until |{}| {}",
+                if until.inclusive { "..=" } else { ".." },
+                until.element.name,
+                until.predicate
+            );
+        }
+        if self.bytes {
+            write!(f, "bytes: ")?;
+        }
         if self.expandable {
             write!(f, "..")?;
         }
         if let Some(value) = self.value.as_ref() {
             value.fmt(f)?;
         }
+        for alternative in &self.terminator.alternatives {
+            write!(f, ", {}", alternative)?;
+        }
+        match (self.terminator.leave, self.terminator.eof) {
+            (true, true) => write!(f, "; leave, eof")?,
+            (true, false) => write!(f, "; leave")?,
+            (false, true) => write!(f, "; eof")?,
+            (false, false) => (),
+        }
         write!(f, "")
     }
 }
@@ -5,6 +5,19 @@ pub struct ContainerType {
     pub length: Option<Expression>,
     pub items: IndexMap<String, Arc<Field>>,
     pub is_enum: Cell<bool>,
+    /// For `match` containers, the discriminant and the first value of it selecting each variant
+    pub discriminant: Option<Expression>,
+    pub tags: IndexMap<String, Expression>,
+    /// For `set` containers, the layout of records, whose members are keyed by their value in `tags`
+    pub set: Option<SetLayout>,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct SetLayout {
+    pub tag: EndianScalarType,
+    pub length: EndianScalarType,
+    /// Whether records are encoded in the order they were decoded in, rather than declaration order
+    pub preserve_order: bool,
 }
 
 impl ContainerType {
@@ -17,4 +30,18 @@ impl ContainerType {
                 _ => vec![(name.clone(), field.clone())],
             })
     }
+
+    /// The items of `flatten_view`, with whether each is held as an `Option` for its own condition or that of an interior container it is in
+    pub fn flatten_view_optional(&self, conditional: bool) -> Vec<(String, Arc<Field>, bool)> {
+        self.items
+            .iter()
+            .flat_map(|(name, field)| {
+                let conditional = conditional || field.condition.borrow().is_some();
+                match &*field.type_.borrow() {
+                    Type::Container(x) => x.flatten_view_optional(conditional),
+                    _ => vec![(name.clone(), field.clone(), conditional)],
+                }
+            })
+            .collect()
+    }
 }
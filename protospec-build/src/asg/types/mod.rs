@@ -18,6 +18,9 @@ pub use foreign::*;
 mod type_ref;
 pub use type_ref::*;
 
+mod size;
+pub use size::*;
+
 #[derive(Clone, Debug)]
 pub enum Type {
     Container(Box<ContainerType>),
@@ -132,6 +135,14 @@ impl Type {
                 true
             }
             (Type::Scalar(s1), Type::Scalar(s2)) => s1.scalar.can_implicit_cast_to(&s2.scalar),
+            (Type::Foreign(f), to) => match f.obj.scalar_type() {
+                Some(scalar) => Type::Scalar(scalar.into()).can_coerce_to(to),
+                None => false,
+            },
+            (from, Type::Foreign(f)) => match f.obj.scalar_type() {
+                Some(scalar) => from.can_coerce_to(&Type::Scalar(scalar.into())),
+                None => false,
+            },
             (_, _) => false,
         }
     }
@@ -148,6 +159,14 @@ impl Type {
             (Type::F64, Type::Scalar(_)) => true,
             (Type::Scalar(_), Type::F32) => true,
             (Type::Scalar(_), Type::F64) => true,
+            (Type::Foreign(f), to) => match f.obj.scalar_type() {
+                Some(scalar) => Type::Scalar(scalar.into()).can_cast_to(to),
+                None => false,
+            },
+            (from, Type::Foreign(f)) => match f.obj.scalar_type() {
+                Some(scalar) => from.can_cast_to(&Type::Scalar(scalar.into())),
+                None => false,
+            },
             _ => false,
         }
     }
@@ -165,6 +184,18 @@ impl Type {
         }
     }
 
+    /// Whether decoding this type seeks, through its own pointer fields or those of referenced types
+    pub fn is_seekable(&self) -> bool {
+        match self {
+            Type::Array(interior) => interior.element.is_seekable(),
+            Type::Container(interior) => interior.items.values().any(|field| {
+                field.pointer.borrow().is_some() || field.type_.borrow().is_seekable()
+            }),
+            Type::Ref(call) => call.target.is_seekable.get(),
+            _ => false,
+        }
+    }
+
     pub(super) fn get_indirect_contained_fields(&self, target: &mut IndexSet<String>) {
         match self {
             Type::Array(interior) => {
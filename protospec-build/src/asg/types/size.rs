@@ -0,0 +1,179 @@
+use super::*;
+use crate::{eval_const_expression, ConstValue};
+
+/// How many bytes values of a type encode to
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum EncodedSize {
+    Fixed(u64),
+    /// At most this many bytes
+    Bounded(u64),
+    Unbounded,
+}
+
+impl EncodedSize {
+    pub fn max(self) -> Option<u64> {
+        match self {
+            EncodedSize::Fixed(size) | EncodedSize::Bounded(size) => Some(size),
+            EncodedSize::Unbounded => None,
+        }
+    }
+
+    /// The size of this followed by `other`
+    pub fn then(self, other: EncodedSize) -> EncodedSize {
+        match (self, other) {
+            (EncodedSize::Fixed(a), EncodedSize::Fixed(b)) => a
+                .checked_add(b)
+                .map_or(EncodedSize::Unbounded, EncodedSize::Fixed),
+            (a, b) => match (a.max(), b.max()) {
+                (Some(a), Some(b)) => a
+                    .checked_add(b)
+                    .map_or(EncodedSize::Unbounded, EncodedSize::Bounded),
+                _ => EncodedSize::Unbounded,
+            },
+        }
+    }
+
+    /// The size of `count` values of this size
+    pub fn repeat(self, count: EncodedSize) -> EncodedSize {
+        match (self, count) {
+            (EncodedSize::Fixed(a), EncodedSize::Fixed(b)) => a
+                .checked_mul(b)
+                .map_or(EncodedSize::Unbounded, EncodedSize::Fixed),
+            (a, b) => match (a.max(), b.max()) {
+                (Some(a), Some(b)) => a
+                    .checked_mul(b)
+                    .map_or(EncodedSize::Unbounded, EncodedSize::Bounded),
+                _ => EncodedSize::Unbounded,
+            },
+        }
+    }
+
+    /// The size of either this or `other`
+    pub fn or(self, other: EncodedSize) -> EncodedSize {
+        match (self, other) {
+            (EncodedSize::Fixed(a), EncodedSize::Fixed(b)) if a == b => EncodedSize::Fixed(a),
+            (a, b) => match (a.max(), b.max()) {
+                (Some(a), Some(b)) => EncodedSize::Bounded(a.max(b)),
+                _ => EncodedSize::Unbounded,
+            },
+        }
+    }
+
+    /// The size of a value which may not be present
+    pub fn optional(self) -> EncodedSize {
+        self.or(EncodedSize::Fixed(0))
+    }
+}
+
+impl Type {
+    pub fn encoded_size(&self) -> EncodedSize {
+        match self {
+            Type::Container(container) => container.encoded_size(),
+            Type::Enum(e) => EncodedSize::Fixed(e.rep.scalar.size()),
+            Type::Bitfield(b) => EncodedSize::Fixed(b.rep.scalar.size()),
+            Type::Scalar(s) => EncodedSize::Fixed(s.scalar.size()),
+            Type::Array(array) => array.encoded_size(),
+            Type::Foreign(f) => f.obj.encoded_size(),
+            Type::F32 => EncodedSize::Fixed(4),
+            Type::F64 => EncodedSize::Fixed(8),
+            Type::Bool => EncodedSize::Fixed(1),
+            Type::Ref(type_ref) => type_ref.target.encoded_size(),
+        }
+    }
+}
+
+impl ContainerType {
+    pub fn encoded_size(&self) -> EncodedSize {
+        // records of no member are kept and encoded
+        if self.set.is_some() {
+            return EncodedSize::Unbounded;
+        }
+        let mut size: Option<EncodedSize> = None;
+        for field in self.items.values() {
+            if field.is_tag.get() {
+                continue;
+            }
+            // pointer fields are laid out after the type
+            if field.pointer.borrow().is_some() {
+                return EncodedSize::Unbounded;
+            }
+            let field_size = field.encoded_size();
+            size = Some(match size {
+                _ if self.is_enum.get() => size.map_or(field_size, |size| size.or(field_size)),
+                _ if field.condition.borrow().is_some() => size
+                    .unwrap_or(EncodedSize::Fixed(0))
+                    .then(field_size.optional()),
+                None => field_size,
+                Some(size) => size.then(field_size),
+            });
+        }
+        let size = size.unwrap_or(EncodedSize::Fixed(0));
+        match (size, self.length.as_ref().map(length_bound)) {
+            (EncodedSize::Unbounded, Some(length)) => match length.max() {
+                Some(max) => EncodedSize::Bounded(max),
+                None => size,
+            },
+            _ => size,
+        }
+    }
+}
+
+impl ArrayType {
+    pub fn encoded_size(&self) -> EncodedSize {
+        if self.length.expandable || self.length.until.is_some() {
+            return EncodedSize::Unbounded;
+        }
+        let count = match &self.length.value {
+            Some(length) => length_bound(length),
+            None => return EncodedSize::Unbounded,
+        };
+        if self.length.bytes {
+            return match count.max() {
+                Some(max) => EncodedSize::Bounded(max),
+                None => EncodedSize::Unbounded,
+            };
+        }
+        self.element.encoded_size().repeat(count)
+    }
+}
+
+/// The values a length may take, bounded by the type of a field holding it
+fn length_bound(length: &Expression) -> EncodedSize {
+    if let Some(ConstValue::Int(value)) = eval_const_expression(length) {
+        return match value.cast_to(ScalarType::U64) {
+            ConstInt::U64(value) => EncodedSize::Fixed(value),
+            _ => EncodedSize::Unbounded,
+        };
+    }
+    let type_ = match length {
+        Expression::FieldRef(field) => field.type_.borrow().clone(),
+        Expression::InputRef(input) => input.type_.clone(),
+        // widened to the type of lengths
+        Expression::Cast(cast) => return length_bound(&cast.inner),
+        _ => return EncodedSize::Unbounded,
+    };
+    match type_ {
+        Type::Scalar(s) if !s.scalar.is_signed() && s.scalar.size() < 8 => {
+            EncodedSize::Bounded((1u64 << (s.scalar.size() * 8)) - 1)
+        }
+        _ => EncodedSize::Unbounded,
+    }
+}
+
+impl Field {
+    /// The size of the field when present, regardless of its condition
+    pub fn encoded_size(&self) -> EncodedSize {
+        if let Some(size) = self.encoded_size.get() {
+            return size;
+        }
+        // a type reached again while sizing it holds itself
+        self.encoded_size.set(Some(EncodedSize::Unbounded));
+        let size = if self.transforms.borrow().is_empty() {
+            self.type_.borrow().encoded_size()
+        } else {
+            EncodedSize::Unbounded
+        };
+        self.encoded_size.set(Some(size));
+        size
+    }
+}
@@ -0,0 +1,20 @@
+use super::*;
+
+/// An `@name` or `@name(arguments)` attribute of a type declaration or container item, read by the backends
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Attribute {
+    pub name: Ident,
+    pub arguments: Option<Vec<AttributeArgument>>,
+    pub span: Span,
+}
+impl_node!(Attribute);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum AttributeArgument {
+    /// `name` or `name(arguments)`
+    Attribute(Attribute),
+    /// `name = literal`
+    Value(Ident, Expression),
+    /// An integer, string or boolean literal
+    Literal(Expression),
+}
@@ -14,6 +14,7 @@ pub struct TypeDeclaration {
     pub name: Ident,
     pub arguments: Vec<TypeArgument>,
     pub value: Field,
+    pub attributes: Vec<Attribute>,
     pub span: Span,
 }
 impl_node!(TypeDeclaration);
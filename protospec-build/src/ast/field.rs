@@ -3,10 +3,23 @@ use super::*;
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Field {
     pub type_: Type,
-    pub flags: Vec<Ident>,
+    pub pointer: Option<Box<Expression>>,
+    pub flags: Vec<Flag>,
     pub calculated: Option<Box<Expression>>,
+    pub fixed: Option<Box<Expression>>,
+    pub constraint: Option<Box<Expression>>,
     pub condition: Option<Box<Expression>>,
     pub transforms: Vec<Transform>,
+    pub attributes: Vec<Attribute>,
     pub span: Span,
 }
 impl_node!(Field);
+
+/// A `+name` flag, with arguments if given as `+name(arguments)`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Flag {
+    pub name: String,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
+impl_node!(Flag);
@@ -31,5 +31,8 @@ pub use transform::*;
 mod field;
 pub use field::*;
 
+mod attribute;
+pub use attribute::*;
+
 mod expression;
 pub use expression::*;
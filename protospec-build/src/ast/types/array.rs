@@ -12,6 +12,28 @@ impl_node!(Array);
 pub struct LengthConstraint {
     pub expandable: bool,
     pub inner: Option<Box<Expression>>,
+    pub until: Option<ArrayUntil>,
+    // whether `inner` is a length in bytes rather than elements, as `[bytes: inner]`
+    pub bytes: bool,
+    pub terminator: TerminatorPolicy,
     pub span: Span,
 }
 impl_node!(LengthConstraint);
+
+/// `.. "a", "b"; leave, eof`, the alternatives and handling of the terminator of an array
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TerminatorPolicy {
+    pub alternatives: Vec<Expression>,
+    pub leave: bool,
+    pub eof: bool,
+}
+
+/// `.. until |element| predicate`, ending an array at the first element matching the predicate
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ArrayUntil {
+    pub inclusive: bool,
+    pub element: Ident,
+    pub predicate: Box<Expression>,
+    pub span: Span,
+}
+impl_node!(ArrayUntil);
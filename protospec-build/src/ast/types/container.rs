@@ -1,16 +1,41 @@
 use super::*;
 
+/// A pattern of a `match` arm
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Pattern {
+    Value(Box<Expression>),
+    Range {
+        low: Box<Expression>,
+        high: Box<Expression>,
+        inclusive: bool,
+    },
+    Wildcard(Span),
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ContainerItem {
     Field(Ident, Field),
     Pad(Expression),
+    Align(Expression),
+    /// A variant of a `match` container, selected by any of its patterns, or a member of a `set` container keyed by its tag value
+    Arm(Vec<Pattern>, Ident, Field),
+}
+
+/// The `set(tag, length)` header of a container of records, each a tag, the length of its value in bytes, and the value
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordLayout {
+    pub tag: Type,
+    pub length: Type,
+    pub span: Span,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Container {
     pub length: Option<Box<Expression>>,
+    pub discriminant: Option<Box<Expression>>,
+    pub set: Option<Box<RecordLayout>>,
     pub items: Vec<ContainerItem>,
-    pub flags: Vec<Ident>,
+    pub flags: Vec<Flag>,
     pub span: Span,
 }
 impl_node!(Container);
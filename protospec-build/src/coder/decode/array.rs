@@ -2,37 +2,63 @@ use super::*;
 
 impl Context {
     pub fn decode_array(&mut self, type_: &ArrayType, source: Target) -> usize {
-        let terminator = if type_.length.expandable && type_.length.value.is_some() {
-            let len = type_.length.value.as_ref().cloned().unwrap();
-            let r = self.alloc_register();
-            self.instructions
-                .push(Instruction::Eval(r, len, self.field_register_map.clone()));
-            Some(r)
-        } else {
-            None
+        if let Some(until) = &type_.length.until {
+            return self.decode_array_until(type_, until, source);
+        }
+        if type_.length.bytes {
+            return self.decode_array_bytes(type_, source);
+        }
+
+        if type_.length.expandable && type_.length.value.is_some() {
+            return self.decode_array_terminated(type_, source);
+        }
+        if let Some(len) = type_.fixed_length().filter(|_| self.options.fixed_arrays) {
+            return self.decode_array_fixed(type_, len, source);
+        }
+        self.decode_array_counted(type_, source)
+    }
+
+    fn decode_array_fixed(&mut self, type_: &ArrayType, len: usize, source: Target) -> usize {
+        let output = self.alloc_register();
+        let primitive = match &*type_.element.resolved() {
+            Type::Scalar(x) => Some(PrimitiveType::Scalar(*x)),
+            Type::F32 => Some(PrimitiveType::F32),
+            Type::F64 => Some(PrimitiveType::F64),
+            _ => None,
         };
+        if let Some(primitive) = primitive {
+            self.instructions.push(Instruction::DecodeFixedArray(
+                source, output, primitive, len,
+            ));
+            return output;
+        }
+        let elements = self.decode_array_counted(type_, source);
+        self.instructions
+            .push(Instruction::IntoFixedArray(output, elements, len));
+        output
+    }
 
+    fn decode_array_counted(&mut self, type_: &ArrayType, source: Target) -> usize {
         let len = if type_.length.expandable {
             None
         } else {
             let len = type_.length.value.as_ref().cloned().unwrap();
             let r = self.alloc_register();
-            self.instructions
-                .push(Instruction::Eval(r, len, self.field_register_map.clone()));
+            self.push_eval(r, len, self.field_register_map.clone());
             Some(r)
         };
 
         let output = self.alloc_register();
-        if terminator.is_none() {
-            let type_ = type_.element.resolved();
-            match &*type_ {
+        {
+            let element = type_.element.resolved();
+            match &*element {
                 // todo: const-length type optimizations for container/array/foreign
                 Type::Container(_) | Type::Array(_) | Type::Foreign(_) | Type::Ref(_) => (),
                 Type::Enum(x) => {
                     self.instructions.push(Instruction::DecodeReprArray(
                         source,
                         output,
-                        x.name.clone(),
+                        repr_type_name(&type_.element, self.options.rust_case),
                         PrimitiveType::Scalar(x.rep.clone()),
                         len,
                     ));
@@ -42,7 +68,7 @@ impl Context {
                     self.instructions.push(Instruction::DecodeReprArray(
                         source,
                         output,
-                        x.name.clone(),
+                        repr_type_name(&type_.element, self.options.rust_case),
                         PrimitiveType::Scalar(x.rep.clone()),
                         len,
                     ));
@@ -93,7 +119,115 @@ impl Context {
             .push(Instruction::LoopOutput(output, item));
         let drained = self.instructions.drain(current_pos..).collect();
         self.instructions
-            .push(Instruction::Loop(source, len, terminator, output, drained));
+            .push(Instruction::Loop(source, len, output, drained));
+        output
+    }
+
+    fn decode_array_until(
+        &mut self,
+        type_: &ArrayType,
+        until: &ArrayUntil,
+        source: Target,
+    ) -> usize {
+        let output = self.alloc_register();
+        let current_pos = self.instructions.len();
+        let item = self.decode_type(source, &type_.element);
+        self.instructions.push(Instruction::BindElement(
+            until.element.name.clone(),
+            item,
+            type_.element.copyable(),
+        ));
+        let done = self.alloc_register();
+        self.push_eval(
+            done,
+            until.predicate.clone(),
+            self.field_register_map.clone(),
+        );
+        let drained = self.instructions.drain(current_pos..).collect();
+        self.instructions.push(Instruction::LoopUntil(
+            output,
+            item,
+            done,
+            until.inclusive,
+            drained,
+        ));
+        output
+    }
+
+    fn decode_array_bytes(&mut self, type_: &ArrayType, source: Target) -> usize {
+        let len = type_.length.value.as_ref().cloned().unwrap();
+        let len_register = self.alloc_register();
+        self.push_eval(len_register, len, self.field_register_map.clone());
+        let stream = self.alloc_register();
+        self.instructions
+            .push(Instruction::Constrict(source, stream, len_register));
+
+        let output = self.alloc_register();
+        let current_pos = self.instructions.len();
+        let item = self.decode_type(Target::Stream(stream), &type_.element);
+        self.instructions
+            .push(Instruction::LoopOutput(output, item));
+        let drained = self.instructions.drain(current_pos..).collect();
+        self.instructions
+            .push(Instruction::LoopExhaust(stream, output, drained));
         output
     }
+
+    fn decode_array_terminated(&mut self, type_: &ArrayType, source: Target) -> usize {
+        let mut terminators = vec![];
+        for terminator in type_
+            .length
+            .value
+            .iter()
+            .chain(type_.length.terminator.alternatives.iter())
+        {
+            let r = self.alloc_register();
+            self.push_eval(r, terminator.clone(), self.field_register_map.clone());
+            terminators.push(r);
+        }
+        let (stream, owned) = match (source, self.lookahead) {
+            (Target::Stream(source), Some(lookahead)) if source == lookahead => (lookahead, false),
+            _ => {
+                let stream = self.alloc_register();
+                self.instructions
+                    .push(Instruction::Lookahead(source, stream));
+                (stream, true)
+            }
+        };
+
+        let output = self.alloc_register();
+        let current_pos = self.instructions.len();
+        let item = self.decode_type(Target::Stream(stream), &type_.element);
+        self.instructions
+            .push(Instruction::LoopOutput(output, item));
+        let drained = self.instructions.drain(current_pos..).collect();
+        self.instructions.push(Instruction::LoopTerminated(
+            stream,
+            terminators,
+            type_.length.terminator.leave,
+            type_.length.terminator.eof,
+            output,
+            drained,
+        ));
+        if owned {
+            self.instructions
+                .push(Instruction::EndLookahead(stream, None));
+        }
+        output
+    }
+}
+
+/// The Rust name of the enum or bitfield an array holds, which is behind references to its declaration
+fn repr_type_name(element: &Type, rust_case: bool) -> String {
+    match element {
+        Type::Ref(type_ref) => match &*type_ref.target.type_.borrow() {
+            Type::Enum(_) | Type::Bitfield(_) => global_name(&type_ref.target, rust_case),
+            target => repr_type_name(target, rust_case),
+        },
+        Type::Enum(x) if rust_case => to_upper_camel(&x.name),
+        Type::Bitfield(x) if rust_case => to_upper_camel(&x.name),
+        Type::Enum(x) => x.name.clone(),
+        Type::Bitfield(x) => x.name.clone(),
+        _ => unreachable!("array of a non-repr type"),
+    }
 }
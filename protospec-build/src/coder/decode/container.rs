@@ -7,26 +7,86 @@ impl Context {
         type_: &ContainerType,
         source: Target,
     ) -> Vec<usize> {
-        let buf_target = if let Some(length) = &type_.length {
+        let (buf_target, len_register) = if let Some(length) = &type_.length {
             //todo: use limited stream
             let len_register = self.alloc_register();
-            self.instructions.push(Instruction::Eval(
+            self.push_eval(
                 len_register,
                 length.clone(),
                 self.field_register_map.clone(),
-            ));
+            );
             let buf = self.alloc_register();
             self.instructions
                 .push(Instruction::Constrict(source, buf, len_register));
-            Target::Stream(buf)
+            (Target::Stream(buf), Some(len_register))
+        } else {
+            (source, None)
+        };
+        let tracks_position = type_.tracks_stream_position(field);
+        let buf_target = if tracks_position {
+            let counter = self.alloc_register();
+            self.instructions
+                .push(Instruction::CountStream(buf_target, counter));
+            self.position_streams.push((counter, len_register));
+            Target::Stream(counter)
+        } else {
+            buf_target
+        };
+        // terminated arrays share the container's lookahead, so bytes they read ahead reach the following fields
+        let reads_ahead = (tracks_position && type_.uses_peek())
+            || type_.items.values().any(|child| {
+                matches!(&*child.type_.borrow(), Type::Array(array) if array.length.expandable && array.length.value.is_some())
+            });
+        let outer_lookahead = self.lookahead;
+        let hand_back = match (buf_target, outer_lookahead) {
+            (Target::Stream(source), Some(outer)) if source == outer => Some(outer),
+            _ => None,
+        };
+        let buf_target = if reads_ahead {
+            let lookahead = self.alloc_register();
+            self.instructions
+                .push(Instruction::Lookahead(buf_target, lookahead));
+            self.lookahead = Some(lookahead);
+            Target::Stream(lookahead)
         } else {
-            source
+            buf_target
         };
-        if type_.is_enum.get() {
-            self.decode_enum_container(field, type_, buf_target)
+        let end_lookahead = self
+            .lookahead
+            .filter(|_| reads_ahead)
+            .map(|x| (x, hand_back));
+        let decoded = if type_.is_enum.get() {
+            self.decode_enum_container(field, type_, buf_target, end_lookahead)
+        } else if let Some(layout) = &type_.set {
+            self.decode_set_container(field, type_, layout, buf_target)
         } else {
             self.decode_struct_container(field, type_, buf_target)
+        };
+        if reads_ahead {
+            // an enum container without a fallback variant has returned from each of them, ending its lookahead on the way
+            if !(type_.is_enum.get() && decoded.is_empty()) {
+                self.instructions.push(Instruction::EndLookahead(
+                    self.lookahead.unwrap(),
+                    hand_back,
+                ));
+            }
+            self.lookahead = outer_lookahead;
         }
+        if tracks_position {
+            self.position_streams.pop();
+        }
+        decoded
+    }
+
+    /// Converts the decoded value of a struct field into its mapped type, if it is held as one
+    fn map_field(&mut self, field: &Field, decoded: usize, optional: bool) -> usize {
+        if self.options.mapped_type(field).is_none() {
+            return decoded;
+        }
+        let mapped = self.alloc_register();
+        self.instructions
+            .push(Instruction::MapValue(mapped, decoded, optional));
+        mapped
     }
 
     fn decode_struct_container(
@@ -45,71 +105,189 @@ impl Context {
                 }
             }
         }
+        self.verify_container_checksums(type_);
         if !field.toplevel {
             return decoded_fields;
         }
         let emitted = self.alloc_register();
         let mut items = vec![];
-        for (name, child) in type_.flatten_view() {
-            if child.is_pad.get() || matches!(&*child.type_.borrow(), Type::Container(_)) {
+        for (name, child, optional) in type_.flatten_view_optional(false) {
+            if child.is_pad.get()
+                || child.is_fixed.get()
+                || matches!(&*child.type_.borrow(), Type::Container(_))
+            {
                 continue;
             }
+            let decoded = *self
+                .field_register_map
+                .get(&name)
+                .expect("missing field in field_register_map");
             items.push((
-                name.clone(),
-                *self
-                    .field_register_map
-                    .get(&name)
-                    .expect("missing field in field_register_map"),
+                field_name(&child, self.options.rust_case),
+                self.map_field(&child, decoded, optional),
             ));
         }
         self.instructions.push(Instruction::Construct(
             emitted,
             Constructable::Struct {
-                name: field.name.clone(),
+                name: global_name(field, self.options.rust_case),
+                items,
+            },
+        ));
+        vec![emitted]
+    }
+
+    fn decode_set_container(
+        &mut self,
+        field: &Arc<Field>,
+        type_: &ContainerType,
+        layout: &SetLayout,
+        buf_target: Target,
+    ) -> Vec<usize> {
+        assert!(field.toplevel);
+        let mut tag_values = vec![];
+        for name in type_.items.keys() {
+            let tag_value = self.alloc_register();
+            self.push_eval(
+                tag_value,
+                type_
+                    .tags
+                    .get(name)
+                    .expect("missing set member tag")
+                    .clone(),
+                self.field_register_map.clone(),
+            );
+            tag_values.push(tag_value);
+        }
+
+        let tag = self.alloc_register();
+        let length = self.alloc_register();
+        let record = self.alloc_register();
+        let header = vec![
+            Instruction::DecodePrimitive(buf_target, tag, PrimitiveType::Scalar(layout.tag)),
+            Instruction::DecodePrimitive(buf_target, length, PrimitiveType::Scalar(layout.length)),
+            Instruction::Constrict(buf_target, record, length),
+        ];
+
+        let mut members = vec![];
+        let mut items = vec![];
+        for ((name, child), tag_value) in type_.items.iter().zip(tag_values) {
+            let start = self.instructions.len();
+            let decoded = self.decode_field_unconditional(Target::Stream(record), child);
+            if let Some(constraint) = child.constraint.borrow().as_ref() {
+                self.verify_field_constraint(child, constraint, &decoded[..]);
+            }
+            let inner = self.instructions.drain(start..).collect();
+            let decoded = *decoded.first().expect("set member did not emit a value");
+            let output = self.alloc_register();
+            members.push((tag_value, output, decoded, name.clone(), inner));
+            items.push((name.clone(), output));
+        }
+
+        let unknown = self.alloc_register();
+        items.push(("unknown".to_string(), unknown));
+        let order = if layout.preserve_order {
+            let order = self.alloc_register();
+            items.push(("order".to_string(), order));
+            Some(order)
+        } else {
+            None
+        };
+        self.instructions.push(Instruction::DecodeRecords(
+            buf_target, header, tag, record, members, unknown, order,
+        ));
+        let items = items
+            .into_iter()
+            .map(|(name, output)| match type_.items.get(&name) {
+                Some(child) => {
+                    let output = self.map_field(child, output, true);
+                    (field_name(child, self.options.rust_case), output)
+                }
+                None => (name, output),
+            })
+            .collect();
+
+        let emitted = self.alloc_register();
+        self.instructions.push(Instruction::Construct(
+            emitted,
+            Constructable::Struct {
+                name: global_name(field, self.options.rust_case),
                 items,
             },
         ));
         vec![emitted]
     }
 
+    fn verify_container_checksums(&mut self, type_: &ContainerType) {
+        for (name, child) in type_.items.iter() {
+            let calculated = child.calculated.borrow();
+            let calculated = match &*calculated {
+                Some(calculated) if calculated.has_verified_call() => calculated,
+                _ => continue,
+            };
+            let decoded = *self
+                .field_register_map
+                .get(name)
+                .expect("missing field in field_register_map");
+            let expected = self.alloc_register();
+            self.push_eval(
+                expected,
+                calculated.clone(),
+                self.field_register_map.clone(),
+            );
+            self.instructions.push(Instruction::VerifyChecksum(
+                decoded,
+                expected,
+                format!("checksum mismatch for field {}", name),
+                child.condition.borrow().is_some(),
+            ));
+        }
+    }
+
     fn decode_enum_container(
         &mut self,
         field: &Arc<Field>,
         type_: &ContainerType,
         buf_target: Target,
+        end_lookahead: Option<(usize, Option<usize>)>,
     ) -> Vec<usize> {
         assert!(type_.is_enum.get());
-        for (name, child) in type_.items.iter() {
+        for child in type_.items.values() {
             let condition = self.decode_field_condition(child);
             let start = self.instructions.len();
             let decoded = self.decode_field_unconditional(buf_target, child);
+            if let Some(constraint) = child.constraint.borrow().as_ref() {
+                self.verify_field_constraint(child, constraint, &decoded[..]);
+            }
             let target = self.alloc_register();
 
             let subtype = child.type_.borrow();
             match &*subtype {
                 Type::Container(c) => {
                     let mut values = vec![];
-                    for (subname, subchild) in c.flatten_view() {
+                    for (subname, subchild, optional) in c.flatten_view_optional(false) {
                         if subchild.is_pad.get()
+                            || subchild.is_fixed.get()
                             || matches!(&*subchild.type_.borrow(), Type::Container(_))
                         {
                             continue;
                         }
 
+                        let decoded = *self
+                            .field_register_map
+                            .get(&subname)
+                            .expect("missing field in field_register_map");
                         values.push((
-                            subname.clone(),
-                            *self
-                                .field_register_map
-                                .get(&subname)
-                                .expect("missing field in field_register_map"),
+                            field_name(&subchild, self.options.rust_case),
+                            self.map_field(&subchild, decoded, optional),
                         ));
                     }
 
                     self.instructions.push(Instruction::Construct(
                         target,
                         Constructable::TaggedEnumStruct {
-                            name: field.name.clone(),
-                            discriminant: name.clone(),
+                            name: global_name(field, self.options.rust_case),
+                            discriminant: global_name(child, self.options.rust_case),
                             values,
                         },
                     ));
@@ -121,8 +299,8 @@ impl Context {
                     self.instructions.push(Instruction::Construct(
                         target,
                         Constructable::TaggedEnum {
-                            name: field.name.clone(),
-                            discriminant: name.clone(),
+                            name: global_name(field, self.options.rust_case),
+                            discriminant: global_name(child, self.options.rust_case),
                             values: vec![*decoded],
                         },
                     ));
@@ -130,6 +308,11 @@ impl Context {
             }
 
             if let Some(condition) = condition {
+                // the variant returns early, so the lookahead the container reads through ends with it
+                if let Some((lookahead, hand_back)) = end_lookahead {
+                    self.instructions
+                        .push(Instruction::EndLookahead(lookahead, hand_back));
+                }
                 self.instructions.push(Instruction::Return(target));
                 let drained = self.instructions.drain(start..).collect();
                 self.instructions
@@ -145,3 +328,37 @@ impl Context {
         vec![]
     }
 }
+
+impl Expression {
+    fn has_verified_call(&self) -> bool {
+        match self {
+            Expression::Binary(expr) => {
+                expr.left.has_verified_call() || expr.right.has_verified_call()
+            }
+            Expression::Unary(expr) => expr.inner.has_verified_call(),
+            Expression::Cast(expr) => expr.inner.has_verified_call(),
+            Expression::ArrayIndex(expr) => {
+                expr.array.has_verified_call() || expr.index.has_verified_call()
+            }
+            Expression::Ternary(expr) => {
+                expr.condition.has_verified_call()
+                    || expr.if_true.has_verified_call()
+                    || expr.if_false.has_verified_call()
+            }
+            Expression::EnumAccess(_) => false,
+            Expression::Int(_) => false,
+            Expression::ConstRef(_) => false,
+            Expression::InputRef(_) => false,
+            Expression::FieldRef(_) => false,
+            Expression::Str(_) => false,
+            Expression::Bool(_) => false,
+            Expression::Tag(_) => false,
+            Expression::SizeOf(_) => false,
+            Expression::Call(expr) => {
+                expr.function.inner.verify_on_decode()
+                    || expr.arguments.iter().any(|x| x.has_verified_call())
+            }
+            Expression::Member(expr) => expr.target.has_verified_call(),
+        }
+    }
+}
@@ -4,6 +4,7 @@ impl Context {
     pub fn decode_field_top(&mut self, field: &Arc<Field>) {
         assert!(field.toplevel);
         self.name = field.name.clone();
+        self.type_name = global_name(field, self.options.rust_case);
         let mut value = self.decode_field(Target::Direct, field);
         if value.len() > 1 {
             panic!("leaked poly-value to top level field");
@@ -19,7 +20,7 @@ impl Context {
                     self.instructions.push(Instruction::Construct(
                         extra_value,
                         Constructable::TaggedTuple {
-                            name: field.name.clone(),
+                            name: global_name(field, self.options.rust_case),
                             items: vec![*old_value],
                         },
                     ));
@@ -35,11 +36,7 @@ impl Context {
     pub fn decode_field_condition(&mut self, field: &Arc<Field>) -> Option<usize> {
         if let Some(condition) = field.condition.borrow().as_ref() {
             let value = self.alloc_register();
-            self.instructions.push(Instruction::Eval(
-                value,
-                condition.clone(),
-                self.field_register_map.clone(),
-            ));
+            self.push_eval(value, condition.clone(), self.field_register_map.clone());
             Some(value)
         } else {
             None
@@ -48,6 +45,12 @@ impl Context {
 
     /// return of `None` means interior container
     pub fn decode_field(&mut self, source: Target, field: &Arc<Field>) -> Vec<usize> {
+        if field.is_tag.get() {
+            let value = self.alloc_register();
+            let discriminant = field.calculated.borrow().clone().unwrap();
+            self.push_eval(value, discriminant, self.field_register_map.clone());
+            return vec![value];
+        }
         let field_condition = self.decode_field_condition(field);
         let start = self.instructions.len();
 
@@ -56,6 +59,12 @@ impl Context {
         if field.is_pad.get() {
             return emitted;
         }
+        if field.is_fixed.get() {
+            self.verify_fixed_field(field, &emitted[..]);
+        }
+        if let Some(constraint) = field.constraint.borrow().as_ref() {
+            self.verify_field_constraint(field, constraint, &emitted[..]);
+        }
         if let Some(field_condition) = field_condition {
             let drained = self.instructions.drain(start..).collect();
             if emitted.is_empty() {
@@ -71,6 +80,56 @@ impl Context {
         emitted
     }
 
+    fn verify_fixed_field(&mut self, field: &Arc<Field>, emitted: &[usize]) {
+        let decoded = *emitted.first().expect("fixed field did not emit a value");
+        let fixed = field.calculated.borrow();
+        let fixed = fixed.as_ref().expect("fixed field without value");
+        let expected = self.alloc_register();
+        self.push_eval(expected, fixed.clone(), self.field_register_map.clone());
+        let show_values = match &*field.type_.borrow() {
+            Type::Array(array) => matches!(&*array.element, Type::Scalar(_)),
+            Type::Scalar(_) | Type::Bool => true,
+            _ => false,
+        };
+        self.instructions.push(Instruction::VerifyFixed(
+            decoded,
+            expected,
+            format!("invalid value for fixed field {}", field.name),
+            show_values,
+            matches!(&*field.type_.borrow(), Type::Array(_)),
+        ));
+    }
+
+    pub(super) fn verify_field_constraint(
+        &mut self,
+        field: &Arc<Field>,
+        constraint: &Expression,
+        emitted: &[usize],
+    ) {
+        let decoded = *emitted
+            .first()
+            .expect("constrained field did not emit a value");
+        let mut field_register_map = self.field_register_map.clone();
+        field_register_map.insert(field.name.clone(), decoded);
+        let violated = self.alloc_register();
+        self.push_eval(
+            violated,
+            Expression::Unary(UnaryExpression {
+                op: crate::UnaryOp::Not,
+                inner: Box::new(constraint.clone()),
+                span: field.span,
+            }),
+            field_register_map,
+        );
+        self.instructions.push(Instruction::ConditionalPredicate(
+            violated,
+            vec![Instruction::Error(format!(
+                "constraint violated for field {}",
+                field.name
+            ))],
+        ));
+    }
+
     pub fn decode_field_unconditional(
         &mut self,
         mut source: Target,
@@ -81,11 +140,7 @@ impl Context {
         for transform in field.transforms.borrow().iter().rev() {
             let condition = if let Some(condition) = &transform.condition {
                 let value = self.alloc_register();
-                self.instructions.push(Instruction::Eval(
-                    value,
-                    condition.clone(),
-                    self.field_register_map.clone(),
-                ));
+                self.push_eval(value, condition.clone(), self.field_register_map.clone());
                 Some(value)
             } else {
                 None
@@ -95,11 +150,7 @@ impl Context {
             let mut args = vec![];
             for arg in transform.arguments.iter() {
                 let r = self.alloc_register();
-                self.instructions.push(Instruction::Eval(
-                    r,
-                    arg.clone(),
-                    self.field_register_map.clone(),
-                ));
+                self.push_eval(r, arg.clone(), self.field_register_map.clone());
                 args.push(r);
             }
             let new_stream = self.alloc_register();
@@ -126,9 +177,34 @@ impl Context {
             source = Target::Stream(new_stream);
         }
 
+        let resume = if let Some(pointer) = field.pointer.borrow().as_ref() {
+            let offset = self.alloc_register();
+            self.push_eval(offset, pointer.clone(), self.field_register_map.clone());
+            let resume = self.alloc_register();
+            self.instructions
+                .push(Instruction::Seek(source, offset, resume));
+            Some(resume)
+        } else {
+            None
+        };
+
         //todo: assert condition matching actual presence
         let emitted = self.decode_complex_type(source, field);
 
+        if let Some(resume) = resume {
+            self.instructions.push(Instruction::Resume(source, resume));
+        }
+
+        if field.is_boxed.get() {
+            return emitted
+                .into_iter()
+                .map(|value| {
+                    let boxed = self.alloc_register();
+                    self.instructions.push(Instruction::BoxValue(boxed, value));
+                    boxed
+                })
+                .collect();
+        }
         emitted
     }
 }
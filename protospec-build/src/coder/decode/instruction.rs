@@ -29,6 +29,10 @@ pub enum Instruction {
     Construct(usize, Constructable),
     // source, new_stream, len constraint
     Constrict(Target, usize, usize),
+    // source, new position counting stream
+    CountStream(Target, usize),
+    // counting stream, len constraint if any, intrinsics to bind
+    BindPosition(usize, Option<usize>, Vec<StreamIntrinsic>),
     WrapStream(Target, usize, Arc<Transform>, Vec<usize>), // stream, new stream, transformer, arguments
     ConditionalWrapStream(
         usize,
@@ -40,26 +44,64 @@ pub enum Instruction {
     ), // condition, prelude, stream, new stream, transformer, arguments
 
     DecodeForeign(Target, usize, Arc<ForeignType>, Vec<usize>),
-    DecodeRef(Target, usize, String, Vec<usize>),
+    // source, dest, type name, arguments, whether the type decodes from a seekable stream
+    DecodeRef(Target, usize, String, Vec<usize>, bool),
     DecodeRepr(String, PrimitiveType, usize, Target),
     DecodePrimitive(Target, usize, PrimitiveType),
     DecodePrimitiveArray(Target, usize, PrimitiveType, Option<usize>),
     DecodeReprArray(Target, usize, String, PrimitiveType, Option<usize>),
+    // source, dest, element type, constant number of elements
+    DecodeFixedArray(Target, usize, PrimitiveType, usize),
+    // dest, decoded elements, constant number of elements
+    IntoFixedArray(usize, usize, usize),
     // target, register of length
     Skip(Target, usize),
+    // stream, register of absolute offset, dest for the position to resume from
+    Seek(Target, usize, usize),
+    // stream, position to resume from
+    Resume(Target, usize),
 
-    // register representing: internal stream, end index, terminator, output handle, inner
-    Loop(
+    // register representing: internal stream, end index, output handle, inner
+    Loop(Target, Option<usize>, usize, Vec<Instruction>),
+    // stream, new lookahead stream
+    Lookahead(Target, usize),
+    // lookahead stream, number of bytes peeked
+    BindPeek(usize, u64),
+    // lookahead stream, lookahead stream it reads from to hand bytes read ahead back to, if any
+    EndLookahead(usize, Option<usize>),
+    // lookahead stream, terminators, whether the terminator is left in the stream, whether eof ends the array, output handle, inner
+    LoopTerminated(usize, Vec<usize>, bool, bool, usize, Vec<Instruction>),
+    LoopOutput(usize, usize), // output handle, item
+    // length constrained stream, output handle, inner
+    LoopExhaust(usize, usize, Vec<Instruction>),
+    // output handle, item, register of predicate result, whether the matching item is kept, inner
+    LoopUntil(usize, usize, usize, bool, Vec<Instruction>),
+    // stream, instructions decoding a record's tag and length and constricting the record stream, tag, record stream,
+    // members as (tag value, output handle, decoded value, member name, inner), unknown records output handle, order output handle
+    DecodeRecords(
         Target,
-        Option<usize>,
-        Option<usize>,
-        usize,
         Vec<Instruction>,
+        usize,
+        usize,
+        Vec<(usize, usize, usize, String, Vec<Instruction>)>,
+        usize,
+        Option<usize>,
     ),
-    LoopOutput(usize, usize), // output handle, item
+    // dest, value moved into a `Box`
+    BoxValue(usize, usize),
+    // dest, value converted into the mapped type of a field, whether the value is an `Option`
+    MapValue(usize, usize, bool),
+    // dest, decoded elements, Rust name of the key field of the elements, handling of duplicate keys
+    CollectMap(usize, usize, String, DuplicateKeys),
+    // element name, item, whether the item is bound by value
+    BindElement(String, usize, bool),
     Conditional(Vec<usize>, Vec<usize>, usize, Vec<Instruction>), // target, interior_register, condition, if_true
     ConditionalPredicate(usize, Vec<Instruction>), // target, interior_register, condition, if_true
     /// returns from decoder early
     Return(usize),
     Error(String),
+    /// decoded value, recomputed value, error message, whether the decoded value is an `Option` only verified if present
+    VerifyChecksum(usize, usize, String, bool),
+    /// decoded value, fixed value, error message, whether to include both values in the message, whether the values are compared as slices
+    VerifyFixed(usize, usize, String, bool, bool),
 }
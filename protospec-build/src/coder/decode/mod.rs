@@ -1,5 +1,7 @@
 use super::*;
 use crate::asg::*;
+use crate::compiler::{field_name, global_name, to_upper_camel, CompileOptions};
+use crate::StreamIntrinsic;
 use std::{collections::HashMap, sync::Arc};
 
 mod instruction;
@@ -20,6 +22,14 @@ pub struct Context {
     pub field_register_map: HashMap<String, usize>,
     pub instructions: Vec<Instruction>,
     pub name: String,
+    // Rust name of the type decoded
+    pub type_name: String,
+    // stack of position tracking streams: counter register, length register if constrained
+    pub position_streams: Vec<(usize, Option<usize>)>,
+    // lookahead stream of the container being decoded, holding terminators left in the stream by its arrays
+    pub lookahead: Option<usize>,
+    // options of the generated code, which decide the names and representation of types
+    pub options: CompileOptions,
 }
 
 impl Context {
@@ -28,15 +38,58 @@ impl Context {
         self.register_count += 1;
         x
     }
+
+    /// Pushes an `Eval`, binding any stream position intrinsics the expression reads first
+    fn push_eval(
+        &mut self,
+        register: usize,
+        expr: Expression,
+        field_register_map: HashMap<String, usize>,
+    ) {
+        let mut intrinsics = expr.stream_intrinsics();
+        if let Some(index) = intrinsics.iter().position(|x| *x == StreamIntrinsic::Peek) {
+            intrinsics.remove(index);
+            let lookahead = self
+                .lookahead
+                .expect("peek() is only used in conditions within a container");
+            let len = expr
+                .peek_len()
+                .expect("peek() offsets are integer literals");
+            self.instructions
+                .push(Instruction::BindPeek(lookahead, len));
+        }
+        if !intrinsics.is_empty() {
+            let (counter, length) = *self.position_streams.last().unwrap_or_else(|| {
+                panic!(
+                    "stream position used outside of a container in {}",
+                    self.name
+                )
+            });
+            if length.is_none() && intrinsics.contains(&StreamIntrinsic::Remaining) {
+                panic!(
+                    "remaining() used outside of a length constrained container in {}",
+                    self.name
+                );
+            }
+            self.instructions
+                .push(Instruction::BindPosition(counter, length, intrinsics));
+        }
+        self.instructions
+            .push(Instruction::Eval(register, expr, field_register_map));
+    }
 }
 
 impl Context {
-    pub fn new() -> Context {
+    pub fn new(options: &CompileOptions) -> Context {
         Context {
             name: String::new(),
+            type_name: String::new(),
             instructions: vec![],
             field_register_map: HashMap::new(),
             register_count: 0,
+            position_streams: vec![],
+            lookahead: None,
+            options: options.clone(),
         }
     }
 
@@ -49,11 +102,7 @@ impl Context {
             };
             let len = array_type.length.value.as_ref().cloned().unwrap();
             let length_register = self.alloc_register();
-            self.instructions.push(Instruction::Eval(
-                length_register,
-                len,
-                self.field_register_map.clone(),
-            ));
+            self.push_eval(length_register, len, self.field_register_map.clone());
             self.instructions
                 .push(Instruction::Skip(source, length_register));
             return vec![];
@@ -70,10 +119,25 @@ impl Context {
             Type::Container(_) => {
                 unimplemented!("invalid container in non-complex context");
             }
-            Type::Array(type_) => self.decode_array(&**type_, source),
+            Type::Array(type_) => {
+                let elements = self.decode_array(&**type_, source);
+                match &type_.map {
+                    Some(map) => {
+                        let key = type_.map_key_field().expect("map key is checked");
+                        self.instructions.push(Instruction::CollectMap(
+                            output,
+                            elements,
+                            field_name(&key, self.options.rust_case),
+                            map.duplicates,
+                        ));
+                        output
+                    }
+                    None => elements,
+                }
+            }
             Type::Enum(e) => {
                 self.instructions.push(Instruction::DecodeRepr(
-                    e.name.clone(),
+                    self.type_name.clone(),
                     PrimitiveType::Scalar(e.rep.clone()),
                     output,
                     source,
@@ -82,7 +146,7 @@ impl Context {
             }
             Type::Bitfield(e) => {
                 self.instructions.push(Instruction::DecodeRepr(
-                    e.name.clone(),
+                    self.type_name.clone(),
                     PrimitiveType::Scalar(e.rep.clone()),
                     output,
                     source,
@@ -134,11 +198,7 @@ impl Context {
                 let mut args = vec![];
                 for arg in r.arguments.iter() {
                     let r = self.alloc_register();
-                    self.instructions.push(Instruction::Eval(
-                        r,
-                        arg.clone(),
-                        self.field_register_map.clone(),
-                    ));
+                    self.push_eval(r, arg.clone(), self.field_register_map.clone());
                     args.push(r);
                 }
                 if let Type::Foreign(f) = &*r.target.type_.borrow() {
@@ -152,8 +212,9 @@ impl Context {
                     self.instructions.push(Instruction::DecodeRef(
                         source,
                         output,
-                        r.target.name.clone(),
+                        global_name(&r.target, self.options.rust_case),
                         args,
+                        r.target.is_seekable.get(),
                     ));
                 }
                 output
@@ -2,10 +2,26 @@ use super::*;
 
 impl Context {
     pub fn encode_array(&mut self, type_: &ArrayType, target: Target, source: usize) {
-        let terminator = if type_.length.expandable && type_.length.value.is_some() {
+        let source = if type_.map.is_some() {
+            let values = self.alloc_register();
+            self.instructions
+                .push(Instruction::MapValues(values, source));
+            values
+        } else {
+            source
+        };
+        if let Some(until) = &type_.length.until {
+            return self.encode_array_until(type_, until, target, source);
+        }
+
+        // the first terminator is written, unless it is left for the next field to encode
+        let terminator = if type_.length.expandable
+            && type_.length.value.is_some()
+            && !type_.length.terminator.leave
+        {
             let len = type_.length.value.as_ref().cloned().unwrap();
             let r = self.alloc_register();
-            self.instructions.push(Instruction::Eval(r, len));
+            self.push_eval(r, len);
             Some(r)
         } else {
             None
@@ -27,10 +43,13 @@ impl Context {
         //     None
         // };
 
-        let len = if !type_.length.expandable {
+        let reads_remaining = type_.reads_remaining();
+        // Rust arrays hold their constant length by type
+        let fixed = self.options.fixed_arrays && type_.fixed_length().is_some();
+        let len = if !type_.length.expandable && !type_.length.bytes && !reads_remaining && !fixed {
             let len = type_.length.value.as_ref().cloned().unwrap();
             let r = self.alloc_register();
-            self.instructions.push(Instruction::Eval(r, len));
+            self.push_eval(r, len);
             Some(r)
         } else {
             None
@@ -49,7 +68,7 @@ impl Context {
                         len,
                     ));
                     return;
-                },
+                }
                 Type::Bitfield(e) => {
                     self.instructions.push(Instruction::EncodeReprArray(
                         target,
@@ -58,7 +77,7 @@ impl Context {
                         len,
                     ));
                     return;
-                },
+                }
                 Type::Scalar(s) => Some(PrimitiveType::Scalar(*s)),
                 Type::F32 => Some(PrimitiveType::F32),
                 Type::F64 => Some(PrimitiveType::F64),
@@ -107,4 +126,50 @@ impl Context {
             ));
         }
     }
+
+    fn encode_array_until(
+        &mut self,
+        type_: &ArrayType,
+        until: &ArrayUntil,
+        target: Target,
+        source: usize,
+    ) {
+        let current_pos = self.instructions.len();
+        let iter_index = self.alloc_register();
+        let new_source = self.alloc_register();
+
+        let mut ops = vec![];
+        if !type_.element.copyable() {
+            ops.push(FieldRef::Ref);
+        }
+        ops.push(FieldRef::ArrayAccess(iter_index));
+        self.instructions
+            .push(Instruction::GetField(new_source, source, ops));
+        self.encode_type(&type_.element, target, new_source);
+        let drained = self.instructions.drain(current_pos..).collect();
+        let len = self.alloc_register();
+        self.instructions
+            .push(Instruction::GetLen(len, source, None));
+        self.instructions
+            .push(Instruction::Loop(iter_index, len, drained));
+
+        let current_pos = self.instructions.len();
+        let ended = self.alloc_register();
+        self.push_eval(ended, until.predicate.clone());
+        let predicate = self.instructions.drain(current_pos..).collect();
+
+        let terminator = self.alloc_register();
+        self.encode_type(&type_.element, target, terminator);
+        let encode_terminator = self.instructions.drain(current_pos..).collect();
+
+        self.instructions.push(Instruction::AppendTerminator(
+            source,
+            (*type_.element).clone(),
+            until.element.name.clone(),
+            ended,
+            predicate,
+            terminator,
+            encode_terminator,
+        ));
+    }
 }
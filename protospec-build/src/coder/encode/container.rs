@@ -8,20 +8,33 @@ use crate::Span;
 use super::*;
 
 impl Context {
+    /// Operations completing the read of a struct field, converting it back from its mapped type if it is held as one, and taking a reference unless copyable
+    fn struct_field_ops(&self, field: &Field, optional: bool) -> Vec<FieldRef> {
+        let type_ = field.type_.borrow();
+        let mut ops = vec![];
+        if self.options.mapped_type(field).is_some() {
+            ops.push(FieldRef::Unmap(type_.clone(), optional));
+        }
+        if !type_.copyable() {
+            ops.push(FieldRef::Ref);
+        }
+        ops
+    }
+
+    fn get_struct_field(&mut self, target: usize, source: usize, field: &Field, optional: bool) {
+        let mut ops = vec![FieldRef::Name(field_name(field, self.options.rust_case))];
+        ops.extend(self.struct_field_ops(field, optional));
+        self.instructions
+            .push(Instruction::GetField(target, source, ops));
+    }
+
     fn encode_container_refs(&mut self, container: &ContainerType, source: usize) {
-        for (name, field) in container.flatten_view() {
+        for (name, field, optional) in container.flatten_view_optional(false) {
             if field.calculated.borrow().is_some() || field.is_pad.get() {
                 continue;
             }
             let target = self.alloc_register();
-            let type_ = field.type_.borrow();
-            let mut ops = vec![];
-            if !type_.copyable() {
-                ops.push(FieldRef::Ref);
-            }
-            ops.push(FieldRef::Name(name.clone()));
-            self.instructions
-                .push(Instruction::GetField(target, source, ops));
+            self.get_struct_field(target, source, &field, optional);
             self.instructions.push(Instruction::SetRef(name, target));
         }
     }
@@ -58,13 +71,19 @@ impl Context {
     fn encode_container_calculated(&mut self, container: &ContainerType) {
         for (name, field) in container.flatten_view() {
             let calculated = field.calculated.borrow();
+            if field.is_tag.get() {
+                continue;
+            }
             if let Some(calculated) = &*calculated {
-                if !calculated.blen_calls().is_empty() {
+                // calculated from the stream position, evaluated once encoding reaches the field
+                if !calculated.blen_calls().is_empty()
+                    || !calculated.stream_intrinsics().is_empty()
+                    || calculated.addr_target().is_some()
+                {
                     continue;
                 }
                 let calculated_register = self.alloc_register();
-                self.instructions
-                    .push(Instruction::Eval(calculated_register, calculated.clone()));
+                self.push_eval(calculated_register, calculated.clone());
                 self.instructions
                     .push(Instruction::SetRef(name, calculated_register));
             }
@@ -89,8 +108,7 @@ impl Context {
         calculated.rewrite_blen_calls(&blen_map);
 
         let calculated_register = self.alloc_register();
-        self.instructions
-            .push(Instruction::Eval(calculated_register, calculated));
+        self.push_eval(calculated_register, calculated);
         self.instructions
             .push(Instruction::SetRef(field.name.clone(), calculated_register));
         calculated_register
@@ -105,7 +123,31 @@ impl Context {
     ) {
         let mut auto_targets = vec![];
         for (name, child) in container.items.iter() {
+            if child.is_tag.get() {
+                continue;
+            }
             let calculated = child.calculated.borrow();
+            if let Some(pointer) = calculated.as_ref().and_then(|x| x.addr_target()) {
+                if !auto_targets.is_empty() || matches!(buf_target, Target::Buf(_)) {
+                    panic!("pointer offset {} cannot be encoded into a buffer", name);
+                }
+                let offset_type = match &*child.type_.borrow() {
+                    Type::Scalar(s) => *s,
+                    _ => panic!("pointer offset {} is not a scalar", name),
+                };
+                let condition = self.encode_field_condition(child);
+                let region = self.region.expect("pointer offset outside of a region");
+                let patch = self.alloc_register();
+                self.instructions.push(Instruction::WritePlaceholder(
+                    buf_target,
+                    condition,
+                    region,
+                    patch,
+                    offset_type.scalar.size(),
+                ));
+                self.pointer_patches.insert(pointer, (patch, offset_type));
+                continue;
+            }
             if let Some(calculated) = &*calculated {
                 let blen_calls = calculated.blen_calls();
                 if !blen_calls.is_empty() {
@@ -122,10 +164,26 @@ impl Context {
                         let new_target = self.alloc_register();
                         self.instructions.push(Instruction::AllocDynBuf(new_target));
                         auto_targets.push((new_target, child));
+                        if let Some(stream) = self.position_streams.last_mut() {
+                            let size = match &*child.type_.borrow() {
+                                Type::Scalar(s) => Some(s.scalar.size()),
+                                _ => None,
+                            };
+                            stream.pending.push((new_target, size));
+                        }
                         continue;
                     }
                 }
             }
+            if let Some(calculated) = &*calculated {
+                if calculated.blen_calls().is_empty() && !calculated.stream_intrinsics().is_empty()
+                {
+                    let calculated_register = self.alloc_register();
+                    self.push_eval(calculated_register, calculated.clone());
+                    self.instructions
+                        .push(Instruction::SetRef(name.clone(), calculated_register));
+                }
+            }
             let (real_target, _) = auto_targets
                 .last()
                 .map(|x| (Target::Buf(x.0), Some(&x.1)))
@@ -152,6 +210,9 @@ impl Context {
                         self.eval_blen_expr(&blen_calls[..], auto_field, calculated);
 
                     auto_targets.remove(i);
+                    if let Some(stream) = self.position_streams.last_mut() {
+                        stream.pending.retain(|(buf, _)| *buf != auto_target);
+                    }
                     let target = auto_targets
                         .get(i)
                         .map(|(target, _)| Target::Buf(*target))
@@ -167,6 +228,83 @@ impl Context {
         }
     }
 
+    fn encode_set_container(
+        &mut self,
+        type_: &ContainerType,
+        layout: &SetLayout,
+        target: Target,
+        source: usize,
+    ) {
+        let mut members = vec![];
+        for (name, child) in type_.items.iter() {
+            let tag_value = self.alloc_register();
+            self.push_eval(
+                tag_value,
+                type_
+                    .tags
+                    .get(name)
+                    .expect("missing set member tag")
+                    .clone(),
+            );
+
+            let start = self.instructions.len();
+            let member = self.alloc_register();
+            self.get_struct_field(member, source, child, true);
+            let present = self.alloc_register();
+            self.instructions.push(Instruction::IsSome(present, member));
+
+            let record_start = self.instructions.len();
+            self.instructions.push(Instruction::EncodePrimitive(
+                target,
+                tag_value,
+                PrimitiveType::Scalar(layout.tag),
+            ));
+            let buf = self.alloc_register();
+            self.instructions.push(Instruction::AllocDynBuf(buf));
+            self.encode_field_unconditional(Target::Buf(buf), member, child, true, false);
+            let length = self.alloc_register();
+            self.instructions
+                .push(Instruction::GetLen(length, buf, Some(layout.length.scalar)));
+            self.instructions.push(Instruction::EncodePrimitive(
+                target,
+                length,
+                PrimitiveType::Scalar(layout.length),
+            ));
+            self.instructions.push(Instruction::EmitBuf(target, buf));
+            let record = self.instructions.drain(record_start..).collect();
+            self.instructions
+                .push(Instruction::Conditional(present, record, vec![]));
+
+            members.push((tag_value, self.instructions.drain(start..).collect()));
+        }
+
+        let unknown = self.alloc_register();
+        self.instructions.push(Instruction::GetField(
+            unknown,
+            source,
+            vec![FieldRef::Ref, FieldRef::Name("unknown".to_string())],
+        ));
+        let order = if layout.preserve_order {
+            let order = self.alloc_register();
+            self.instructions.push(Instruction::GetField(
+                order,
+                source,
+                vec![FieldRef::Ref, FieldRef::Name("order".to_string())],
+            ));
+            Some(order)
+        } else {
+            None
+        };
+        self.instructions.push(Instruction::EncodeRecords(
+            target,
+            order,
+            unknown,
+            members,
+            layout.tag,
+            layout.length,
+        ));
+    }
+
     pub fn encode_container(
         &mut self,
         field: &Arc<Field>,
@@ -175,73 +313,157 @@ impl Context {
         source: usize,
         conditional: bool,
     ) {
-        let buf_target = if let Some(length) = &type_.length {
+        let (buf_target, len_register) = if let Some(length) = &type_.length {
             //todo: use limited stream
             let buf = self.alloc_register();
             // we avoid cyclic dependency during encoding by ignoring container length constraint if we're a blen-target
             if self.pending_autos.contains(&field.name) {
                 self.instructions.push(Instruction::AllocDynBuf(buf));
+                (Target::Buf(buf), None)
             } else {
                 let len_register = self.alloc_register();
-                self.instructions
-                    .push(Instruction::Eval(len_register, length.clone()));
+                self.push_eval(len_register, length.clone());
                 self.instructions
                     .push(Instruction::AllocBuf(buf, len_register));
+                (Target::Buf(buf), Some(len_register))
             }
-            Target::Buf(buf)
         } else {
-            target
+            (target, None)
+        };
+        let tracks_position = type_.tracks_stream_position(field);
+        let items_target = if tracks_position {
+            let counter = self.alloc_register();
+            self.instructions
+                .push(Instruction::CountStream(buf_target, counter));
+            self.position_streams.push(PositionStream {
+                counter,
+                length: len_register,
+                pending: vec![],
+            });
+            Target::Stream(counter)
+        } else {
+            buf_target
         };
         if type_.is_enum.get() {
             let break_start = self.instructions.len();
-            for (name, child) in type_.items.iter() {
-                let condition = self.encode_field_condition(child);
+            for child in type_.items.values() {
+                // variants of a match, or told apart by peeking at their own encoding, are chosen by discriminant
+                let peeks = matches!(&*child.condition.borrow(), Some(x) if x.stream_intrinsics().contains(&StreamIntrinsic::Peek));
+                let condition = if peeks || type_.discriminant.is_some() {
+                    let value = self.alloc_register();
+                    self.instructions.push(Instruction::IsVariant(
+                        value,
+                        global_name(field, self.options.rust_case),
+                        global_name(child, self.options.rust_case),
+                        source,
+                    ));
+                    Some(value)
+                } else {
+                    self.encode_field_condition(child)
+                };
                 let start = self.instructions.len();
                 let unwrapped = self.alloc_register();
+                let peeked = peeks.then(|| self.begin_peeked(child)).flatten();
+                let variant_target = peeked.map(Target::Buf).unwrap_or(items_target);
+
+                // the discriminant an unknown variant is encoded with cannot select another arm, or it decodes as that arm
+                let unknown = matches!(&*child.type_.borrow(), Type::Container(x) if x.items.values().any(|x| x.is_tag.get()));
+                let other_conditions: Vec<Expression> = type_
+                    .items
+                    .values()
+                    .filter(|x| unknown && !Arc::ptr_eq(x, child))
+                    .filter_map(|x| x.condition.borrow().clone())
+                    .collect();
 
                 let subtype = child.type_.borrow();
                 match &*subtype {
                     Type::Container(type_) => {
                         let mut unwrapped = vec![];
-                        for (subname, subchild) in type_.flatten_view() {
+                        let mut names = vec![];
+                        let mut mapped = vec![];
+                        for (subname, subchild, optional) in type_.flatten_view_optional(false) {
                             if subchild.is_pad.get()
+                                || subchild.is_fixed.get()
                                 || matches!(&*subchild.type_.borrow(), Type::Container(_))
                             {
                                 continue;
                             }
                             let alloced = self.alloc_register();
-                            unwrapped.push((
-                                subname.clone(),
-                                alloced,
-                                subchild.type_.borrow().copyable(),
-                            ));
+                            let member = field_name(&subchild, self.options.rust_case);
+                            names.push(subname);
+                            // fields held as a mapped type are bound by reference and converted back
+                            if self.options.mapped_type(&subchild).is_some() {
+                                unwrapped.push((member, alloced, false));
+                                mapped.push((alloced, subchild, optional));
+                            } else {
+                                unwrapped.push((
+                                    member,
+                                    alloced,
+                                    subchild.type_.borrow().copyable(),
+                                ));
+                            }
                         }
 
                         self.instructions.push(Instruction::UnwrapEnumStruct(
-                            field.name.clone(),
-                            name.clone(),
+                            global_name(field, self.options.rust_case),
+                            global_name(child, self.options.rust_case),
                             source,
                             unwrapped.clone(),
                             "mismatch betweeen condition and enum discriminant".to_string(),
                         ));
 
-                        for (name, register, _) in unwrapped {
+                        for (name, (_, register, _)) in names.into_iter().zip(unwrapped) {
+                            let register = match mapped.iter().find(|(x, _, _)| *x == register) {
+                                Some((_, subchild, optional)) => {
+                                    let ops = self.struct_field_ops(subchild, *optional);
+                                    let unmapped = self.alloc_register();
+                                    self.instructions
+                                        .push(Instruction::GetField(unmapped, register, ops));
+                                    unmapped
+                                }
+                                None => register,
+                            };
                             self.instructions.push(Instruction::SetRef(name, register));
                         }
+                        for condition in other_conditions {
+                            self.instructions.push(Instruction::Constrain(
+                                source,
+                                child.name.clone(),
+                                Expression::Unary(UnaryExpression {
+                                    op: crate::UnaryOp::Not,
+                                    inner: Box::new(condition),
+                                    span: child.span,
+                                }),
+                                format!("tag of {} is matched by another arm", child.name),
+                            ));
+                        }
                         self.encode_container_calculated(type_);
-                        self.encode_container_items(type_, buf_target, source, false);
+                        self.encode_container_items(type_, variant_target, source, false);
+                        if let Some(buf) = peeked {
+                            self.end_peeked(child, items_target, buf);
+                        }
                         self.instructions.push(Instruction::Break);
                     }
                     _ => {
                         self.instructions.push(Instruction::UnwrapEnum(
-                            field.name.clone(),
-                            name.clone(),
+                            global_name(field, self.options.rust_case),
+                            global_name(child, self.options.rust_case),
                             source,
                             unwrapped,
+                            subtype.copyable(),
                             "mismatch betweeen condition and enum discriminant".to_string(),
                         ));
 
-                        self.encode_field_unconditional(buf_target, unwrapped, child, false, false);
+                        self.encode_field_unconditional(
+                            variant_target,
+                            unwrapped,
+                            child,
+                            false,
+                            false,
+                        );
+                        if let Some(buf) = peeked {
+                            self.end_peeked(child, items_target, buf);
+                        }
                         self.instructions.push(Instruction::Break);
                     }
                 }
@@ -254,6 +476,8 @@ impl Context {
             }
             let drained = self.instructions.drain(break_start..).collect();
             self.instructions.push(Instruction::BreakBlock(drained));
+        } else if let Some(layout) = &type_.set {
+            self.encode_set_container(type_, layout, items_target, source);
         } else {
             if field.toplevel {
                 self.encode_container_refs(type_, source);
@@ -261,7 +485,10 @@ impl Context {
             } else {
                 self.nullcheck_container_refs(type_, conditional);
             }
-            self.encode_container_items(type_, buf_target, source, conditional);
+            self.encode_container_items(type_, items_target, source, conditional);
+        }
+        if tracks_position {
+            self.position_streams.pop();
         }
 
         if type_.length.is_some() {
@@ -309,6 +536,8 @@ impl Expression {
             Expression::FieldRef(_) => (),
             Expression::Str(_) => (),
             Expression::Bool(_) => (),
+            Expression::Tag(_) => (),
+            Expression::SizeOf(_) => (),
             Expression::Call(expr) => {
                 for expr in expr.arguments.iter_mut() {
                     expr.rewrite_blen_calls(map);
@@ -326,11 +555,19 @@ impl Expression {
                         span: Span::default(),
                         type_: RefCell::new(Type::Scalar(ScalarType::U64.into())),
                         calculated: RefCell::new(None),
+                        constraint: RefCell::new(None),
                         condition: RefCell::new(None),
+                        pointer: RefCell::new(None),
                         transforms: RefCell::new(vec![]),
                         toplevel: false,
                         is_maybe_cyclical: Cell::new(false),
+                        is_boxed: Cell::new(false),
                         is_pad: Cell::new(false),
+                        is_fixed: Cell::new(false),
+                        is_seekable: Cell::new(false),
+                        is_tag: Cell::new(false),
+                        encoded_size: Cell::new(None),
+                        attributes: vec![],
                     }));
                 }
             }
@@ -340,7 +577,21 @@ impl Expression {
         }
     }
 
-    fn blen_calls(&self) -> Vec<String> {
+    /// The pointer field an `addr` calculated offset is back-patched from
+    fn addr_target(&self) -> Option<String> {
+        match self {
+            Expression::Cast(expr) => expr.inner.addr_target(),
+            Expression::Call(expr) if expr.function.name == "addr" => {
+                match expr.arguments.first() {
+                    Some(Expression::FieldRef(f)) => Some(f.name.clone()),
+                    _ => panic!("invalid addr target, expected field ref"),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn blen_calls(&self) -> Vec<String> {
         let mut out = HashSet::new();
         self.extract_magic_blen_calls(&mut out);
         out.into_iter().collect()
@@ -374,6 +625,8 @@ impl Expression {
             Expression::FieldRef(_) => (),
             Expression::Str(_) => (),
             Expression::Bool(_) => (),
+            Expression::Tag(_) => (),
+            Expression::SizeOf(_) => (),
             Expression::Call(expr) => {
                 for expr in &expr.arguments {
                     expr.extract_magic_blen_calls(output);
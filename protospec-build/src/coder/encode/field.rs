@@ -18,20 +18,57 @@ impl Context {
                 self.instructions.push(Instruction::GetField(0, 0, ops))
             }
         }
+        if field.is_seekable.get() {
+            let region = self.alloc_register();
+            self.instructions.push(Instruction::BindRegion(region));
+            self.region = Some(region);
+        }
         self.encode_field(Target::Direct, top, field, false);
     }
 
     pub fn encode_field_condition(&mut self, field: &Arc<Field>) -> Option<usize> {
         if let Some(condition) = field.condition.borrow().as_ref() {
             let value = self.alloc_register();
-            self.instructions
-                .push(Instruction::Eval(value, condition.clone()));
+            self.push_eval(value, condition.clone());
             Some(value)
         } else {
             None
         }
     }
 
+    /// Fields conditioned on `remaining()`, `eof()` or `peek()` are encoded if they are present, as the encoder cannot see what follows
+    fn encode_field_presence(&mut self, field: &Arc<Field>) -> Option<usize> {
+        let condition = field.condition.borrow();
+        let intrinsics = condition.as_ref()?.stream_intrinsics();
+        if field.toplevel
+            || field.calculated.borrow().is_some()
+            || !intrinsics.iter().any(|x| {
+                matches!(
+                    x,
+                    StreamIntrinsic::Remaining | StreamIntrinsic::Eof | StreamIntrinsic::Peek
+                )
+            })
+        {
+            return None;
+        }
+        let name = match &*field.type_.borrow() {
+            Type::Container(type_) => type_
+                .flatten_view()
+                .find(|(_, child)| {
+                    child.calculated.borrow().is_none()
+                        && !child.is_pad.get()
+                        && !matches!(&*child.type_.borrow(), Type::Container(_))
+                })
+                .map(|(name, _)| name)?,
+            _ => field.name.clone(),
+        };
+        let source = self.alloc_register();
+        self.instructions.push(Instruction::GetRef(source, name));
+        let value = self.alloc_register();
+        self.instructions.push(Instruction::IsSome(value, source));
+        Some(value)
+    }
+
     pub fn encode_field(
         &mut self,
         target: Target,
@@ -39,16 +76,22 @@ impl Context {
         field: &Arc<Field>,
         conditional: bool,
     ) {
-        let field_condition = self.encode_field_condition(field);
+        let field_condition = self
+            .encode_field_presence(field)
+            .or_else(|| self.encode_field_condition(field));
         let start = self.instructions.len();
 
+        let peeked = self.begin_peeked(field);
         self.encode_field_unconditional(
-            target,
+            peeked.map(Target::Buf).unwrap_or(target),
             source,
             field,
             field_condition.is_some(),
             field_condition.is_some() || conditional,
         );
+        if let Some(buf) = peeked {
+            self.end_peeked(field, target, buf);
+        }
 
         if let Some(field_condition) = field_condition {
             let drained = self.instructions.drain(start..).collect();
@@ -70,8 +113,7 @@ impl Context {
         for transform in field.transforms.borrow().iter() {
             let condition = if let Some(condition) = &transform.condition {
                 let value = self.alloc_register();
-                self.instructions
-                    .push(Instruction::Eval(value, condition.clone()));
+                self.push_eval(value, condition.clone());
                 Some(value)
             } else {
                 None
@@ -81,7 +123,7 @@ impl Context {
             let mut args = vec![];
             for arg in transform.arguments.iter() {
                 let r = self.alloc_register();
-                self.instructions.push(Instruction::Eval(r, arg.clone()));
+                self.push_eval(r, arg.clone());
                 args.push(r);
             }
             let new_stream = self.alloc_register();
@@ -130,6 +172,16 @@ impl Context {
             source
         };
 
+        if let Some(constraint) = field.constraint.borrow().as_ref() {
+            self.bind_stream_position(constraint);
+            self.instructions.push(Instruction::Constrain(
+                source,
+                field.name.clone(),
+                constraint.clone(),
+                format!("constraint violated for field {}", field.name),
+            ));
+        }
+
         match &*field.type_.borrow() {
             _ if field.is_pad.get() => {
                 let array_type = field.type_.borrow();
@@ -139,11 +191,13 @@ impl Context {
                 };
                 let len = array_type.length.value.as_ref().cloned().unwrap();
                 let length_register = self.alloc_register();
-                self.instructions
-                    .push(Instruction::Eval(length_register, len));
+                self.push_eval(length_register, len);
                 self.instructions
                     .push(Instruction::Pad(target, length_register));
             }
+            type_ if field.pointer.borrow().is_some() => {
+                self.encode_pointer_field(field, type_, source, total_conditional)
+            }
             type_ => self.encode_complex_type(field, type_, target, source, total_conditional),
         }
 
@@ -13,6 +13,8 @@ pub enum Instruction {
     GetField(usize, usize, Vec<FieldRef>),
     /// dest, ref name
     GetRef(usize, String),
+    /// dest, optional source
+    IsSome(usize, usize),
     /// ref name, value
     SetRef(String, usize),
     /// buf handle, len register
@@ -33,14 +35,23 @@ pub enum Instruction {
     ),
     /// stream
     EndStream(usize),
+    /// stream, new position counting stream
+    CountStream(Target, usize),
+    /// counting stream, length, pending auto buffers and their field sizes, intrinsics to bind
+    BindPosition(
+        usize,
+        Option<usize>,
+        Vec<(usize, u64)>,
+        Vec<StreamIntrinsic>,
+    ),
 
     /// dest, buf handle
     EmitBuf(Target, usize),
 
     /// dest, source, type, arguments
     EncodeForeign(Target, usize, Arc<ForeignType>, Vec<usize>),
-    /// dest, source, arguments
-    EncodeRef(Target, usize, Vec<usize>),
+    /// dest, source, arguments, region encoded into if the type is seekable
+    EncodeRef(Target, usize, Vec<usize>, Option<usize>),
     /// rep type, dest, source
     EncodeEnum(Target, usize, EndianScalarType),
     /// dest, source
@@ -54,20 +65,64 @@ pub enum Instruction {
     /// dest, length register
     Pad(Target, usize),
 
+    /// dest, the region the encoder was called for
+    BindRegion(usize),
+    /// dest, condition, region of dest, patch handle, placeholder size
+    WritePlaceholder(Target, Option<usize>, usize, usize, u64),
+    /// dest region handle
+    ReserveRegion(usize),
+    /// patch handle, region pointed to, offset type
+    PatchPointer(usize, usize, EndianScalarType),
+    /// region, offset register
+    PlaceRegion(usize, usize),
+    /// region, instructions encoding its contents
+    EncodeRegion(usize, Vec<Instruction>),
+
     /// register representing iterator from -> term, term, inner
     Loop(usize, usize, Vec<Instruction>),
+    /// source array, element type, element name, predicate result, instructions evaluating the predicate,
+    /// terminator, instructions encoding the terminator. The terminator is the default value unless the array ends with a matching element
+    AppendTerminator(
+        usize,
+        Type,
+        String,
+        usize,
+        Vec<Instruction>,
+        usize,
+        Vec<Instruction>,
+    ),
+    /// dest, order of records if preserved, unknown records, tag values of members and instructions encoding their records,
+    /// tag type, length type
+    EncodeRecords(
+        Target,
+        Option<usize>,
+        usize,
+        Vec<(usize, Vec<Instruction>)>,
+        EndianScalarType,
+        EndianScalarType,
+    ),
+    /// dest, map whose values are encoded as an array
+    MapValues(usize, usize),
     /// len target, buffer, cast_type
     GetLen(usize, usize, Option<ScalarType>),
+    /// encoded length, expected length, message
+    CheckLen(usize, usize, String),
     /// register
     Drop(usize),
     /// original, checked, is_copyable, message
     NullCheck(usize, usize, bool, String),
     /// condition, if_true, if_false
     Conditional(usize, Vec<Instruction>, Vec<Instruction>),
-    /// enum name, discriminant, original, checked, message
-    UnwrapEnum(String, String, usize, usize, String),
+    /// dest, enum name, discriminant, original
+    IsVariant(usize, String, String, usize),
+    /// enum name, discriminant, original, checked, do_copy, message
+    UnwrapEnum(String, String, usize, usize, bool, String),
     /// enum name, discriminant, original, checked: (enumstruct field name, checked, do_copy), message
     UnwrapEnumStruct(String, String, usize, Vec<(String, usize, bool)>, String),
+    /// value, field name, constraint, message
+    Constrain(usize, String, Expression, String),
+    /// buffer holding an encoding, `peek()` condition its leading bytes satisfy, number of bytes peeked, message
+    CheckPeek(usize, Expression, u64, String),
     /// instructions
     BreakBlock(Vec<Instruction>),
     Break,
@@ -91,6 +146,7 @@ impl fmt::Display for Instruction {
                 write!(f, "GetField({}, {}, {:?})", dest, source, ops)
             }
             Instruction::GetRef(dest, ref_name) => write!(f, "GetRef({}, '{}')", dest, ref_name),
+            Instruction::IsSome(dest, source) => write!(f, "IsSome({}, {})", dest, source),
             Instruction::SetRef(ref_name, source) => {
                 write!(f, "SetRef('{}', {})", ref_name, source)
             }
@@ -127,6 +183,14 @@ impl fmt::Display for Instruction {
                 Ok(())
             }
             Instruction::EndStream(stream) => write!(f, "EndStream({})", stream),
+            Instruction::CountStream(stream, new_stream) => {
+                write!(f, "CountStream({:?}, {})", stream, new_stream)
+            }
+            Instruction::BindPosition(stream, length, pending, intrinsics) => write!(
+                f,
+                "BindPosition({}, {:?}, {:?}, {:?})",
+                stream, length, pending, intrinsics
+            ),
             Instruction::EmitBuf(dest, buf_handle) => {
                 write!(f, "EmitBuf({:?}, {})", dest, buf_handle)
             }
@@ -139,10 +203,10 @@ impl fmt::Display for Instruction {
                 write_arguments(f, &arguments[..])?;
                 write!(f, ")")
             }
-            Instruction::EncodeRef(dest, source, arguments) => {
+            Instruction::EncodeRef(dest, source, arguments, region) => {
                 write!(f, "EncodeRef({:?}, {}, ", dest, source)?;
                 write_arguments(f, &arguments[..])?;
-                write!(f, ")")
+                write!(f, ", {:?})", region)
             }
             Instruction::EncodeEnum(dest, source, type_) => {
                 write!(f, "EncodeEnum({:?}, {}, {})", dest, source, type_)
@@ -164,6 +228,26 @@ impl fmt::Display for Instruction {
                 dest, source, element_type, length
             ),
             Instruction::Pad(dest, length) => write!(f, "Pad({:?}, {})", dest, length),
+            Instruction::BindRegion(dest) => write!(f, "BindRegion({})", dest),
+            Instruction::WritePlaceholder(dest, condition, region, patch, size) => write!(
+                f,
+                "WritePlaceholder({:?}, {:?}, {}, {}, {})",
+                dest, condition, region, patch, size
+            ),
+            Instruction::ReserveRegion(dest) => write!(f, "ReserveRegion({})", dest),
+            Instruction::PatchPointer(patch, region, type_) => {
+                write!(f, "PatchPointer({}, {}, {})", patch, region, type_)
+            }
+            Instruction::PlaceRegion(region, offset) => {
+                write!(f, "PlaceRegion({}, {})", region, offset)
+            }
+            Instruction::EncodeRegion(region, instructions) => {
+                write!(f, "EncodeRegion({})", region)?;
+                for instruction in instructions {
+                    write!(indented(f), "\n{}", instruction)?;
+                }
+                Ok(())
+            }
             Instruction::Loop(inner, end, instructions) => {
                 write!(f, "Loop({}, {})", inner, end)?;
                 for instruction in instructions {
@@ -171,9 +255,50 @@ impl fmt::Display for Instruction {
                 }
                 Ok(())
             }
+            Instruction::AppendTerminator(
+                source,
+                type_,
+                name,
+                ended,
+                predicate,
+                terminator,
+                instructions,
+            ) => {
+                write!(
+                    f,
+                    "AppendTerminator({}, {}, '{}', {}, {})",
+                    source, type_, name, ended, terminator
+                )?;
+                for instruction in predicate {
+                    write!(indented(f), "\n{}", instruction)?;
+                }
+                write!(f, "\nEncode")?;
+                for instruction in instructions {
+                    write!(indented(f), "\n{}", instruction)?;
+                }
+                Ok(())
+            }
+            Instruction::EncodeRecords(dest, order, unknown, members, tag_type, length_type) => {
+                write!(
+                    f,
+                    "EncodeRecords({:?}, {:?}, {}, {}, {})",
+                    dest, order, unknown, tag_type, length_type
+                )?;
+                for (tag, instructions) in members {
+                    write!(f, "\nRecord({})", tag)?;
+                    for instruction in instructions {
+                        write!(indented(f), "\n{}", instruction)?;
+                    }
+                }
+                Ok(())
+            }
+            Instruction::MapValues(dest, map) => write!(f, "MapValues({}, {})", dest, map),
             Instruction::GetLen(dest, buffer, cast_type) => {
                 write!(f, "GetLen({}, {}, {:?})", dest, buffer, cast_type)
             }
+            Instruction::CheckLen(len, expected, message) => {
+                write!(f, "CheckLen({}, {}, {})", len, expected, message)
+            }
             Instruction::Drop(register) => write!(f, "Drop({})", register),
             Instruction::NullCheck(original, checked, is_copyable, message) => write!(
                 f,
@@ -191,11 +316,30 @@ impl fmt::Display for Instruction {
                 }
                 Ok(())
             }
-            Instruction::UnwrapEnum(enum_name, discriminant, original, checked, message) => write!(
+            Instruction::IsVariant(dest, enum_name, discriminant, original) => write!(
                 f,
-                "UnwrapEnum('{}', '{}', {}, {}, '{}')",
-                enum_name, discriminant, original, checked, message
+                "IsVariant({}, '{}', '{}', {})",
+                dest, enum_name, discriminant, original
             ),
+            Instruction::UnwrapEnum(
+                enum_name,
+                discriminant,
+                original,
+                checked,
+                do_copy,
+                message,
+            ) => {
+                write!(
+                    f,
+                    "UnwrapEnum('{}', '{}', {}, {}{}, '{}')",
+                    enum_name,
+                    discriminant,
+                    original,
+                    if *do_copy { "*" } else { "" },
+                    checked,
+                    message
+                )
+            }
             Instruction::UnwrapEnumStruct(enum_name, discriminant, original, checked, message) => {
                 write!(
                     f,
@@ -213,6 +357,16 @@ impl fmt::Display for Instruction {
                 }
                 Ok(())
             }
+            Instruction::CheckPeek(buf, condition, len, message) => write!(
+                f,
+                "CheckPeek({}, {:?}, {}, '{}')",
+                buf, condition, len, message
+            ),
+            Instruction::Constrain(value, name, constraint, message) => write!(
+                f,
+                "Constrain({}, '{}', {:?}, '{}')",
+                value, name, constraint, message
+            ),
             Instruction::BreakBlock(instructions) => {
                 write!(f, "BreakBlock()")?;
                 for instruction in instructions {
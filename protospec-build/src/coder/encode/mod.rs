@@ -2,6 +2,8 @@ use indexmap::{IndexMap, IndexSet};
 
 use super::*;
 use crate::asg::*;
+use crate::compiler::{field_name, global_name, CompileOptions};
+use crate::StreamIntrinsic;
 use std::sync::Arc;
 
 mod instruction;
@@ -27,6 +29,23 @@ pub struct Context {
     pub resolved_autos: IndexMap<String, usize>,
     // set of pending field name
     pub pending_autos: IndexSet<String>,
+    // stack of position tracking streams
+    pub position_streams: Vec<PositionStream>,
+    // region currently encoded into, for seekable types
+    pub region: Option<usize>,
+    // map of pointer field name -> placeholder patch handle and offset type
+    pub pointer_patches: IndexMap<String, (usize, EndianScalarType)>,
+    // options of the generated code, which decide the names and representation of types
+    pub options: CompileOptions,
+}
+
+#[derive(Debug)]
+pub struct PositionStream {
+    pub counter: usize,
+    // length register if the container length is known ahead of encoding
+    pub length: Option<usize>,
+    // buffers of pending auto fields, not yet written to the counter, and the encoded size of each auto field
+    pub pending: Vec<(usize, Option<u64>)>,
 }
 
 impl Context {
@@ -35,15 +54,90 @@ impl Context {
         self.register_count += 1;
         x
     }
+
+    /// Binds the stream position intrinsics read by `expr` ahead of its evaluation
+    fn bind_stream_position(&mut self, expr: &Expression) {
+        let intrinsics = expr.stream_intrinsics();
+        if intrinsics.is_empty() {
+            return;
+        }
+        let stream = self
+            .position_streams
+            .last()
+            .expect("stream position used outside of a container");
+        if intrinsics.contains(&StreamIntrinsic::Eof) {
+            panic!("eof() can only be encoded in field conditions and array lengths");
+        }
+        if intrinsics.contains(&StreamIntrinsic::Peek) {
+            panic!("peek() can only be encoded in field and enum variant conditions");
+        }
+        if stream.length.is_none() && intrinsics.contains(&StreamIntrinsic::Remaining) {
+            panic!("remaining() used in a container without a length known ahead of encoding");
+        }
+        let pending = stream
+            .pending
+            .iter()
+            .map(|(buf, size)| {
+                (
+                    *buf,
+                    size.expect("stream position used after an auto field of unknown size"),
+                )
+            })
+            .collect();
+        self.instructions.push(Instruction::BindPosition(
+            stream.counter,
+            stream.length,
+            pending,
+            intrinsics,
+        ));
+    }
+
+    /// Allocates a buffer to encode a field conditioned on `peek()` into, so the bytes it begins with can be checked against its condition
+    fn begin_peeked(&mut self, field: &Field) -> Option<usize> {
+        let condition = field.condition.borrow();
+        if condition.as_ref()?.stream_intrinsics() != [StreamIntrinsic::Peek] {
+            return None;
+        }
+        let buf = self.alloc_register();
+        self.instructions.push(Instruction::AllocDynBuf(buf));
+        Some(buf)
+    }
+
+    /// Checks the encoding of a field in the buffer from [`Context::begin_peeked`] satisfies its condition, as decoding tells it apart by it, and writes it to `target`
+    fn end_peeked(&mut self, field: &Field, target: Target, buf: usize) {
+        let condition = field.condition.borrow().clone().unwrap();
+        let len = condition
+            .peek_len()
+            .expect("peek() offsets are integer literals");
+        self.instructions.push(Instruction::CheckPeek(
+            buf,
+            condition,
+            len,
+            format!(
+                "encoding of '{}' does not satisfy its peek() condition",
+                field.name
+            ),
+        ));
+        self.instructions.push(Instruction::EmitBuf(target, buf));
+    }
+
+    fn push_eval(&mut self, register: usize, expr: Expression) {
+        self.bind_stream_position(&expr);
+        self.instructions.push(Instruction::Eval(register, expr));
+    }
 }
 
 impl Context {
-    pub fn new() -> Context {
+    pub fn new(options: &CompileOptions) -> Context {
         Context {
             instructions: vec![],
             register_count: 0,
             resolved_autos: IndexMap::new(),
             pending_autos: IndexSet::new(),
+            position_streams: vec![],
+            region: None,
+            pointer_patches: IndexMap::new(),
+            options: options.clone(),
         }
     }
 
@@ -59,10 +153,70 @@ impl Context {
             Type::Container(type_) => {
                 self.encode_container(field, &**type_, target, source, conditional)
             }
+            // a byte bounded array is encoded ahead of its `blen` into a buffer of its own
+            Type::Array(array) if array.length.bytes && self.pending_autos.remove(&field.name) => {
+                let buf = self.alloc_register();
+                self.instructions.push(Instruction::AllocDynBuf(buf));
+                self.encode_array(array, Target::Buf(buf), source);
+                let len = self.alloc_register();
+                self.instructions
+                    .push(Instruction::GetLen(len, buf, Some(ScalarType::U64)));
+                self.resolved_autos.insert(field.name.clone(), len);
+                self.instructions.push(Instruction::EmitBuf(target, buf));
+            }
+            // otherwise it is encoded into a buffer to check it fills the length it is given
+            Type::Array(array) if array.length.bytes && !array.reads_remaining() => {
+                let buf = self.alloc_register();
+                self.instructions.push(Instruction::AllocDynBuf(buf));
+                self.encode_array(array, Target::Buf(buf), source);
+                let len = self.alloc_register();
+                self.instructions
+                    .push(Instruction::GetLen(len, buf, Some(ScalarType::U64)));
+                let expected = self.alloc_register();
+                self.push_eval(expected, array.length.value.clone().unwrap());
+                self.instructions.push(Instruction::CheckLen(
+                    len,
+                    expected,
+                    format!(
+                        "encoded length of '{}' does not match its byte length",
+                        field.name
+                    ),
+                ));
+                self.instructions.push(Instruction::EmitBuf(target, buf));
+            }
             type_ => self.encode_type(type_, target, source),
         }
     }
 
+    /// Encodes a pointer field into a region of its own, laid out once the whole type is encoded
+    fn encode_pointer_field(
+        &mut self,
+        field: &Arc<Field>,
+        type_: &Type,
+        source: usize,
+        conditional: bool,
+    ) {
+        let region = self.alloc_register();
+        self.instructions.push(Instruction::ReserveRegion(region));
+        if let Some((patch, offset_type)) = self.pointer_patches.remove(&field.name) {
+            self.instructions
+                .push(Instruction::PatchPointer(patch, region, offset_type));
+        } else {
+            let pointer = field.pointer.borrow().clone().unwrap();
+            let offset = self.alloc_register();
+            self.push_eval(offset, pointer);
+            self.instructions
+                .push(Instruction::PlaceRegion(region, offset));
+        }
+        let outer_region = self.region.replace(region);
+        let start = self.instructions.len();
+        self.encode_complex_type(field, type_, Target::Direct, source, conditional);
+        let drained = self.instructions.drain(start..).collect();
+        self.instructions
+            .push(Instruction::EncodeRegion(region, drained));
+        self.region = outer_region;
+    }
+
     pub fn encode_type(&mut self, type_: &Type, target: Target, source: usize) {
         match type_ {
             Type::Container(_) => {
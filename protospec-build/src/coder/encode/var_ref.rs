@@ -6,7 +6,7 @@ impl Context {
             let mut function_args = vec![];
             for expr in &type_.arguments {
                 let r = self.alloc_register();
-                self.instructions.push(Instruction::Eval(r, expr.clone()));
+                self.push_eval(r, expr.clone());
                 function_args.push(r);
             }
             self.instructions.push(Instruction::EncodeForeign(
@@ -19,11 +19,25 @@ impl Context {
             let mut args = vec![];
             for arg in type_.arguments.iter() {
                 let r = self.alloc_register();
-                self.instructions.push(Instruction::Eval(r, arg.clone()));
+                self.push_eval(r, arg.clone());
                 args.push(r);
             }
+            let region = if type_.target.is_seekable.get() {
+                if matches!(target, Target::Buf(_)) {
+                    panic!(
+                        "{} uses pointer fields, and cannot be encoded into a buffer",
+                        type_.target.name
+                    );
+                }
+                Some(
+                    self.region
+                        .expect("seekable type encoded outside of a region"),
+                )
+            } else {
+                None
+            };
             self.instructions
-                .push(Instruction::EncodeRef(target, source, args));
+                .push(Instruction::EncodeRef(target, source, args, region));
         }
     }
 }
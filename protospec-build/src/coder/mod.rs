@@ -3,6 +3,7 @@ use std::fmt;
 use proc_macro2::TokenStream;
 use quote::{ToTokens, TokenStreamExt};
 
+use crate::asg::Type;
 use crate::{EndianScalarType, ScalarType};
 
 #[derive(Debug)]
@@ -11,6 +12,8 @@ pub enum FieldRef {
     Name(String),
     ArrayAccess(usize),
     TupleAccess(usize),
+    // converts a field held as its mapped type back to this type, whether the field is an `Option`
+    Unmap(Type, bool),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -70,5 +73,7 @@ impl ToTokens for PrimitiveType {
     }
 }
 
+mod position;
+
 pub mod decode;
 pub mod encode;
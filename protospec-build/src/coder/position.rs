@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use crate::asg::*;
+use crate::{ScalarType, StreamIntrinsic};
+
+impl Expression {
+    /// The stream position intrinsics called anywhere in this expression
+    pub fn stream_intrinsics(&self) -> Vec<StreamIntrinsic> {
+        let mut out = vec![];
+        self.extract_stream_intrinsics(&mut out);
+        out
+    }
+
+    /// The number of bytes `peek` calls in this expression read ahead of the stream, or `None` if an offset is not an integer literal
+    pub fn peek_len(&self) -> Option<u64> {
+        Some(match self {
+            Expression::Binary(expr) => expr.left.peek_len()?.max(expr.right.peek_len()?),
+            Expression::Unary(expr) => expr.inner.peek_len()?,
+            Expression::Cast(expr) => expr.inner.peek_len()?,
+            Expression::ArrayIndex(expr) => expr.array.peek_len()?.max(expr.index.peek_len()?),
+            Expression::Ternary(expr) => expr
+                .condition
+                .peek_len()?
+                .max(expr.if_true.peek_len()?)
+                .max(expr.if_false.peek_len()?),
+            Expression::Call(expr) => {
+                let mut arguments = 0;
+                for argument in &expr.arguments {
+                    arguments = arguments.max(argument.peek_len()?);
+                }
+                if expr.function.inner.stream_intrinsic() != Some(StreamIntrinsic::Peek) {
+                    return Some(arguments);
+                }
+                let offset = match expr.arguments.first() {
+                    None => 0,
+                    Some(Expression::Int(offset)) => match offset.value.cast_to(ScalarType::U64) {
+                        ConstInt::U64(offset) => offset,
+                        _ => unreachable!(),
+                    },
+                    Some(_) => return None,
+                };
+                arguments.max(offset.saturating_add(1))
+            }
+            Expression::Member(expr) => expr.target.peek_len()?,
+            _ => 0,
+        })
+    }
+
+    fn extract_stream_intrinsics(&self, output: &mut Vec<StreamIntrinsic>) {
+        match self {
+            Expression::Binary(expr) => {
+                expr.left.extract_stream_intrinsics(output);
+                expr.right.extract_stream_intrinsics(output);
+            }
+            Expression::Unary(expr) => {
+                expr.inner.extract_stream_intrinsics(output);
+            }
+            Expression::Cast(expr) => {
+                expr.inner.extract_stream_intrinsics(output);
+            }
+            Expression::ArrayIndex(expr) => {
+                expr.array.extract_stream_intrinsics(output);
+                expr.index.extract_stream_intrinsics(output);
+            }
+            Expression::Ternary(expr) => {
+                expr.condition.extract_stream_intrinsics(output);
+                expr.if_true.extract_stream_intrinsics(output);
+                expr.if_false.extract_stream_intrinsics(output);
+            }
+            Expression::EnumAccess(_) => (),
+            Expression::Int(_) => (),
+            Expression::ConstRef(_) => (),
+            Expression::InputRef(_) => (),
+            Expression::FieldRef(_) => (),
+            Expression::Str(_) => (),
+            Expression::Bool(_) => (),
+            Expression::Tag(_) => (),
+            Expression::SizeOf(_) => (),
+            Expression::Call(expr) => {
+                for expr in &expr.arguments {
+                    expr.extract_stream_intrinsics(output);
+                }
+                if let Some(intrinsic) = expr.function.inner.stream_intrinsic() {
+                    if !output.contains(&intrinsic) {
+                        output.push(intrinsic);
+                    }
+                }
+            }
+            Expression::Member(expr) => {
+                expr.target.extract_stream_intrinsics(output);
+            }
+        }
+    }
+}
+
+// whether an expression reads the stream intrinsics of interest
+type Reads<'a> = &'a dyn Fn(&Expression) -> bool;
+
+fn type_uses_stream_position(type_: &Type, reads: Reads) -> bool {
+    match type_ {
+        // length constrained containers track their own position, only their length is evaluated in ours
+        Type::Container(container) => match &container.length {
+            Some(length) => reads(length),
+            None => container
+                .items
+                .values()
+                .any(|x| field_uses_stream_position(x, reads)),
+        },
+        Type::Array(array) => {
+            matches!(&array.length.value, Some(x) if reads(x))
+                || matches!(&array.length.until, Some(x) if reads(&x.predicate))
+                || type_uses_stream_position(&array.element, reads)
+        }
+        Type::Ref(type_ref) => type_ref.arguments.iter().any(reads),
+        _ => false,
+    }
+}
+
+fn field_uses_stream_position(field: &Arc<Field>, reads: Reads) -> bool {
+    let uses = |expr: &Option<Expression>| matches!(expr, Some(x) if reads(x));
+    uses(&field.condition.borrow())
+        || uses(&field.calculated.borrow())
+        || uses(&field.constraint.borrow())
+        || field
+            .transforms
+            .borrow()
+            .iter()
+            .any(|transform| uses(&transform.condition) || transform.arguments.iter().any(reads))
+        || type_uses_stream_position(&field.type_.borrow(), reads)
+}
+
+impl ContainerType {
+    /// Whether any item of this container, or of its unconstrained interior containers, reads the stream position
+    pub fn uses_stream_position(&self) -> bool {
+        self.items
+            .values()
+            .any(|x| field_uses_stream_position(x, &|x| !x.stream_intrinsics().is_empty()))
+    }
+
+    /// Whether any item of this container, or of its unconstrained interior containers, peeks ahead of the stream
+    pub fn uses_peek(&self) -> bool {
+        self.items.values().any(|x| {
+            field_uses_stream_position(x, &|x| {
+                x.stream_intrinsics().contains(&StreamIntrinsic::Peek)
+            })
+        })
+    }
+
+    /// Whether this container tracks a stream position for its items
+    pub fn tracks_stream_position(&self, field: &Arc<Field>) -> bool {
+        (field.toplevel || self.length.is_some()) && self.uses_stream_position()
+    }
+}
@@ -1,10 +1,10 @@
 use super::*;
 use crate::coder::decode::*;
-use crate::{coder::*, map_async, Endian, EndianScalarType};
+use crate::{coder::*, map_async, Endian, EndianScalarType, ScalarType, StreamIntrinsic};
 
 fn emit_target(target: &Target) -> TokenStream {
     match target {
-        Target::Direct => quote! { reader },
+        Target::Direct => quote! { _ps_reader },
         Target::Stream(x) => emit_register(*x),
         Target::Buf(x) => {
             let buf = emit_register(*x);
@@ -25,7 +25,7 @@ fn prepare_decode(
     if is_root {
         if is_async {
             statements.push(quote! {
-                use tokio::io::{ AsyncRead, AsyncBufRead, AsyncBufReadExt, AsyncReadExt };
+                use tokio::io::{ AsyncRead, AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncSeek, AsyncSeekExt };
             })
         } else {
             statements.push(quote! {
@@ -44,7 +44,7 @@ fn prepare_decode(
         match instruction {
             Instruction::Eval(target, expr, field_register_map) => {
                 let target = emit_register(*target);
-                let value = emit_expression(expr, &|field| {
+                let value = emit_expression(expr, options, &|field| {
                     emit_register(
                         *field_register_map
                             .get(&field.name)
@@ -81,7 +81,7 @@ fn prepare_decode(
                         })
                         .collect::<Vec<_>>(),
                 );
-                let name = emit_ident(name);
+                let name = emit_type_path(name);
                 statements.push(quote! {
                     let #target = #name(#items);
                 });
@@ -98,7 +98,7 @@ fn prepare_decode(
                         })
                         .collect::<Vec<_>>(),
                 );
-                let name = emit_ident(name);
+                let name = emit_type_path(name);
                 statements.push(quote! {
                     let #target = #name { #items };
                 });
@@ -121,7 +121,7 @@ fn prepare_decode(
                         })
                         .collect::<Vec<_>>(),
                 );
-                let name = emit_ident(name);
+                let name = emit_type_path(name);
                 let discriminant = emit_ident(discriminant);
                 statements.push(quote! {
                     let #target = #name::#discriminant(#items);
@@ -146,7 +146,7 @@ fn prepare_decode(
                         })
                         .collect::<Vec<_>>(),
                 );
-                let name = emit_ident(name);
+                let name = emit_type_path(name);
                 let discriminant = emit_ident(discriminant);
                 statements.push(quote! {
                     let #target = #name::#discriminant { #items };
@@ -161,6 +161,34 @@ fn prepare_decode(
                     let #new_stream = &mut #new_stream;
                 });
             }
+            Instruction::CountStream(stream, new_stream) => {
+                let stream = emit_target(stream);
+                let new_stream = emit_register(*new_stream);
+                statements.push(quote! {
+                    let mut #new_stream = CountingStream::new(#stream);
+                    let #new_stream = &mut #new_stream;
+                });
+            }
+            Instruction::BindPosition(stream, len, intrinsics) => {
+                let stream = emit_register(*stream);
+                for intrinsic in intrinsics {
+                    statements.push(match intrinsic {
+                        StreamIntrinsic::Offset => quote! {
+                            let stream_offset = #stream.count;
+                        },
+                        StreamIntrinsic::Remaining => {
+                            let len = emit_register(len.expect("remaining() without length"));
+                            quote! {
+                                let stream_remaining = (#len as u64).saturating_sub(#stream.count);
+                            }
+                        }
+                        StreamIntrinsic::Eof => quote! {
+                            let stream_eof = #stream.fill_buf()#async_?.is_empty();
+                        },
+                        StreamIntrinsic::Peek => unreachable!("peek() is bound by BindPeek"),
+                    });
+                }
+            }
             Instruction::WrapStream(stream, new_stream, transformer, args) => {
                 let new_stream_value = emit_register(*new_stream);
                 let args = args.iter().map(|x| emit_register(*x)).collect::<Vec<_>>();
@@ -228,7 +256,7 @@ fn prepare_decode(
                         .decoding_gen(target, data, out_arguments, is_async),
                 );
             }
-            Instruction::DecodeRef(target, source, class, args) => {
+            Instruction::DecodeRef(target, source, class, args, seekable) => {
                 let mut out_arguments = vec![];
                 for argument in args {
                     let value = emit_register(*argument);
@@ -237,8 +265,18 @@ fn prepare_decode(
                 let out_arguments = flatten(out_arguments);
                 let target = emit_target(target);
                 let source = emit_register(*source);
-                let class = emit_ident(class);
-                if is_async {
+                let class = emit_type_path(class);
+                if *seekable {
+                    if is_async {
+                        statements.push(quote! {
+                            let #source = #class::decode_async_at(#target, _ps_stream_base #out_arguments).await?;
+                        });
+                    } else {
+                        statements.push(quote! {
+                            let #source = #class::decode_sync_at(#target, _ps_stream_base #out_arguments)?;
+                        });
+                    }
+                } else if is_async {
                     statements.push(quote! {
                         let #source = #class::decode_async(#target #out_arguments).await?;
                     });
@@ -252,7 +290,7 @@ fn prepare_decode(
                 let target = emit_target(target);
                 let value = emit_register(*value);
 
-                let enum_ident = format_ident!("{}", &name);
+                let enum_ident = emit_type_path(name);
                 let length = type_.size() as usize;
                 let decoder = match type_ {
                     PrimitiveType::Scalar(EndianScalarType {
@@ -318,27 +356,94 @@ fn prepare_decode(
                         let #data = {
                             let t_count = #len as usize;
                             let size = mem::size_of::<#type_>();
-                            let mut raw: Vec<u8> = Vec::with_capacity(t_count * size);
+                            let mut raw: ::std::vec::Vec<u8> = ::std::vec::Vec::with_capacity(t_count * size);
                             unsafe { raw.set_len(t_count * size) };
                             #target.read_exact(&mut raw[..])#async_?;
-                            raw.chunks_exact(size).map(|x| #type_::#decoder(x.try_into().unwrap())).collect::<Vec<#type_>>()
+                            raw.chunks_exact(size).map(|x| #type_::#decoder(x.try_into().unwrap())).collect::<::std::vec::Vec<#type_>>()
                         };
                     });
                 } else {
                     statements.push(quote! {
                         let #data = {
-                            let mut raw: Vec<u8> = Vec::new();
+                            let mut raw: ::std::vec::Vec<u8> = ::std::vec::Vec::new();
                             #target.read_to_end(&mut raw)#async_?;
                             let size = mem::size_of::<#type_>();
-                            raw.chunks_exact(size).map(|x| #type_::#decoder(x.try_into().unwrap())).collect::<Vec<#type_>>()
+                            raw.chunks_exact(size).map(|x| #type_::#decoder(x.try_into().unwrap())).collect::<::std::vec::Vec<#type_>>()
                         };
                     });
                 }
             }
+            Instruction::DecodeFixedArray(target, data, type_, len) => {
+                let target = emit_target(target);
+                let data = emit_register(*data);
+                if matches!(
+                    type_,
+                    PrimitiveType::Scalar(EndianScalarType {
+                        scalar: ScalarType::U8,
+                        ..
+                    })
+                ) {
+                    statements.push(quote! {
+                        let mut #data = [0u8; #len];
+                        #target.read_exact(&mut #data[..])#async_?;
+                    });
+                } else {
+                    let decoder = match type_ {
+                        PrimitiveType::Scalar(EndianScalarType {
+                            endian: Endian::Little,
+                            ..
+                        }) => quote! { from_le_bytes },
+                        _ => quote! { from_be_bytes },
+                    };
+                    let size = type_.size() as usize;
+                    let raw_len = size * *len;
+                    statements.push(quote! {
+                        let #data = {
+                            let mut raw = [0u8; #raw_len];
+                            #target.read_exact(&mut raw[..])#async_?;
+                            let mut out = [0 as #type_; #len];
+                            for (x, raw) in out.iter_mut().zip(raw.chunks_exact(#size)) {
+                                *x = #type_::#decoder(raw.try_into().unwrap());
+                            }
+                            out
+                        };
+                    });
+                }
+            }
+            Instruction::BoxValue(dest, value) => {
+                let dest = emit_register(*dest);
+                let value = emit_register(*value);
+                statements.push(quote! {
+                    let #dest = ::std::boxed::Box::new(#value);
+                });
+            }
+            Instruction::MapValue(dest, value, optional) => {
+                let dest = emit_register(*dest);
+                let value = emit_register(*value);
+                statements.push(if *optional {
+                    quote! {
+                        let #dest = #value.map(From::from);
+                    }
+                } else {
+                    quote! {
+                        let #dest = From::from(#value);
+                    }
+                });
+            }
+            Instruction::IntoFixedArray(dest, elements, len) => {
+                let dest = emit_register(*dest);
+                let elements = emit_register(*elements);
+                statements.push(quote! {
+                    let #dest: [_; #len] = match #elements.try_into() {
+                        Ok(x) => x,
+                        Err(_) => unreachable!("array decoded with the wrong length"),
+                    };
+                });
+            }
             Instruction::DecodeReprArray(target, data, name, type_, len) => {
                 let target = emit_target(target);
                 let data = emit_register(*data);
-                let enum_ident = format_ident!("{}", &name);
+                let enum_ident = emit_type_path(name);
                 let decoder = match type_ {
                     PrimitiveType::Scalar(EndianScalarType {
                         endian: Endian::Little,
@@ -353,58 +458,38 @@ fn prepare_decode(
                         let #data = {
                             let t_count = #len as usize;
                             let size = mem::size_of::<#type_>();
-                            let mut raw: Vec<u8> = Vec::with_capacity(t_count * size);
+                            let mut raw: ::std::vec::Vec<u8> = ::std::vec::Vec::with_capacity(t_count * size);
                             unsafe { raw.set_len(t_count * size) };
                             #target.read_exact(&mut raw[..])#async_?;
-                            raw.chunks_exact(size).map(|x| #enum_ident::from_repr(#type_::#decoder(x.try_into().unwrap()))).collect::<Result<Vec<#enum_ident>>>()?
+                            raw.chunks_exact(size).map(|x| #enum_ident::from_repr(#type_::#decoder(x.try_into().unwrap()))).collect::<Result<::std::vec::Vec<#enum_ident>>>()?
                         };
                     });
                 } else {
                     statements.push(quote! {
                         let #data = {
-                            let mut raw: Vec<u8> = Vec::new();
+                            let mut raw: ::std::vec::Vec<u8> = ::std::vec::Vec::new();
                             #target.read_to_end(&mut raw)#async_?;
                             let size = mem::size_of::<#type_>();
-                            raw.chunks_exact(size).map(|x| #enum_ident::from_repr(#type_::#decoder(x.try_into().unwrap()))).collect::<Result<Vec<#enum_ident>>>()?
+                            raw.chunks_exact(size).map(|x| #enum_ident::from_repr(#type_::#decoder(x.try_into().unwrap()))).collect::<Result<::std::vec::Vec<#enum_ident>>>()?
                         };
                     });
                 }
             }
-            Instruction::Loop(target, stop_index, terminator, output, inner) => {
+            Instruction::Loop(target, stop_index, output, inner) => {
                 let output = emit_register(*output);
                 let inner = prepare_decode(options, context, &inner[..], is_async, false);
                 let stop = stop_index.map(emit_register);
-                let terminator = terminator.map(emit_register);
                 let target = emit_target(target);
                 if let Some(stop) = stop {
                     statements.push(quote! {
-                        let mut #output = Vec::with_capacity(#stop as usize);
+                        let mut #output = ::std::vec::Vec::with_capacity(#stop as usize);
                         for _ in 0..#stop {
                             #inner
                         }
                     });
-                } else if let Some(terminator) = terminator {
-                    statements.push(quote! {
-                        let mut #output = Vec::new();
-                        loop {
-                            let buf = #target.fill_buf()#async_?;
-                            if buf.len() == 0 {
-                                break;
-                            }
-                            if (buf.len() < #terminator.len()) {
-                                //todo: confirm this cannot infinite loop
-                                continue;
-                            }
-                            if &buf[..#terminator.len()] == #terminator {
-                                #target.consume(#terminator.len());
-                                break;
-                            }
-                            #inner
-                        }
-                    });
                 } else {
                     statements.push(quote! {
-                        let mut #output = Vec::new();
+                        let mut #output = ::std::vec::Vec::new();
                         //TODO: optimize this to not buffer with a Peekable type
                         {
                             let mut r = vec![];
@@ -422,6 +507,96 @@ fn prepare_decode(
                     });
                 }
             }
+            Instruction::Lookahead(stream, new_stream) => {
+                let stream = emit_target(stream);
+                let new_stream = emit_register(*new_stream);
+                statements.push(quote! {
+                    let mut #new_stream = Lookahead::new(&mut *#stream);
+                    let #new_stream = &mut #new_stream;
+                });
+            }
+            Instruction::BindPeek(stream, len) => {
+                let stream = emit_register(*stream);
+                let len = *len as usize;
+                let peek = if is_async {
+                    quote! { peek_async }
+                } else {
+                    quote! { peek_sync }
+                };
+                statements.push(quote! {
+                    let stream_peek = #stream.#peek(#len)#async_?.to_vec();
+                    if stream_peek.len() < #len {
+                        return Err(decode_error("unexpected end of stream in peek()").into());
+                    }
+                });
+            }
+            Instruction::EndLookahead(stream, outer) => {
+                let stream = emit_register(*stream);
+                if let Some(outer) = outer {
+                    let outer = emit_register(*outer);
+                    statements.push(quote! {
+                        let pending = std::mem::take(&mut #stream.pending);
+                        #outer.pending.splice(..0, pending);
+                    });
+                } else {
+                    statements.push(quote! {
+                        if !#stream.pending.is_empty() {
+                            return Err(decode_error("bytes read ahead of the stream were not decoded").into());
+                        }
+                    });
+                }
+            }
+            Instruction::LoopTerminated(stream, terminators, leave, eof, output, inner) => {
+                let stream = emit_register(*stream);
+                let terminators = flatten_separated(
+                    terminators
+                        .iter()
+                        .map(|x| {
+                            let x = emit_register(*x);
+                            quote! { &#x[..] }
+                        })
+                        .collect::<Vec<_>>(),
+                    quote! {,},
+                );
+                let output = emit_register(*output);
+                let inner = prepare_decode(options, context, &inner[..], is_async, false);
+                let (terminator, peek, skip) = if is_async {
+                    (
+                        quote! { terminator_async },
+                        quote! { peek_async },
+                        quote! { skip_async },
+                    )
+                } else {
+                    (
+                        quote! { terminator_sync },
+                        quote! { peek_sync },
+                        quote! { skip_sync },
+                    )
+                };
+                let consume = if *leave {
+                    quote! {}
+                } else {
+                    quote! { #stream.#skip(terminator); }
+                };
+                let at_eof = if *eof {
+                    quote! { break; }
+                } else {
+                    quote! { return Err(decode_error("unterminated array").into()); }
+                };
+                statements.push(quote! {
+                    let mut #output = ::std::vec::Vec::new();
+                    loop {
+                        if let Some(terminator) = #stream.#terminator(&[#terminators])#async_? {
+                            #consume
+                            break;
+                        }
+                        if #stream.#peek(1)#async_?.is_empty() {
+                            #at_eof
+                        }
+                        #inner
+                    }
+                });
+            }
             Instruction::LoopOutput(output, item) => {
                 let output = emit_register(*output);
                 let item = emit_register(*item);
@@ -429,6 +604,151 @@ fn prepare_decode(
                     #output.push(#item);
                 });
             }
+            Instruction::LoopExhaust(stream, output, inner) => {
+                let stream = emit_register(*stream);
+                let output = emit_register(*output);
+                let inner = prepare_decode(options, context, &inner[..], is_async, false);
+                statements.push(quote! {
+                    let mut #output = ::std::vec::Vec::new();
+                    while #stream.limit() > 0 {
+                        #inner
+                    }
+                });
+            }
+            Instruction::LoopUntil(output, item, done, inclusive, inner) => {
+                let output = emit_register(*output);
+                let item = emit_register(*item);
+                let done = emit_register(*done);
+                let inner = prepare_decode(options, context, &inner[..], is_async, false);
+                let keep = if *inclusive {
+                    quote! { #output.push(#item); }
+                } else {
+                    quote! {}
+                };
+                statements.push(quote! {
+                    let mut #output = ::std::vec::Vec::new();
+                    loop {
+                        #inner
+                        if #done {
+                            #keep
+                            break;
+                        }
+                        #output.push(#item);
+                    }
+                });
+            }
+            Instruction::DecodeRecords(target, header, tag, record, members, unknown, order) => {
+                let target = emit_target(target);
+                let header = prepare_decode(options, context, &header[..], is_async, false);
+                let tag = emit_register(*tag);
+                let record = emit_register(*record);
+                let unknown = emit_register(*unknown);
+                let mut outputs = vec![];
+                let mut arms = vec![];
+                for (tag_value, output, decoded, name, inner) in members {
+                    let tag_value = emit_register(*tag_value);
+                    let output = emit_register(*output);
+                    let decoded = emit_register(*decoded);
+                    let inner = prepare_decode(options, context, &inner[..], is_async, false);
+                    let message = format!("duplicate record for set member {}", name);
+                    outputs.push(quote! {
+                        let mut #output = None;
+                    });
+                    arms.push(quote! {
+                        if #tag == #tag_value {
+                            if #output.is_some() {
+                                return Err(decode_error(#message).into());
+                            }
+                            #inner
+                            #output = Some(#decoded);
+                        } else
+                    });
+                }
+                let outputs = flatten(outputs);
+                let arms = flatten(arms);
+                let (order_init, order_push) = match order {
+                    Some(order) => {
+                        let order = emit_register(*order);
+                        (
+                            quote! { let mut #order = ::std::vec::Vec::new(); },
+                            quote! { #order.push(#tag); },
+                        )
+                    }
+                    None => (quote! {}, quote! {}),
+                };
+                // a cursor would be both `Read` and `AsyncRead`, which makes reading it ambiguous
+                let buffered = if is_async {
+                    quote! { tokio::io::BufReader::new(Cursor::new(r)) }
+                } else {
+                    quote! { Cursor::new(r) }
+                };
+                statements.push(quote! {
+                    #outputs
+                    let mut #unknown = ::std::vec::Vec::new();
+                    #order_init
+                    {
+                        let mut r = vec![];
+                        #target.read_to_end(&mut r)#async_?;
+
+                        {
+                            let mut #target = #buffered;
+                            let #target = &mut #target;
+                            while !#target.fill_buf()#async_?.is_empty() {
+                                #header
+                                #arms {
+                                    let mut raw = vec![];
+                                    #record.read_to_end(&mut raw)#async_?;
+                                    #unknown.push((#tag, raw));
+                                }
+                                #order_push
+                                if #record.limit() > 0 {
+                                    return Err(decode_error("set record not fully decoded").into());
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+            Instruction::CollectMap(output, elements, key, duplicates) => {
+                let output = emit_register(*output);
+                let elements = emit_register(*elements);
+                let message = format!("duplicate map key {}", key);
+                let key = emit_ident(key);
+                let duplicate = match duplicates {
+                    DuplicateKeys::Error => quote! {
+                        if #output.contains_key(&key) {
+                            return Err(decode_error(#message).into());
+                        }
+                    },
+                    DuplicateKeys::First => quote! {
+                        if #output.contains_key(&key) {
+                            continue;
+                        }
+                    },
+                    DuplicateKeys::Last => quote! {},
+                };
+                statements.push(quote! {
+                    let mut #output = Map::new();
+                    for item in #elements {
+                        let key = item.#key.clone();
+                        #duplicate
+                        #output.insert(key, item);
+                    }
+                });
+            }
+            Instruction::BindElement(name, item, by_value) => {
+                let name = options.emit_field(name);
+                let item = emit_register(*item);
+                if *by_value {
+                    statements.push(quote! {
+                        let #name = #item;
+                    });
+                } else {
+                    statements.push(quote! {
+                        let #name = &#item;
+                    });
+                }
+            }
             Instruction::Conditional(target, interior, condition, inner) => {
                 let targets = target
                     .iter()
@@ -498,6 +818,56 @@ fn prepare_decode(
                     return Err(decode_error(#e).into());
                 });
             }
+            Instruction::VerifyChecksum(decoded, expected, message, optional) => {
+                let decoded = emit_register(*decoded);
+                let expected = emit_register(*expected);
+                let mismatch = if options.warn_on_checksum_mismatch {
+                    quote! {
+                        eprintln!("warning: {}", #message);
+                    }
+                } else {
+                    quote! {
+                        return Err(decode_error(#message).into());
+                    }
+                };
+                if *optional {
+                    statements.push(quote! {
+                        if let Some(decoded) = &#decoded {
+                            if *decoded != #expected {
+                                #mismatch
+                            }
+                        }
+                    });
+                } else {
+                    statements.push(quote! {
+                        if #decoded != #expected {
+                            #mismatch
+                        }
+                    });
+                }
+            }
+            Instruction::VerifyFixed(decoded, expected, message, show_values, slices) => {
+                let decoded = emit_register(*decoded);
+                let expected = emit_register(*expected);
+                let error = if *show_values {
+                    quote! {
+                        format!("{}: expected {:?}, got {:?}", #message, #expected, #decoded)
+                    }
+                } else {
+                    quote! { #message }
+                };
+                // a Rust array does not compare with a reference to another
+                let (decoded_value, expected_value) = if *slices {
+                    (quote! { #decoded[..] }, quote! { #expected[..] })
+                } else {
+                    (quote! { #decoded }, quote! { #expected })
+                };
+                statements.push(quote! {
+                    if #decoded_value != #expected_value {
+                        return Err(decode_error(#error).into());
+                    }
+                });
+            }
             Instruction::Skip(target, len) => {
                 let target = emit_target(target);
                 let len = emit_register(*len);
@@ -506,6 +876,22 @@ fn prepare_decode(
                     #target.read_exact(&mut big_scratch[..])#async_?;
                 });
             }
+            Instruction::Seek(target, offset, resume) => {
+                let target = emit_target(target);
+                let offset = emit_register(*offset);
+                let resume = emit_register(*resume);
+                statements.push(quote! {
+                    let #resume = #target.stream_position()#async_?;
+                    #target.seek(SeekFrom::Start(_ps_stream_base + (#offset as u64)))#async_?;
+                });
+            }
+            Instruction::Resume(target, resume) => {
+                let target = emit_target(target);
+                let resume = emit_register(*resume);
+                statements.push(quote! {
+                    #target.seek(SeekFrom::Start(#resume))#async_?;
+                });
+            }
         }
     }
 
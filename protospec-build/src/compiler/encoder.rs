@@ -1,12 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::*;
 use crate::coder::encode::*;
-use crate::{coder::*, map_async, Endian, EndianScalarType, ScalarType};
+use crate::{coder::*, map_async, Endian, EndianScalarType, ScalarType, StreamIntrinsic};
 
 fn emit_target(target: &Target) -> TokenStream {
     match target {
-        Target::Direct => quote! { writer },
+        Target::Direct => quote! { _ps_writer },
         Target::Stream(x) => emit_register(*x),
         Target::Buf(x) => {
             let buf = emit_register(*x);
@@ -15,11 +15,31 @@ fn emit_target(target: &Target) -> TokenStream {
     }
 }
 
-struct EncoderContext {
+/// Writes a scalar in the byte order of its type
+fn emit_scalar_write(
+    target: &TokenStream,
+    value: TokenStream,
+    type_: &EndianScalarType,
+    async_: &TokenStream,
+) -> TokenStream {
+    match type_.endian {
+        Endian::Little => quote! {
+            #target.write_all(&#value.to_le_bytes()[..])#async_?;
+        },
+        Endian::Big => quote! {
+            #target.write_all(&#value.to_be_bytes()[..])#async_?;
+        },
+    }
+}
+
+struct EncoderContext<'a> {
+    options: &'a CompileOptions,
     is_async: bool,
     resolved_refs: HashMap<String, usize>,
+    /// streams which may be the untransformed target, and so must not be shut down
+    conditional_streams: HashSet<usize>,
 }
-impl EncoderContext {
+impl EncoderContext<'_> {
     fn prepare_encode(&mut self, instructions: &[Instruction], is_root: bool) -> TokenStream {
         let async_ = map_async(self.is_async);
         let mut statements = vec![];
@@ -40,7 +60,7 @@ impl EncoderContext {
             match instruction {
                 Instruction::Eval(target, expr) => {
                     let target = emit_register(*target);
-                    let value = emit_expression(expr, &|f: &Arc<Field>| {
+                    let value = emit_expression(expr, self.options, &|f: &Arc<Field>| {
                         let register = self
                             .resolved_refs
                             .get(&*f.name)
@@ -60,7 +80,7 @@ impl EncoderContext {
                                 quote! { &#source }
                             }
                             FieldRef::Name(name) => {
-                                let name = format_ident!("{}", name);
+                                let name = emit_ident(name);
                                 quote! { #source.#name }
                             }
                             FieldRef::ArrayAccess(index) => {
@@ -68,8 +88,12 @@ impl EncoderContext {
                                 quote! { #source[#index as usize] }
                             }
                             FieldRef::TupleAccess(x) => {
+                                let x = syn::Index::from(*x);
                                 quote! { #source.#x }
                             }
+                            FieldRef::Unmap(type_, optional) => {
+                                emit_unmapped(source, type_, *optional, self.options)
+                            }
                         };
                     }
                     statements.push(quote! {
@@ -81,13 +105,13 @@ impl EncoderContext {
                     let len = emit_register(*len);
                     statements.push(quote! {
                         //todo: strictly bound this
-                        let mut #buf: Vec<u8> = Vec::with_capacity(#len as usize);
+                        let mut #buf: ::std::vec::Vec<u8> = ::std::vec::Vec::with_capacity(#len as usize);
                     });
                 }
                 Instruction::AllocDynBuf(buf) => {
                     let buf = emit_register(*buf);
                     statements.push(quote! {
-                        let mut #buf: Vec<u8> = Vec::new();
+                        let mut #buf: ::std::vec::Vec<u8> = ::std::vec::Vec::new();
                     });
                 }
                 Instruction::Loop(index, stop_index, inner) => {
@@ -100,19 +124,162 @@ impl EncoderContext {
                         }
                     });
                 }
+                Instruction::AppendTerminator(
+                    source,
+                    type_,
+                    name,
+                    ended,
+                    predicate,
+                    terminator,
+                    instructions,
+                ) => {
+                    let source = emit_register(*source);
+                    let type_ref = emit_type_ref(type_, self.options);
+                    let name = self.options.emit_field(name);
+                    let ended = emit_register(*ended);
+                    let predicate = self.prepare_encode(&predicate[..], false);
+                    let terminator = emit_register(*terminator);
+                    let instructions = self.prepare_encode(&instructions[..], false);
+                    let (deref, ref_) = if type_.copyable() {
+                        (quote! { * }, quote! {})
+                    } else {
+                        (quote! {}, quote! { & })
+                    };
+                    let early_message = format!(
+                        "element of {} array ends the array before its last element",
+                        type_
+                    );
+                    let message = format!("default value of {} does not end the array", type_);
+                    statements.push(quote! {
+                        let mut _ps_terminated = false;
+                        for (_ps_index, _ps_element) in #source.iter().enumerate() {
+                            let #name = #deref _ps_element;
+                            #predicate
+                            if #ended {
+                                if _ps_index + 1 < #source.len() {
+                                    return Err(encode_error(#early_message).into());
+                                }
+                                _ps_terminated = true;
+                            }
+                        }
+                        let #terminator: ::core::option::Option<#type_ref> = if _ps_terminated {
+                            None
+                        } else {
+                            let #terminator: #type_ref = Default::default();
+                            let #name = #ref_ #terminator;
+                            #predicate
+                            if !#ended {
+                                return Err(encode_error(#message).into());
+                            }
+                            Some(#terminator)
+                        };
+                        if let Some(#terminator) = #ref_ #terminator {
+                            #instructions
+                        }
+                    });
+                }
+                Instruction::EncodeRecords(
+                    target,
+                    order,
+                    unknown,
+                    members,
+                    tag_type,
+                    length_type,
+                ) => {
+                    let target = emit_target(target);
+                    let unknown = emit_register(*unknown);
+                    let length_cast = emit_ident(&length_type.scalar.to_string());
+                    let write_tag = emit_scalar_write(&target, quote! { tag }, tag_type, &async_);
+                    let write_length =
+                        emit_scalar_write(&target, quote! { length }, length_type, &async_);
+                    let write_unknown = quote! {
+                        let length: #length_cast = raw.len().try_into().map_err(|_| encode_error("length out of range"))?;
+                        #write_tag
+                        #write_length
+                        #target.write_all(&raw[..])#async_?;
+                    };
+                    let records = members
+                        .iter()
+                        .map(|(_, inner)| self.prepare_encode(&inner[..], false))
+                        .collect::<Vec<_>>();
+                    if let Some(order) = order {
+                        let order = emit_register(*order);
+                        let count = members.len();
+                        let mut arms = vec![];
+                        let mut rest = vec![];
+                        for (i, ((tag_value, _), record)) in members.iter().zip(records).enumerate()
+                        {
+                            let tag_value = emit_register(*tag_value);
+                            arms.push(quote! {
+                                if *tag == #tag_value {
+                                    if !written[#i] {
+                                        written[#i] = true;
+                                        #record
+                                    }
+                                    continue;
+                                }
+                            });
+                            rest.push(quote! {
+                                if !written[#i] {
+                                    #record
+                                }
+                            });
+                        }
+                        let arms = flatten(arms);
+                        let rest = flatten(rest);
+                        // records are written in the order they were read, members not read last
+                        statements.push(quote! {
+                            let mut written = [false; #count];
+                            let mut unknown_records = #unknown.iter();
+                            for tag in #order.iter() {
+                                #arms
+                                if let Some((tag, raw)) = unknown_records.next() {
+                                    #write_unknown
+                                }
+                            }
+                            #rest
+                            for (tag, raw) in unknown_records {
+                                #write_unknown
+                            }
+                        });
+                    } else {
+                        let records = flatten(records);
+                        statements.push(quote! {
+                            #records
+                            for (tag, raw) in #unknown.iter() {
+                                #write_unknown
+                            }
+                        });
+                    }
+                }
+                Instruction::MapValues(values, map) => {
+                    let values = emit_register(*values);
+                    let map = emit_register(*map);
+                    statements.push(quote! {
+                        let #values = #map.values().collect::<Vec<_>>();
+                    });
+                }
                 Instruction::GetLen(len, source, cast_type) => {
                     let len = emit_register(*len);
                     let source = emit_register(*source);
-                    let cast = if let Some(cast_type) = cast_type {
+                    if let Some(cast_type) = cast_type {
                         let cast_type = emit_ident(&cast_type.to_string());
-                        quote! {
-                            as #cast_type
-                        }
+                        statements.push(quote! {
+                            let #len: #cast_type = #source.len().try_into().map_err(|_| encode_error("length out of range"))?;
+                        });
                     } else {
-                        quote! {}
-                    };
+                        statements.push(quote! {
+                            let #len = #source.len();
+                        });
+                    }
+                }
+                Instruction::CheckLen(len, expected, message) => {
+                    let len = emit_register(*len);
+                    let expected = emit_register(*expected);
                     statements.push(quote! {
-                        let #len = #source.len() #cast;
+                        if #len != #expected as u64 {
+                            return Err(encode_error(#message).into());
+                        }
                     });
                 }
                 Instruction::NullCheck(target, destination, is_copyable, message) => {
@@ -146,9 +313,18 @@ impl EncoderContext {
                 }
                 Instruction::Drop(register) => {
                     let register = emit_register(*register);
-                    statements.push(quote! {
-                        drop(#register);
-                    });
+                    if self.is_async {
+                        // async writers can't finish their output on drop
+                        statements.push(quote! {
+                            if let Some(mut stream) = #register.take() {
+                                stream.shutdown().await?;
+                            }
+                        });
+                    } else {
+                        statements.push(quote! {
+                            drop(#register);
+                        });
+                    }
                 }
                 Instruction::WrapStream(stream, new_stream, transformer, args) => {
                     let new_stream_value = emit_register(*new_stream);
@@ -176,11 +352,19 @@ impl EncoderContext {
                     ));
                 }
                 Instruction::EndStream(stream) => {
+                    let is_conditional = self.conditional_streams.contains(stream);
                     let stream = emit_register(*stream);
-                    statements.push(quote! {
-                        #stream.flush()#async_?;
-                        drop(#stream);
-                    });
+                    if self.is_async && !is_conditional {
+                        statements.push(quote! {
+                            #stream.shutdown().await?;
+                            drop(#stream);
+                        });
+                    } else {
+                        statements.push(quote! {
+                            #stream.flush()#async_?;
+                            drop(#stream);
+                        });
+                    }
                 }
                 Instruction::EmitBuf(target, buf) => {
                     let target = emit_target(target);
@@ -189,7 +373,7 @@ impl EncoderContext {
                         #target.write_all(&#buf[..])#async_?;
                     });
                 }
-                Instruction::EncodeRef(target, source, args) => {
+                Instruction::EncodeRef(target, source, args, region) => {
                     let mut out_arguments = vec![];
                     for argument in args {
                         let value = emit_register(*argument);
@@ -198,7 +382,13 @@ impl EncoderContext {
                     let out_arguments = flatten(out_arguments);
                     let target = emit_target(target);
                     let source = emit_register(*source);
-                    if self.is_async {
+                    if let Some(region) = region {
+                        // seekable types are always encoded in memory
+                        let region = emit_register(*region);
+                        statements.push(quote! {
+                            #source.encode_sync_at(#target, _ps_layout, #region #out_arguments)?;
+                        });
+                    } else if self.is_async {
                         statements.push(quote! {
                             #source.encode_async(#target #out_arguments).await?;
                         });
@@ -260,7 +450,8 @@ impl EncoderContext {
                         #target.write_all(&#data.to_be_bytes()[..])#async_?;
                     });
                 }
-                Instruction::EncodePrimitiveArray(target, data, type_, len) | Instruction::EncodeReprArray(target, data, type_, len) => {
+                Instruction::EncodePrimitiveArray(target, data, type_, len)
+                | Instruction::EncodeReprArray(target, data, type_, len) => {
                     let target = emit_target(target);
                     let data = emit_register(*data);
                     let writing = match type_ {
@@ -326,6 +517,7 @@ impl EncoderContext {
                     transformer,
                     args,
                 ) => {
+                    self.conditional_streams.insert(*new_stream);
                     let condition = emit_register(*condition);
                     let new_stream_value = emit_register(*new_stream);
                     let owned_new_stream = emit_register(*owned_new_stream);
@@ -355,15 +547,36 @@ impl EncoderContext {
                         };
                     })
                 }
-                Instruction::UnwrapEnum(enum_name, discriminant, original, checked, message) => {
-                    let enum_name = emit_ident(enum_name);
+                Instruction::IsVariant(dest, enum_name, discriminant, original) => {
+                    let dest = emit_register(*dest);
+                    let enum_name = emit_type_path(enum_name);
+                    let discriminant = emit_ident(discriminant);
+                    let original = emit_register(*original);
+                    statements.push(quote! {
+                        let #dest = matches!(&#original, #enum_name::#discriminant { .. });
+                    });
+                }
+                Instruction::UnwrapEnum(
+                    enum_name,
+                    discriminant,
+                    original,
+                    checked,
+                    do_copy,
+                    message,
+                ) => {
+                    let enum_name = emit_type_path(enum_name);
                     let discriminant = emit_ident(discriminant);
                     let original = emit_register(*original);
                     let checked = emit_register(*checked);
+                    let unwrapped = if *do_copy {
+                        quote! { *#checked }
+                    } else {
+                        quote! { #checked }
+                    };
 
                     statements.push(quote! {
                         let #checked = if let #enum_name::#discriminant(#checked) = &#original {
-                            #checked
+                            #unwrapped
                         } else {
                             return Err(encode_error(#message).into())
                         };
@@ -376,7 +589,7 @@ impl EncoderContext {
                     checked,
                     message,
                 ) => {
-                    let enum_name = emit_ident(enum_name);
+                    let enum_name = emit_type_path(enum_name);
                     let discriminant = emit_ident(discriminant);
                     let original = emit_register(*original);
                     // let checked = emit_register(*checked);
@@ -427,9 +640,149 @@ impl EncoderContext {
                         #target.write_all(&vec![0u8; #length as usize][..])#async_?;
                     });
                 }
+                Instruction::Constrain(value, name, constraint, message) => {
+                    let value = emit_register(*value);
+                    let constraint =
+                        emit_expression(constraint, self.options, &|f: &Arc<Field>| {
+                            if &f.name == name {
+                                return value.clone();
+                            }
+                            let register = self
+                                .resolved_refs
+                                .get(&*f.name)
+                                .expect("failed to dereference");
+                            emit_register(*register)
+                        });
+                    statements.push(quote! {
+                        if !(#constraint) {
+                            return Err(encode_error(#message).into());
+                        }
+                    });
+                }
+                Instruction::CheckPeek(buf, condition, len, message) => {
+                    let buf = emit_register(*buf);
+                    let len = *len as usize;
+                    let condition = emit_expression(condition, self.options, &|f: &Arc<Field>| {
+                        let register = self
+                            .resolved_refs
+                            .get(&*f.name)
+                            .expect("failed to dereference");
+                        emit_register(*register)
+                    });
+                    // bytes peeked beyond the encoding belong to what follows, which is not known here
+                    statements.push(quote! {
+                        if #buf.len() >= #len {
+                            let stream_peek = &#buf[..];
+                            if !(#condition) {
+                                return Err(encode_error(#message).into());
+                            }
+                        }
+                    });
+                }
                 Instruction::SetRef(name, value) => {
                     self.resolved_refs.insert(name.clone(), *value);
                 }
+                Instruction::IsSome(target, source) => {
+                    let target = emit_register(*target);
+                    let source = emit_register(*source);
+                    statements.push(quote! {
+                        let #target = #source.is_some();
+                    });
+                }
+                Instruction::CountStream(stream, new_stream) => {
+                    let stream = emit_target(stream);
+                    let new_stream = emit_register(*new_stream);
+                    statements.push(quote! {
+                        let mut #new_stream = CountingStream::new(#stream);
+                        let #new_stream = &mut #new_stream;
+                    });
+                }
+                Instruction::BindPosition(stream, len, pending, intrinsics) => {
+                    let stream = emit_register(*stream);
+                    let pending = flatten(pending.iter().map(|(buf, size)| {
+                        let buf = emit_register(*buf);
+                        quote! { + #size + #buf.len() as u64 }
+                    }));
+                    // bytes of pending auto fields count towards the offset, though not yet written
+                    statements.push(quote! {
+                        let stream_offset = #stream.count #pending;
+                    });
+                    if intrinsics.contains(&StreamIntrinsic::Remaining) {
+                        let len = emit_register(len.expect("remaining() without length"));
+                        statements.push(quote! {
+                            let stream_remaining = (#len as u64).saturating_sub(stream_offset);
+                        });
+                    }
+                }
+                Instruction::BindRegion(target) => {
+                    let target = emit_register(*target);
+                    statements.push(quote! {
+                        let #target = _ps_region;
+                    });
+                }
+                Instruction::WritePlaceholder(target, condition, region, patch, size) => {
+                    let target = emit_target(target);
+                    let condition = condition
+                        .map(emit_register)
+                        .unwrap_or_else(|| quote! { true });
+                    let region = emit_register(*region);
+                    let patch = emit_register(*patch);
+                    let size = *size as usize;
+                    statements.push(quote! {
+                        let #patch = if #condition {
+                            let at = #target.stream_position()?;
+                            #target.write_all(&[0u8; #size])?;
+                            Some((#region, at))
+                        } else {
+                            None
+                        };
+                    });
+                }
+                Instruction::ReserveRegion(target) => {
+                    let target = emit_register(*target);
+                    statements.push(quote! {
+                        let #target = _ps_layout.reserve();
+                    });
+                }
+                Instruction::PatchPointer(patch, region, type_) => {
+                    let patch = emit_register(*patch);
+                    let region = emit_register(*region);
+                    let scalar = emit_ident(&type_.scalar.to_string());
+                    let encoder = if type_.is_little_endian() {
+                        quote! { to_le_bytes }
+                    } else {
+                        quote! { to_be_bytes }
+                    };
+                    statements.push(quote! {
+                        if let Some((patch_region, patch_at)) = #patch {
+                            _ps_layout.patch(patch_region, patch_at, #region, |position: u64| -> Result<::std::vec::Vec<u8>> {
+                                let position: #scalar = position.try_into().map_err(|_| encode_error("pointer offset out of range"))?;
+                                Ok(position.#encoder().to_vec())
+                            });
+                        }
+                    });
+                }
+                Instruction::PlaceRegion(region, offset) => {
+                    let region = emit_register(*region);
+                    let offset = emit_register(*offset);
+                    statements.push(quote! {
+                        _ps_layout.place_at(#region, #offset as u64);
+                    });
+                }
+                Instruction::EncodeRegion(region, instructions) => {
+                    let region = emit_register(*region);
+                    let inner = self.prepare_encode(&instructions[..], false);
+                    statements.push(quote! {
+                        {
+                            let mut _ps_writer = Cursor::new(::std::vec::Vec::new());
+                            {
+                                let _ps_writer = &mut _ps_writer;
+                                #inner
+                            }
+                            _ps_layout.fill(#region, _ps_writer.into_inner());
+                        }
+                    });
+                }
                 Instruction::GetRef(target, name) => {
                     let target = emit_register(*target);
                     let value =
@@ -448,10 +801,12 @@ impl EncoderContext {
     }
 }
 
-pub fn prepare_encoder(coder: &Context, is_async: bool) -> TokenStream {
+pub fn prepare_encoder(options: &CompileOptions, coder: &Context, is_async: bool) -> TokenStream {
     let mut context = EncoderContext {
+        options,
         is_async,
         resolved_refs: Default::default(),
+        conditional_streams: Default::default(),
     };
     let decode_sync = context.prepare_encode(&coder.instructions[..], true);
     let base = emit_register(0);
@@ -197,8 +197,12 @@ pub fn eval_const_expression(expr: &Expression) -> Option<ConstValue> {
             })
         }
         Member(c) => {
+            let flag = match &c.member {
+                crate::asg::Member::Flag(flag) => flag,
+                crate::asg::Member::Field(_) => return None,
+            };
             let target = eval_const_expression(&c.target)?;
-            let member = eval_const_expression(&c.member.value)?;
+            let member = eval_const_expression(&flag.value)?;
             match (target, member) {
                 (ConstValue::Int(target), ConstValue::Int(member)) => {
                     // todo: find better way to get same-typed zero
@@ -219,18 +223,9 @@ pub fn eval_const_expression(expr: &Expression) -> Option<ConstValue> {
             let inner = eval_const_expression(&c.inner)?;
             inner.cast_to(&c.type_)
         }
-        ArrayIndex(_) => {
-            unimplemented!("can not use array indexing in const")
-        }
         EnumAccess(c) => eval_const_expression(&c.variant.value),
         Int(c) => Some(ConstValue::Int(c.value)),
         ConstRef(c) => eval_const_expression(&c.value),
-        InputRef(_) => {
-            unimplemented!("cannot access input in constant");
-        }
-        FieldRef(_) => {
-            unimplemented!("cannot access field in constant");
-        }
         Str(c) => Some(ConstValue::String(c.content.clone())),
         Ternary(c) => {
             let condition = eval_const_expression(&c.condition)?;
@@ -244,21 +239,25 @@ pub fn eval_const_expression(expr: &Expression) -> Option<ConstValue> {
             }
         }
         Bool(c) => Some(ConstValue::Bool(*c)),
-        Call(_) => {
-            unimplemented!("cannot call ffi in constant");
-        }
+        // sizes are known once the program is fully converted
+        SizeOf(c) => match c.target.encoded_size.get()? {
+            EncodedSize::Fixed(size) => Some(ConstValue::Int(ConstInt::U64(size))),
+            _ => None,
+        },
+        ArrayIndex(_) | InputRef(_) | FieldRef(_) | Call(_) | Tag(_) => None,
     }
 }
 
 pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
     expr: &Expression,
+    options: &CompileOptions,
     ref_resolver: &F,
 ) -> TokenStream {
     use Expression::*;
     match expr {
         Binary(c) => {
-            let left = emit_expression(&c.left, ref_resolver);
-            let right = emit_expression(&c.right, ref_resolver);
+            let left = emit_expression(&c.left, options, ref_resolver);
+            let right = emit_expression(&c.right, options, ref_resolver);
             if c.op == BinaryOp::Elvis {
                 quote! {
                     (#left).unwrap_or(#right)
@@ -292,14 +291,25 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
         }
         Member(c) => {
-            let target = emit_expression(&c.target, ref_resolver);
-            let get_member = format_ident!("{}", c.member.name.to_snake());
-            quote! {
-                #target.#get_member()
+            let target = emit_expression(&c.target, options, ref_resolver);
+            let member = match &c.member {
+                crate::asg::Member::Flag(_) => emit_ident(&c.member.name().to_snake()),
+                crate::asg::Member::Field(field) => options.emit_member(field),
+            };
+            match &c.member {
+                crate::asg::Member::Flag(_) => quote! {
+                    #target.#member()
+                },
+                crate::asg::Member::Field(field) if field.type_.borrow().copyable() => quote! {
+                    #target.#member
+                },
+                crate::asg::Member::Field(_) => quote! {
+                    #target.#member.clone()
+                },
             }
         }
         Unary(c) => {
-            let inner = emit_expression(&c.inner, ref_resolver);
+            let inner = emit_expression(&c.inner, options, ref_resolver);
             let op = match c.op {
                 UnaryOp::Negate => quote! { - },
                 UnaryOp::Not => quote! { ! },
@@ -310,8 +320,8 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
         }
         Cast(c) => {
-            let inner = emit_expression(&c.inner, ref_resolver);
-            let target = emit_type_ref(&c.type_);
+            let inner = emit_expression(&c.inner, options, ref_resolver);
+            let target = emit_type_ref(&c.type_, &CompileOptions::default());
             match &*c.inner.get_type().unwrap().resolved() {
                 Type::Enum(_) => {
                     quote! {
@@ -331,16 +341,16 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
         }
         ArrayIndex(c) => {
-            let array = emit_expression(&c.array, ref_resolver);
-            let index = emit_expression(&c.index, ref_resolver);
+            let array = emit_expression(&c.array, options, ref_resolver);
+            let index = emit_expression(&c.index, options, ref_resolver);
 
             quote! {
                 (#array)[#index]
             }
         }
         EnumAccess(c) => {
-            let enum_name = emit_ident(&c.enum_field.name);
-            let enum_variant_name = emit_ident(&c.variant.name);
+            let enum_name = options.emit_type_path(&c.enum_field);
+            let enum_variant_name = options.emit_enum_variant(&c.variant.name);
             quote! {
                 #enum_name::#enum_variant_name
             }
@@ -361,13 +371,13 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
         }
         ConstRef(c) => {
-            let c = format_ident!("{}", c.name);
+            let c = emit_ident(&c.name);
             quote! {
                 #c
             }
         }
         InputRef(c) => {
-            let c = format_ident!("{}", c.name);
+            let c = options.emit_field(&c.name);
             quote! {
                 #c
             }
@@ -380,9 +390,9 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
         }
         Ternary(c) => {
-            let condition = emit_expression(&c.condition, ref_resolver);
-            let if_true = emit_expression(&c.if_true, ref_resolver);
-            let if_false = emit_expression(&c.if_false, ref_resolver);
+            let condition = emit_expression(&c.condition, options, ref_resolver);
+            let if_true = emit_expression(&c.if_true, options, ref_resolver);
+            let if_false = emit_expression(&c.if_false, options, ref_resolver);
 
             quote! {
                 if #condition {
@@ -400,7 +410,7 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
         Call(c) => {
             let mut arguments = vec![];
             for argument in &c.arguments {
-                let expression = emit_expression(argument, ref_resolver);
+                let expression = emit_expression(argument, options, ref_resolver);
                 arguments.push(FFIArgumentValue {
                     type_: argument.get_type().expect("missing type in ffi argument"),
                     present: true,
@@ -416,5 +426,56 @@ pub fn emit_expression<F: Fn(&Arc<Field>) -> TokenStream>(
             }
             c.function.inner.call(&arguments[..])
         }
+        Tag(c) => {
+            let target = ref_resolver(&c.target);
+            let type_ref = match &*c.target.type_.borrow() {
+                Type::Ref(type_ref) => type_ref.clone(),
+                _ => unreachable!("tag of a field not referencing a match container"),
+            };
+            let container = match &*type_ref.target.type_.borrow() {
+                Type::Container(container) => container.clone(),
+                _ => unreachable!("tag of a field not referencing a match container"),
+            };
+            let enum_name = options.emit_type_path(&type_ref.target);
+            let arms = container.items.iter().map(|(variant, field)| {
+                let variant_name = options.emit_variant(field);
+                match container.tags.get(variant) {
+                    Some(tag) => {
+                        let tag = emit_expression(tag, options, ref_resolver);
+                        quote! {
+                            #enum_name::#variant_name { .. } => #tag,
+                        }
+                    }
+                    None if is_unknown_variant(field) => quote! {
+                        #enum_name::#variant_name { tag, .. } => tag.clone(),
+                    },
+                    None => {
+                        let message = format!(
+                            "cannot derive the tag of {}::{}, which is not selected by a value",
+                            type_ref.target.name, variant
+                        );
+                        quote! {
+                            #enum_name::#variant_name { .. } => return Err(encode_error(#message).into()),
+                        }
+                    }
+                }
+            });
+            quote! {
+                match &#target {
+                    #(#arms)*
+                }
+            }
+        }
+        SizeOf(c) => match c.target.encoded_size() {
+            EncodedSize::Fixed(size) => quote! { #size },
+            _ => unreachable!("sizeof a type without a fixed size"),
+        },
+    }
+}
+
+fn is_unknown_variant(variant: &Field) -> bool {
+    match &*variant.type_.borrow() {
+        Type::Container(container) => container.items.values().any(|x| x.is_tag.get()),
+        _ => false,
     }
 }
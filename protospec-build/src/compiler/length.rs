@@ -0,0 +1,271 @@
+use super::*;
+use std::cell::Cell;
+
+/// Emits an expression of the `usize` encoded length of `value`, a reference to a value of `type_`, if it can be computed without encoding it
+fn emit_type_len<F: Fn(&Field) -> TokenStream>(
+    type_: &Type,
+    value: TokenStream,
+    options: &CompileOptions,
+    access: &F,
+) -> Option<TokenStream> {
+    if let EncodedSize::Fixed(size) = type_.encoded_size() {
+        let size = size as usize;
+        return Some(quote! { #size });
+    }
+    match type_ {
+        Type::Ref(type_ref) => {
+            let arguments = emit_ref_arguments(&type_ref.arguments, options, access)?;
+            if let Type::Foreign(foreign) = &*type_ref.target.type_.borrow() {
+                return foreign.obj.encoded_len_gen(value, arguments);
+            }
+            // arguments left to their default are passed by the encoder as given
+            if type_ref
+                .target
+                .arguments
+                .borrow()
+                .iter()
+                .any(|argument| argument.default_value.is_some())
+            {
+                return None;
+            }
+            let arguments = flatten_separated(arguments, quote! { , });
+            Some(quote! { #value.encoded_len(#arguments)? })
+        }
+        Type::Foreign(foreign) => foreign.obj.encoded_len_gen(value, vec![]),
+        Type::Array(array) => {
+            if array.length.until.is_some() {
+                return None;
+            }
+            let terminator = if array.length.expandable && !array.length.terminator.leave {
+                match array.length.value.as_ref().map(eval_const_expression) {
+                    None => 0,
+                    Some(Some(ConstValue::String(terminator))) => terminator.len(),
+                    Some(_) => return None,
+                }
+            } else {
+                0
+            };
+            let len = match array.element.encoded_size() {
+                EncodedSize::Fixed(size) => {
+                    let size = size as usize;
+                    quote! { #value.len() * #size }
+                }
+                _ => {
+                    let elements = if array.map.is_some() {
+                        quote! { #value.values() }
+                    } else {
+                        quote! { #value.iter() }
+                    };
+                    let element =
+                        emit_type_len(&array.element, quote! { element }, options, access)?;
+                    quote! {
+                        {
+                            let mut len = 0usize;
+                            for element in #elements {
+                                len += #element;
+                            }
+                            len
+                        }
+                    }
+                }
+            };
+            Some(quote! { (#len + #terminator) })
+        }
+        _ => None,
+    }
+}
+
+/// Emits the arguments of a type reference, unless they read calculated fields, whose values are only known while encoding, or fields held as a mapped type
+fn emit_ref_arguments<F: Fn(&Field) -> TokenStream>(
+    arguments: &[Expression],
+    options: &CompileOptions,
+    access: &F,
+) -> Option<Vec<TokenStream>> {
+    let calculated = Cell::new(false);
+    let resolver = |field: &Arc<Field>| {
+        if field.calculated.borrow().is_some() || options.mapped_type(field).is_some() {
+            calculated.set(true);
+        }
+        let value = access(field);
+        quote! { (*#value) }
+    };
+    let mut out = vec![];
+    for argument in arguments {
+        if !argument.stream_intrinsics().is_empty() {
+            return None;
+        }
+        out.push(emit_expression(argument, options, &resolver));
+    }
+    if calculated.get() {
+        return None;
+    }
+    Some(out)
+}
+
+/// Emits the summed encoded lengths of the items of a struct container, accessed as references through `access`
+fn container_len<F: Fn(&Field) -> TokenStream>(
+    container: &ContainerType,
+    options: &CompileOptions,
+    access: &F,
+    conditional: bool,
+    lens: &mut Vec<TokenStream>,
+) -> Option<()> {
+    if container.set.is_some() {
+        return None;
+    }
+    for field in container.items.values() {
+        if field.is_tag.get() {
+            continue;
+        }
+        // fields held as a mapped type are measured by encoding them
+        if field.pointer.borrow().is_some()
+            || !field.transforms.borrow().is_empty()
+            || options.mapped_type(field).is_some()
+        {
+            return None;
+        }
+        let conditional = conditional || field.condition.borrow().is_some();
+        let type_ = field.type_.borrow();
+        if let Type::Container(sub_container) = &*type_ {
+            container_len(sub_container, options, access, conditional, lens)?;
+            continue;
+        }
+        // fields which are not held by the value are evaluated while encoding
+        if field.is_pad.get() || field.is_fixed.get() || field.calculated.borrow().is_some() {
+            if conditional {
+                return None;
+            }
+            let size = if field.is_pad.get() {
+                match &*type_ {
+                    Type::Array(array) => array.fixed_length(),
+                    _ => None,
+                }
+            } else {
+                match type_.encoded_size() {
+                    EncodedSize::Fixed(size) => Some(size as usize),
+                    _ => None,
+                }
+            };
+            let size = size?;
+            lens.push(quote! { #size });
+            continue;
+        }
+        let place = access(field);
+        if conditional {
+            let len = emit_type_len(&type_, quote! { value }, options, access)?;
+            lens.push(quote! {
+                match #place {
+                    Some(value) => #len,
+                    None => 0,
+                }
+            });
+        } else {
+            lens.push(emit_type_len(&type_, place, options, access)?);
+        }
+    }
+    Some(())
+}
+
+fn sum(lens: Vec<TokenStream>) -> TokenStream {
+    if lens.is_empty() {
+        return quote! { 0usize };
+    }
+    flatten_separated(lens, quote! { + })
+}
+
+/// Emits the body of `encoded_len`, walking the value if its layout allows, or `None` if it must be encoded to be measured
+fn prepare_encoded_len_walk(field: &Arc<Field>, options: &CompileOptions) -> Option<TokenStream> {
+    if field.is_seekable.get() || field.condition.borrow().is_some() {
+        return None;
+    }
+    if !field.transforms.borrow().is_empty() {
+        return None;
+    }
+    let type_ = field.type_.borrow();
+    let container = match &*type_ {
+        Type::Container(container) => container,
+        type_ => {
+            let len = emit_type_len(type_, quote! { (&self.0) }, options, &|field: &Field| {
+                let name = options.emit_member(field);
+                quote! { (&self.#name) }
+            })?;
+            return Some(quote! { Ok(#len) });
+        }
+    };
+    if !container.is_enum.get() {
+        let mut lens = vec![];
+        container_len(
+            container,
+            options,
+            &|field: &Field| {
+                let name = options.emit_member(field);
+                quote! { (&self.#name) }
+            },
+            false,
+            &mut lens,
+        )?;
+        let len = sum(lens);
+        return Some(quote! { Ok(#len) });
+    }
+    let mut arms = vec![];
+    let access = |field: &Field| options.emit_field(&field.name);
+    for (name, child) in container.items.iter() {
+        if child.pointer.borrow().is_some() || !child.transforms.borrow().is_empty() {
+            return None;
+        }
+        let variant = options.emit_variant(child);
+        match &*child.type_.borrow() {
+            Type::Container(sub_container) => {
+                let mut lens = vec![];
+                container_len(sub_container, options, &access, false, &mut lens)?;
+                let len = sum(lens);
+                let bindings = flatten_separated(
+                    sub_container
+                        .flatten_view()
+                        .filter(|(_, x)| !x.is_pad.get() && !x.is_fixed.get())
+                        .map(|(_, x)| options.emit_binding(&x)),
+                    quote! { , },
+                );
+                arms.push(quote! {
+                    Self::#variant { #bindings } => #len,
+                });
+            }
+            type_ => {
+                let ident = options.emit_field(name);
+                let len = emit_type_len(type_, quote! { #ident }, options, &access)?;
+                arms.push(quote! {
+                    Self::#variant(#ident) => #len,
+                });
+            }
+        }
+    }
+    let arms = flatten(arms);
+    Some(quote! {
+        Ok(match self {
+            #arms
+        })
+    })
+}
+
+/// Emits the body of `encoded_len`, which is the encoded length of `self` given the arguments of `encode_sync`
+pub fn prepare_encoded_len(
+    field: &Arc<Field>,
+    options: &CompileOptions,
+    redefaults: &TokenStream,
+) -> TokenStream {
+    if let EncodedSize::Fixed(_) = field.encoded_size() {
+        return quote! { Ok(Self::ENCODED_SIZE) };
+    }
+    if let Some(walk) = prepare_encoded_len_walk(field, options) {
+        return quote! {
+            #redefaults
+            #walk
+        };
+    }
+    let argument_names = emit_argument_names(field, options);
+    quote! {
+        let mut counter = CountingStream::new(std::io::sink());
+        self.encode_sync(&mut counter #argument_names)?;
+        Ok(counter.count as usize)
+    }
+}
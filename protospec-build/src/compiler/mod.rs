@@ -1,8 +1,9 @@
 use crate::asg::*;
 use crate::coder;
-use crate::{BinaryOp, UnaryOp};
+use crate::{BinaryOp, StdResult, UnaryOp};
 use case::CaseExt;
 use expr::*;
+pub(crate) use expr::{eval_const_expression, ConstValue};
 use proc_macro2::TokenStream;
 use quote::TokenStreamExt;
 use quote::{format_ident, quote};
@@ -11,9 +12,72 @@ use std::{sync::Arc, unimplemented};
 mod decoder;
 mod encoder;
 mod expr;
+mod length;
+mod naming;
+pub use naming::*;
+mod runtime;
+mod type_map;
+use type_map::*;
+mod validate;
+
+/// The traits a type declaration derives on top of the configured ones through `@derive(Trait, ...)`
+fn extra_derives(field: &Field) -> Vec<&str> {
+    field
+        .attributes_named("derive")
+        .filter_map(Attribute::name_arguments)
+        .flatten()
+        .collect()
+}
+
+/// Emits the arguments of `@rust(...)` attributes as Rust attributes, i.e. `#[serde(rename = "name")]` for `@rust(serde(rename = "name"))`
+fn emit_rust_attributes(field: &Field) -> TokenStream {
+    flatten(
+        field
+            .attributes_named("rust")
+            .flat_map(|attribute| attribute.arguments.iter().flatten())
+            .map(|argument| {
+                let argument = emit_attribute_argument(argument);
+                quote! { #[#argument] }
+            }),
+    )
+}
 
-pub fn global_name(input: &str) -> String {
-    input.to_string()
+fn emit_attribute_argument(argument: &AttributeArgument) -> TokenStream {
+    match argument {
+        AttributeArgument::Attribute(attribute) => {
+            let name = emit_ident(&attribute.name);
+            match &attribute.arguments {
+                Some(arguments) => {
+                    let arguments = flatten_separated(
+                        arguments.iter().map(emit_attribute_argument),
+                        quote! { , },
+                    );
+                    quote! { #name(#arguments) }
+                }
+                None => name,
+            }
+        }
+        AttributeArgument::Value(name, value) => {
+            let name = emit_ident(name);
+            let value = emit_attribute_literal(value);
+            quote! { #name = #value }
+        }
+        AttributeArgument::Literal(value) => emit_attribute_literal(value),
+    }
+}
+
+fn emit_attribute_literal(value: &AttributeLiteral) -> TokenStream {
+    match value {
+        AttributeLiteral::Int(value) => {
+            let value = proc_macro2::Literal::u64_unsuffixed(*value);
+            quote! { #value }
+        }
+        AttributeLiteral::Str(value) => {
+            let value = String::from_utf8_lossy(value);
+            quote! { #value }
+        }
+        AttributeLiteral::Bool(value) => quote! { #value },
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -23,6 +87,18 @@ pub struct CompileOptions {
     pub include_async: bool,
     pub use_anyhow: bool,
     pub debug_mode: bool,
+    /// Report checksum mismatches on stderr instead of failing to decode
+    pub warn_on_checksum_mismatch: bool,
+    /// Path of the map type `+map` arrays decode into, which needs `new`, `len`, `contains_key`, `insert` and `values`
+    pub map_type: String,
+    /// Represent arrays of constant length as `[T; N]` rather than `Vec<T>`
+    pub fixed_arrays: bool,
+    /// Path of the `protospec` runtime crate, whose error types and codecs are used and whose `Decode` and `Encode` traits are implemented, `protospec` by default. If `None`, standalone code is emitted
+    pub runtime: Option<String>,
+    /// Name types and enum variants in `UpperCamelCase` and fields in `snake_case` rather than as in the spec, unless renamed by `@rust_name`
+    pub rust_case: bool,
+    /// Rust types container fields are held as in place of the type they would have, i.e. `("Vec<u8>", "bytes::Bytes")`, unless given per field by `@rust_type("Type")`. A mapped type converts from that type with `From`, and is borrowed back as it to be encoded with `AsRef`: as a slice of the elements for arrays, i.e. `AsRef<[u8]>`, as `str` for strings, and as the type itself otherwise. Copyable types are instead cloned and converted back with `From`
+    pub type_map: Vec<(String, String)>,
 }
 
 impl Default for CompileOptions {
@@ -30,6 +106,7 @@ impl Default for CompileOptions {
         Self {
             include_async: false,
             debug_mode: false,
+            warn_on_checksum_mismatch: false,
             enum_derives: vec![
                 "PartialEq".to_string(),
                 "Debug".to_string(),
@@ -43,11 +120,36 @@ impl Default for CompileOptions {
                 "Default".to_string(),
             ],
             use_anyhow: false,
+            map_type: "indexmap::IndexMap".to_string(),
+            fixed_arrays: true,
+            runtime: Some("protospec".to_string()),
+            rust_case: false,
+            type_map: vec![],
         }
     }
 }
 
 impl CompileOptions {
+    /// Checks that the Rust paths and types given as options parse
+    pub fn check(&self) -> StdResult<(), String> {
+        if let Some(runtime) = &self.runtime {
+            if syn::parse_str::<syn::Path>(runtime).is_err() {
+                return Err(format!("invalid runtime path `{}`", runtime));
+            }
+        }
+        if syn::parse_str::<syn::Path>(&self.map_type).is_err() {
+            return Err(format!("invalid map type `{}`", self.map_type));
+        }
+        for (from, to) in &self.type_map {
+            for type_ in [from, to] {
+                if parse_type(type_).is_none() {
+                    return Err(format!("invalid mapped type `{}`", type_));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn emit_struct_derives(&self, extra: &[&str]) -> TokenStream {
         let mut all: Vec<_> = self.struct_derives.iter().map(|x| &**x).collect();
         all.extend_from_slice(extra);
@@ -57,6 +159,25 @@ impl CompileOptions {
         self.emit_derives(&all[..])
     }
 
+    /// Struct derives, leaving out `Default` for types that implement it manually
+    fn emit_struct_derives_except_default(&self, extra: &[&str]) -> TokenStream {
+        let mut all: Vec<_> = self.struct_derives.iter().map(|x| &**x).collect();
+        all.extend_from_slice(extra);
+        all.retain(|x| *x != "Default");
+        all.sort();
+        all.dedup();
+
+        self.emit_derives(&all[..])
+    }
+
+    fn derives_struct_default(&self, extra: &[&str]) -> bool {
+        self.struct_derives.iter().any(|x| x == "Default") || extra.contains(&"Default")
+    }
+
+    fn derives_enum_default(&self, extra: &[&str]) -> bool {
+        self.enum_derives.iter().any(|x| x == "Default") || extra.contains(&"Default")
+    }
+
     fn emit_enum_derives(&self, extra: &[&str]) -> TokenStream {
         let mut all: Vec<_> = self.enum_derives.iter().map(|x| &**x).collect();
         all.extend_from_slice(extra);
@@ -88,17 +209,41 @@ impl CompileOptions {
     }
 }
 
+/// Emits implementations in a scope of their own, where the generated helpers take precedence over user types of the same names, which are referred to by `self::` paths
+fn emit_impls(impls: TokenStream) -> TokenStream {
+    quote! {
+        const _: () = {
+            use _ps_rt::*;
+            #impls
+        };
+    }
+}
+
+/// Compiles a program to Rust, or to a `compile_error!` if the options are invalid or names of the program collide in Rust
 pub fn compile_program(program: &Program, options: &CompileOptions) -> TokenStream {
+    if let Err(message) = options.check() {
+        return quote! { compile_error!(#message); };
+    }
+    if let Err(error) = program.check_rust_names(options.rust_case) {
+        let message = error.to_string();
+        return quote! { compile_error!(#message); };
+    }
     let mut components = vec![];
-    let errors = if options.use_anyhow {
+    let errors = if let Some(runtime) = options.runtime.as_ref().filter(|_| !options.use_anyhow) {
+        let runtime: syn::Path = syn::parse_str(runtime).expect("runtime path is checked");
+        quote! {
+            pub use #runtime::{Result, DecodeError, EncodeError};
+            pub(super) use #runtime::{encode_error, decode_error};
+        }
+    } else if options.use_anyhow {
         quote! {
             pub type Result<T> = anyhow::Result<T>;
 
-            fn encode_error<S: AsRef<str>>(value: S) -> anyhow::Error {
+            pub(super) fn encode_error<S: AsRef<str>>(value: S) -> anyhow::Error {
                 anyhow::anyhow!("{}", value.as_ref())
             }
 
-            fn decode_error<S: AsRef<str>>(value: S) -> anyhow::Error {
+            pub(super) fn decode_error<S: AsRef<str>>(value: S) -> anyhow::Error {
                 anyhow::anyhow!("{}", value.as_ref())
             }
         }
@@ -124,59 +269,114 @@ pub fn compile_program(program: &Program, options: &CompileOptions) -> TokenStre
             }
             impl Error for EncodeError {}
 
-            fn encode_error<S: AsRef<str>>(value: S) -> EncodeError {
+            pub(super) fn encode_error<S: AsRef<str>>(value: S) -> EncodeError {
                 EncodeError(value.as_ref().to_string())
             }
 
-            fn decode_error<S: AsRef<str>>(value: S) -> DecodeError {
+            pub(super) fn decode_error<S: AsRef<str>>(value: S) -> DecodeError {
                 DecodeError(value.as_ref().to_string())
             }
         }
     };
 
+    let codecs = emit_codecs(options);
+    let counting_stream = emit_counting_stream(options);
+    let pointer_layout = emit_pointer_layout();
+    let lookahead = emit_lookahead(options);
+    let map = if program
+        .types
+        .values()
+        .any(|field| uses_map(&field.type_.borrow()))
+    {
+        let map_type: syn::Path = syn::parse_str(&options.map_type).expect("map type is checked");
+        quote! {
+            pub type Map<K, V> = #map_type<K, V>;
+        }
+    } else {
+        quote! {}
+    };
+    // the helpers are declared apart from the user types, which shadow the public ones of the same names
     components.push(quote! {
-        use std::io::{Read, BufRead, Cursor};
-        use std::slice;
-        use std::mem;
-        use std::convert::TryInto;
-
-        #errors
+        mod _ps_rt {
+            pub(super) use std::io::{Read, BufRead, Cursor, Seek, SeekFrom};
+            pub(super) use std::slice;
+            pub(super) use std::mem;
+            pub(super) use std::convert::TryInto;
+
+            #errors
+            #codecs
+            #counting_stream
+            #pointer_layout
+            #lookahead
+            #map
+        }
+        pub use _ps_rt::*;
     });
-    for (name, field) in program.types.iter() {
+    for field in program.types.values() {
         match &*field.type_.borrow() {
             Type::Foreign(_) => continue,
             Type::Container(item) => {
-                components.push(generate_container(&name, &**item, options));
+                components.push(generate_container(field, &**item, options));
             }
             Type::Enum(item) => {
-                components.push(generate_enum(&name, item, options));
+                components.push(generate_enum(field, item, options));
             }
             Type::Bitfield(item) => {
-                components.push(generate_bitfield(&name, item, options));
+                components.push(generate_bitfield(field, item, options));
             }
             generic => {
-                let ident = format_ident!("{}", global_name(name));
-                let type_ref = emit_type_ref(generic);
+                let ident = options.emit_type_name(field);
+                let path = options.emit_type_path(field);
+                let extra_derives = extra_derives(field);
+                let attributes = emit_rust_attributes(field);
+                let type_ref = emit_type_ref(generic, options);
                 let type_ref = if field.condition.borrow().is_some() {
                     quote! {
-                        Option<#type_ref>
+                        ::core::option::Option<#type_ref>
                     }
                 } else {
                     type_ref
                 };
-                let derives = options.emit_struct_derives(&[]);
+                if field.condition.borrow().is_none()
+                    && needs_array_default(generic, options)
+                    && options.derives_struct_default(&extra_derives)
+                {
+                    let derives = options.emit_struct_derives_except_default(&extra_derives);
+                    let default_value = emit_default_value(generic, options);
+                    let impls = emit_impls(quote! {
+                        impl Default for #path {
+                            fn default() -> Self {
+                                Self(#default_value)
+                            }
+                        }
+                    });
+                    components.push(quote! {
+                        #derives
+                        #attributes
+                        pub struct #ident(pub #type_ref);
 
-                components.push(quote! {
-                    #derives
-                    pub struct #ident(pub #type_ref);
-                });
+                        #impls
+                    });
+                } else {
+                    let derives = options.emit_struct_derives(&extra_derives);
+                    components.push(quote! {
+                        #derives
+                        #attributes
+                        pub struct #ident(pub #type_ref);
+                    });
+                }
             }
         }
-        components.push(prepare_impls(&field, options));
+        let impls = prepare_impls(&field, options);
+        let runtime_impls = runtime::prepare_runtime_impls(field, options);
+        components.push(emit_impls(quote! {
+            #impls
+            #runtime_impls
+        }));
     }
     let components = flatten(components);
     quote! {
-        #[allow(unused_imports, unused_parens, unused_variables, dead_code, unused_mut, non_upper_case_globals)]
+        #[allow(unused_imports, unused_parens, unused_variables, dead_code, unused_mut, non_upper_case_globals, non_camel_case_types, non_snake_case)]
         mod _ps {
             #components
         }
@@ -184,35 +384,562 @@ pub fn compile_program(program: &Program, options: &CompileOptions) -> TokenStre
     }
 }
 
+fn uses_map(type_: &Type) -> bool {
+    match type_ {
+        Type::Container(container) => container
+            .items
+            .values()
+            .any(|field| uses_map(&field.type_.borrow())),
+        Type::Array(array) => array.map.is_some() || uses_map(&array.element),
+        _ => false,
+    }
+}
+
+/// Emits the `varint` and `string` codecs of the prelude types, taken from the runtime crate where its errors are
+fn emit_codecs(options: &CompileOptions) -> TokenStream {
+    if let Some(runtime) = options.runtime.as_ref().filter(|_| !options.use_anyhow) {
+        let runtime: syn::Path = syn::parse_str(runtime).expect("runtime path is checked");
+        return quote! {
+            pub(super) use #runtime::{string, varint};
+        };
+    }
+    quote! {
+        pub(super) mod varint {
+            use super::{decode_error, Result};
+            use std::io::{Read, Write};
+
+            pub(crate) fn read_leb128<R: Read>(reader: &mut R) -> Result<u64> {
+                let mut buf = [0u8; 1];
+                let mut output = 0u64;
+                let mut shift = 0u32;
+                loop {
+                    reader.read_exact(&mut buf[..])?;
+                    let bits = (buf[0] & 127) as u64;
+                    if shift >= 64 || (bits << shift) >> shift != bits {
+                        return Err(decode_error("LEB128 varint overflows u64").into());
+                    }
+                    output |= bits << shift;
+                    if buf[0] & 128 == 0 {
+                        if buf[0] == 0 && shift > 0 {
+                            return Err(decode_error("overlong LEB128 varint").into());
+                        }
+                        return Ok(output);
+                    }
+                    shift += 7;
+                }
+            }
+
+            pub(crate) fn write_leb128<W: Write>(writer: &mut W, mut value: u64) -> Result<()> {
+                while (value & !0b1111111) != 0 {
+                    writer.write_all(&[(value as u8 & 127) | 128])?;
+                    value >>= 7;
+                }
+                writer.write_all(&[value as u8])?;
+                Ok(())
+            }
+
+            pub(crate) fn leb128_len(mut value: u64) -> usize {
+                let mut len = 1;
+                while (value & !0b1111111) != 0 {
+                    value >>= 7;
+                    len += 1;
+                }
+                len
+            }
+
+            pub(crate) fn zigzag_encode(value: i64) -> u64 {
+                ((value << 1) ^ (value >> 63)) as u64
+            }
+
+            pub(crate) fn zigzag_decode(value: u64) -> i64 {
+                (value >> 1) as i64 ^ -((value & 1) as i64)
+            }
+        }
+
+        pub(super) mod string {
+            use super::Result;
+            use std::io::{BufRead, Write};
+
+            pub(crate) fn read_cstring<R: BufRead>(reader: &mut R) -> Result<String> {
+                let mut buf = vec![];
+                reader.read_until(0u8, &mut buf)?;
+                if buf.last() == Some(&0u8) {
+                    buf.pop();
+                }
+                Ok(String::from_utf8(buf)?)
+            }
+
+            pub(crate) fn write_cstring<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+                writer.write_all(value.as_bytes())?;
+                writer.write_all(&[0u8])?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Emits `CountingStream`, which counts the bytes passing through a stream for the stream position intrinsics
+fn emit_counting_stream(options: &CompileOptions) -> TokenStream {
+    let async_impls = if options.include_async {
+        quote! {
+            impl<S: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for CountingStream<S> {
+                fn poll_read(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                    buf: &mut tokio::io::ReadBuf<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    let this = &mut *self;
+                    let filled = buf.filled().len();
+                    let result = std::pin::Pin::new(&mut this.inner).poll_read(cx, buf);
+                    this.count += (buf.filled().len() - filled) as u64;
+                    result
+                }
+            }
+
+            impl<S: tokio::io::AsyncBufRead + Unpin> tokio::io::AsyncBufRead for CountingStream<S> {
+                fn poll_fill_buf(
+                    self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<&[u8]>> {
+                    std::pin::Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+                }
+
+                fn consume(mut self: std::pin::Pin<&mut Self>, amt: usize) {
+                    self.count += amt as u64;
+                    std::pin::Pin::new(&mut self.inner).consume(amt)
+                }
+            }
+
+            impl<S: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for CountingStream<S> {
+                fn poll_write(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                    buf: &[u8],
+                ) -> std::task::Poll<std::io::Result<usize>> {
+                    let this = &mut *self;
+                    let result = std::pin::Pin::new(&mut this.inner).poll_write(cx, buf);
+                    if let std::task::Poll::Ready(Ok(written)) = &result {
+                        this.count += *written as u64;
+                    }
+                    result
+                }
+
+                fn poll_flush(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+                }
+
+                // the counted stream is borrowed, so is not ours to shut down
+                fn poll_shutdown(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+                }
+            }
+
+            impl<S: tokio::io::AsyncSeek + Unpin> tokio::io::AsyncSeek for CountingStream<S> {
+                fn start_seek(
+                    mut self: std::pin::Pin<&mut Self>,
+                    position: SeekFrom,
+                ) -> std::io::Result<()> {
+                    std::pin::Pin::new(&mut self.inner).start_seek(position)
+                }
+
+                fn poll_complete(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<u64>> {
+                    let this = &mut *self;
+                    let result = std::pin::Pin::new(&mut this.inner).poll_complete(cx);
+                    if let std::task::Poll::Ready(Ok(position)) = &result {
+                        this.seeked(*position);
+                    }
+                    result
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        pub(super) struct CountingStream<S> {
+            pub(super) inner: S,
+            pub(super) count: u64,
+            // stream position and count when last seeked, so seeking moves the count along with the stream
+            pub(super) anchor: Option<(u64, u64)>,
+        }
+
+        impl<S> CountingStream<S> {
+            pub(super) fn new(inner: S) -> Self {
+                CountingStream { inner, count: 0, anchor: None }
+            }
+
+            pub(super) fn seeked(&mut self, position: u64) {
+                if let Some((anchor_position, anchor_count)) = self.anchor {
+                    let current = anchor_position.wrapping_add(self.count.wrapping_sub(anchor_count));
+                    self.count = self.count.wrapping_add(position.wrapping_sub(current));
+                }
+                self.anchor = Some((position, self.count));
+            }
+        }
+
+        impl<S: Read> Read for CountingStream<S> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                self.count += read as u64;
+                Ok(read)
+            }
+        }
+
+        impl<S: BufRead> BufRead for CountingStream<S> {
+            fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+                self.inner.fill_buf()
+            }
+
+            fn consume(&mut self, amt: usize) {
+                self.count += amt as u64;
+                self.inner.consume(amt)
+            }
+        }
+
+        impl<S: std::io::Write> std::io::Write for CountingStream<S> {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let written = self.inner.write(buf)?;
+                self.count += written as u64;
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl<S: Seek> Seek for CountingStream<S> {
+            fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+                let position = self.inner.seek(position)?;
+                self.seeked(position);
+                Ok(position)
+            }
+        }
+
+        #async_impls
+    }
+}
+
+/// Emits `Lookahead`, which reads ahead of a stream to match the terminators of arrays
+fn emit_lookahead(options: &CompileOptions) -> TokenStream {
+    let async_impls = if options.include_async {
+        quote! {
+            impl<S: tokio::io::AsyncBufRead + Unpin> Lookahead<S> {
+                pub(super) async fn peek_async(&mut self, len: usize) -> std::io::Result<&[u8]> {
+                    use tokio::io::AsyncBufReadExt;
+                    if self.pending.is_empty() && self.inner.fill_buf().await?.len() >= len {
+                        return Ok(&self.inner.fill_buf().await?[..len]);
+                    }
+                    while self.pending.len() < len {
+                        let buf = self.inner.fill_buf().await?;
+                        if buf.is_empty() {
+                            break;
+                        }
+                        let read = buf.len().min(len - self.pending.len());
+                        self.pending.extend_from_slice(&buf[..read]);
+                        self.inner.consume(read);
+                    }
+                    Ok(&self.pending[..self.pending.len().min(len)])
+                }
+
+                pub(super) fn skip_async(&mut self, len: usize) {
+                    if self.pending.is_empty() {
+                        tokio::io::AsyncBufRead::consume(std::pin::Pin::new(&mut self.inner), len);
+                    } else {
+                        self.pending.drain(..len);
+                    }
+                }
+
+                pub(super) async fn terminator_async(&mut self, terminators: &[&[u8]]) -> std::io::Result<Option<usize>> {
+                    'terminators: for terminator in terminators {
+                        for len in 1..=terminator.len() {
+                            if self.peek_async(len).await? != &terminator[..len] {
+                                continue 'terminators;
+                            }
+                        }
+                        return Ok(Some(terminator.len()));
+                    }
+                    Ok(None)
+                }
+            }
+
+            impl<S: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for Lookahead<S> {
+                fn poll_read(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                    buf: &mut tokio::io::ReadBuf<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    let this = &mut *self;
+                    if this.pending.is_empty() {
+                        return std::pin::Pin::new(&mut this.inner).poll_read(cx, buf);
+                    }
+                    let read = buf.remaining().min(this.pending.len());
+                    buf.put_slice(&this.pending[..read]);
+                    this.pending.drain(..read);
+                    std::task::Poll::Ready(Ok(()))
+                }
+            }
+
+            impl<S: tokio::io::AsyncBufRead + Unpin> tokio::io::AsyncBufRead for Lookahead<S> {
+                fn poll_fill_buf(
+                    self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<&[u8]>> {
+                    let this = self.get_mut();
+                    if this.pending.is_empty() {
+                        std::pin::Pin::new(&mut this.inner).poll_fill_buf(cx)
+                    } else {
+                        std::task::Poll::Ready(Ok(&this.pending[..]))
+                    }
+                }
+
+                fn consume(mut self: std::pin::Pin<&mut Self>, amt: usize) {
+                    let this = &mut *self;
+                    if this.pending.is_empty() {
+                        std::pin::Pin::new(&mut this.inner).consume(amt)
+                    } else {
+                        this.pending.drain(..amt);
+                    }
+                }
+            }
+
+            impl<S: tokio::io::AsyncSeek + Unpin> tokio::io::AsyncSeek for Lookahead<S> {
+                fn start_seek(
+                    mut self: std::pin::Pin<&mut Self>,
+                    position: SeekFrom,
+                ) -> std::io::Result<()> {
+                    let this = &mut *self;
+                    let position = this.unread(position);
+                    std::pin::Pin::new(&mut this.inner).start_seek(position)
+                }
+
+                fn poll_complete(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<u64>> {
+                    std::pin::Pin::new(&mut self.inner).poll_complete(cx)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        pub(super) struct Lookahead<S> {
+            pub(super) inner: S,
+            // bytes read ahead of the stream, which are read again before the stream
+            pub(super) pending: Vec<u8>,
+        }
+
+        impl<S> Lookahead<S> {
+            pub(super) fn new(inner: S) -> Self {
+                Lookahead { inner, pending: Vec::new() }
+            }
+
+            // drops the bytes read ahead before a seek, moving a relative seek back over them
+            pub(super) fn unread(&mut self, position: SeekFrom) -> SeekFrom {
+                let pending = std::mem::take(&mut self.pending).len() as i64;
+                match position {
+                    SeekFrom::Current(offset) => SeekFrom::Current(offset - pending),
+                    position => position,
+                }
+            }
+        }
+
+        impl<S: BufRead> Lookahead<S> {
+            // bytes still buffered by the stream are peeked in place, only those beyond its buffer are read ahead
+            pub(super) fn peek_sync(&mut self, len: usize) -> std::io::Result<&[u8]> {
+                if self.pending.is_empty() && self.inner.fill_buf()?.len() >= len {
+                    return Ok(&self.inner.fill_buf()?[..len]);
+                }
+                while self.pending.len() < len {
+                    let buf = self.inner.fill_buf()?;
+                    if buf.is_empty() {
+                        break;
+                    }
+                    let read = buf.len().min(len - self.pending.len());
+                    self.pending.extend_from_slice(&buf[..read]);
+                    self.inner.consume(read);
+                }
+                Ok(&self.pending[..self.pending.len().min(len)])
+            }
+
+            pub(super) fn skip_sync(&mut self, len: usize) {
+                if self.pending.is_empty() {
+                    self.inner.consume(len);
+                } else {
+                    self.pending.drain(..len);
+                }
+            }
+
+            // length of the first of `terminators` the stream continues with, reading no further than needed to rule out the others
+            pub(super) fn terminator_sync(&mut self, terminators: &[&[u8]]) -> std::io::Result<Option<usize>> {
+                'terminators: for terminator in terminators {
+                    for len in 1..=terminator.len() {
+                        if self.peek_sync(len)? != &terminator[..len] {
+                            continue 'terminators;
+                        }
+                    }
+                    return Ok(Some(terminator.len()));
+                }
+                Ok(None)
+            }
+        }
+
+        impl<S: Read> Read for Lookahead<S> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.pending.is_empty() {
+                    return self.inner.read(buf);
+                }
+                let read = buf.len().min(self.pending.len());
+                buf[..read].copy_from_slice(&self.pending[..read]);
+                self.pending.drain(..read);
+                Ok(read)
+            }
+        }
+
+        impl<S: BufRead> BufRead for Lookahead<S> {
+            fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+                if self.pending.is_empty() {
+                    self.inner.fill_buf()
+                } else {
+                    Ok(&self.pending[..])
+                }
+            }
+
+            fn consume(&mut self, amt: usize) {
+                if self.pending.is_empty() {
+                    self.inner.consume(amt)
+                } else {
+                    self.pending.drain(..amt);
+                }
+            }
+        }
+
+        impl<S: Seek> Seek for Lookahead<S> {
+            fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+                let position = self.unread(position);
+                self.inner.seek(position)
+            }
+        }
+
+        #async_impls
+    }
+}
+
+/// Emits `PointerLayout`, which collects the regions of pointer fields during encoding and lays them out after the type
+fn emit_pointer_layout() -> TokenStream {
+    quote! {
+        pub(super) struct PointerLayout {
+            // explicit offset if any, contents
+            pub(super) regions: Vec<(Option<u64>, Vec<u8>)>,
+            // region and position of the placeholder, region pointed to, placeholder encoder
+            pub(super) patches: Vec<(usize, u64, usize, fn(u64) -> Result<Vec<u8>>)>,
+        }
+
+        impl PointerLayout {
+            pub(super) fn new() -> Self {
+                PointerLayout { regions: vec![], patches: vec![] }
+            }
+
+            pub(super) fn reserve(&mut self) -> usize {
+                self.regions.push((None, vec![]));
+                self.regions.len() - 1
+            }
+
+            pub(super) fn place_at(&mut self, region: usize, offset: u64) {
+                self.regions[region].0 = Some(offset);
+            }
+
+            pub(super) fn fill(&mut self, region: usize, contents: Vec<u8>) {
+                self.regions[region].1 = contents;
+            }
+
+            pub(super) fn patch(&mut self, region: usize, at: u64, target: usize, encode: fn(u64) -> Result<Vec<u8>>) {
+                self.patches.push((region, at, target, encode));
+            }
+
+            // the first region starts the output, explicitly placed regions go at their offset, and the rest are appended in order
+            pub(super) fn finish(self) -> Result<Vec<u8>> {
+                fn write_at(out: &mut Vec<u8>, position: u64, contents: &[u8]) {
+                    let position = position as usize;
+                    let end = position + contents.len();
+                    if out.len() < end {
+                        out.resize(end, 0);
+                    }
+                    out[position..end].copy_from_slice(contents);
+                }
+                let mut out = vec![];
+                let mut positions = vec![0u64; self.regions.len()];
+                let mut placed: Vec<(u64, u64)> = vec![];
+                for (i, (offset, contents)) in self.regions.iter().enumerate() {
+                    if i == 0 || offset.is_some() {
+                        positions[i] = offset.unwrap_or(0);
+                        let end = positions[i]
+                            .checked_add(contents.len() as u64)
+                            .ok_or_else(|| encode_error("pointer region out of bounds"))?;
+                        if end > positions[i] {
+                            if placed.iter().any(|(start, placed_end)| positions[i] < *placed_end && *start < end) {
+                                return Err(encode_error(format!("pointer region at offset {} overlaps another region", positions[i])).into());
+                            }
+                            placed.push((positions[i], end));
+                        }
+                        write_at(&mut out, positions[i], contents);
+                    }
+                }
+                for (i, (offset, contents)) in self.regions.iter().enumerate() {
+                    if i != 0 && offset.is_none() {
+                        positions[i] = out.len() as u64;
+                        write_at(&mut out, positions[i], contents);
+                    }
+                }
+                for (region, at, target, encode) in self.patches {
+                    let encoded = encode(positions[target])?;
+                    write_at(&mut out, positions[region] + at, &encoded[..]);
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
 fn ref_resolver(_f: &Arc<Field>) -> TokenStream {
     unimplemented!("cannot reference field in input default");
 }
 
 fn prepare_impls(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
-    let container_ident = format_ident!("{}", global_name(&field.name));
+    let container_ident = options.emit_type_path(field);
 
-    let mut decode_context = coder::decode::Context::new();
+    let mut decode_context = coder::decode::Context::new(options);
     decode_context.decode_field_top(field);
-    let decode_sync = decoder::prepare_decoder(options, &decode_context, false);
 
-    let mut new_context = coder::encode::Context::new();
+    let mut new_context = coder::encode::Context::new(options);
     new_context.encode_field_top(field);
 
-    let encode_sync = encoder::prepare_encoder(&new_context, false);
+    let validate = validate::prepare_validate(field, options);
 
     let mut arguments = vec![];
     let mut redefaults = vec![];
     for argument in field.arguments.borrow().iter() {
-        let name = emit_ident(&argument.name);
-        let type_ref = emit_type_ref(&argument.type_);
+        let name = options.emit_field(&argument.name);
+        let type_ref = emit_type_ref(&argument.type_, options);
         let opt_type_ref = if argument.default_value.is_some() {
-            quote! { Option<#type_ref> }
+            quote! { ::core::option::Option<#type_ref> }
         } else {
             type_ref.clone()
         };
         arguments.push(quote! {, #name: #opt_type_ref});
         if let Some(default_value) = argument.default_value.as_ref() {
-            let emitted = emit_expression(default_value, &ref_resolver);
+            let emitted = emit_expression(default_value, options, &ref_resolver);
             redefaults.push(quote! {
                 let #name: #type_ref = if let Some(#name) = #name {
                     #name
@@ -224,6 +951,36 @@ fn prepare_impls(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
     }
     let arguments = flatten(arguments);
     let redefaults = flatten(redefaults);
+    if field.is_seekable.get() {
+        return prepare_seekable_impls(
+            field,
+            options,
+            &decode_context,
+            &new_context,
+            arguments,
+            redefaults,
+            validate,
+        );
+    }
+    let encoded_len = length::prepare_encoded_len(field, options, &redefaults);
+    let decode_sync = decoder::prepare_decoder(options, &decode_context, false);
+    let encode_sync = encoder::prepare_encoder(options, &new_context, false);
+
+    let size_constant = match field.encoded_size() {
+        EncodedSize::Fixed(size) => {
+            let size = size as usize;
+            quote! {
+                pub const ENCODED_SIZE: usize = #size;
+            }
+        }
+        EncodedSize::Bounded(size) => {
+            let size = size as usize;
+            quote! {
+                pub const MAX_ENCODED_SIZE: usize = #size;
+            }
+        }
+        EncodedSize::Unbounded => quote! {},
+    };
 
     let async_functions = if options.include_async {
         let async_recursion = if field.is_maybe_cyclical.get() {
@@ -234,17 +991,17 @@ fn prepare_impls(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
             quote! {}
         };
 
-        let encode_async = encoder::prepare_encoder(&new_context, true);
+        let encode_async = encoder::prepare_encoder(options, &new_context, true);
         let decode_async = decoder::prepare_decoder(options, &decode_context, true);
         quote! {
             #async_recursion
-            pub async fn encode_async<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(&self, writer: &mut W #arguments) -> Result<()> {
+            pub async fn encode_async<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(&self, _ps_writer: &mut W #arguments) -> Result<()> {
                 #redefaults
                 #encode_async
             }
 
             #async_recursion
-            pub async fn decode_async<R: tokio::io::AsyncBufRead + Send + Sync + Unpin>(reader: &mut R #arguments) -> Result<Self> {
+            pub async fn decode_async<R: tokio::io::AsyncBufRead + Send + Sync + Unpin>(_ps_reader: &mut R #arguments) -> Result<Self> {
                 #redefaults
                 #decode_async
             }
@@ -255,28 +1012,143 @@ fn prepare_impls(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
 
     quote! {
         impl #container_ident {
-            pub fn decode_sync<R: Read + BufRead>(reader: &mut R #arguments) -> Result<Self> {
+            #size_constant
+
+            pub fn decode_sync<R: Read + BufRead>(_ps_reader: &mut R #arguments) -> Result<Self> {
                 #redefaults
                 #decode_sync
             }
 
-            pub fn encode_sync<W: std::io::Write>(&self, writer: &mut W #arguments) -> Result<()> {
+            pub fn encode_sync<W: std::io::Write>(&self, _ps_writer: &mut W #arguments) -> Result<()> {
                 #redefaults
                 #encode_sync
             }
 
+            pub fn validate(&self #arguments) -> Result<()> {
+                #redefaults
+                #validate
+            }
+
+            pub fn encoded_len(&self #arguments) -> Result<usize> {
+                #encoded_len
+            }
+
             #async_functions
         }
     }
 }
 
-fn emit_ident(name: &str) -> TokenStream {
-    let ident = format_ident!("{}", name);
+/// Seekable types decode from `Seek` streams relative to where decoding started, and encode in memory to lay out their pointer regions
+fn prepare_seekable_impls(
+    field: &Arc<Field>,
+    options: &CompileOptions,
+    decode_context: &coder::decode::Context,
+    encode_context: &coder::encode::Context,
+    arguments: TokenStream,
+    redefaults: TokenStream,
+    validate: TokenStream,
+) -> TokenStream {
+    let container_ident = options.emit_type_path(field);
+    let decode_sync = decoder::prepare_decoder(options, decode_context, false);
+    let encode_sync = encoder::prepare_encoder(options, encode_context, false);
+    let argument_names = emit_argument_names(field, options);
+    let encoded_len = length::prepare_encoded_len(field, options, &redefaults);
+    let encode_layout = quote! {
+        let mut _ps_layout = PointerLayout::new();
+        let _ps_region = _ps_layout.reserve();
+        let mut _ps_buf = Cursor::new(::std::vec::Vec::new());
+        self.encode_sync_at(&mut _ps_buf, &mut _ps_layout, _ps_region #argument_names)?;
+        _ps_layout.fill(_ps_region, _ps_buf.into_inner());
+        let _ps_encoded = _ps_layout.finish()?;
+    };
+
+    let async_functions = if options.include_async {
+        let async_recursion = if field.is_maybe_cyclical.get() {
+            quote! {
+                #[async_recursion::async_recursion]
+            }
+        } else {
+            quote! {}
+        };
+
+        let decode_async = decoder::prepare_decoder(options, decode_context, true);
+        quote! {
+            pub async fn encode_async<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(&self, _ps_writer: &mut W #arguments) -> Result<()> {
+                use tokio::io::AsyncWriteExt;
+                #encode_layout
+                _ps_writer.write_all(&_ps_encoded[..]).await?;
+                Ok(())
+            }
+
+            pub async fn decode_async<R: tokio::io::AsyncBufRead + tokio::io::AsyncSeek + Send + Sync + Unpin>(_ps_reader: &mut R #arguments) -> Result<Self> {
+                use tokio::io::AsyncSeekExt;
+                let _ps_stream_base = _ps_reader.stream_position().await?;
+                Self::decode_async_at(_ps_reader, _ps_stream_base #argument_names).await
+            }
+
+            #async_recursion
+            async fn decode_async_at<R: tokio::io::AsyncBufRead + tokio::io::AsyncSeek + Send + Sync + Unpin>(_ps_reader: &mut R, _ps_stream_base: u64 #arguments) -> Result<Self> {
+                #redefaults
+                #decode_async
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     quote! {
-        #ident
+        impl #container_ident {
+            pub fn decode_sync<R: Read + BufRead + Seek>(_ps_reader: &mut R #arguments) -> Result<Self> {
+                let _ps_stream_base = _ps_reader.stream_position()?;
+                Self::decode_sync_at(_ps_reader, _ps_stream_base #argument_names)
+            }
+
+            fn decode_sync_at<R: Read + BufRead + Seek>(_ps_reader: &mut R, _ps_stream_base: u64 #arguments) -> Result<Self> {
+                #redefaults
+                #decode_sync
+            }
+
+            pub fn encode_sync<W: std::io::Write>(&self, _ps_writer: &mut W #arguments) -> Result<()> {
+                use std::io::Write;
+                #encode_layout
+                _ps_writer.write_all(&_ps_encoded[..])?;
+                Ok(())
+            }
+
+            fn encode_sync_at<W: std::io::Write + Seek>(&self, _ps_writer: &mut W, _ps_layout: &mut PointerLayout, _ps_region: usize #arguments) -> Result<()> {
+                #redefaults
+                #encode_sync
+            }
+
+            pub fn validate(&self #arguments) -> Result<()> {
+                #redefaults
+                #validate
+            }
+
+            pub fn encoded_len(&self #arguments) -> Result<usize> {
+                #encoded_len
+            }
+
+            #async_functions
+        }
     }
 }
 
+/// Emits the names of the arguments of a type, each preceded by a comma to follow the other parameters of a call
+fn emit_argument_names(field: &Field, options: &CompileOptions) -> TokenStream {
+    flatten(
+        field
+            .arguments
+            .borrow()
+            .iter()
+            .map(|argument| {
+                let name = options.emit_field(&argument.name);
+                quote! {, #name}
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
 fn emit_register(register: usize) -> TokenStream {
     let ident = format_ident!("r_{}", register);
     quote! {
@@ -305,16 +1177,31 @@ fn flatten_separated<T: IntoIterator<Item = TokenStream>>(
     out
 }
 
-pub fn emit_type_ref(item: &Type) -> TokenStream {
+pub fn emit_type_ref(item: &Type, options: &CompileOptions) -> TokenStream {
     match item {
         Type::Container(_) => unimplemented!(),
-        Type::Enum(enum_type) => emit_ident(&*enum_type.name),
+        Type::Enum(enum_type) if options.rust_case => {
+            emit_type_path(&to_upper_camel(&enum_type.name))
+        }
+        Type::Enum(enum_type) => emit_type_path(&enum_type.name),
         Type::Bitfield(_) => unimplemented!(),
         Type::Scalar(s) => emit_ident(&s.scalar.to_string()),
         Type::Array(array_type) => {
-            let interior = emit_type_ref(&*array_type.element);
-            quote! {
-                Vec<#interior>
+            let interior = emit_type_ref(&*array_type.element, options);
+            if let Some(key) = array_type.map_key_field() {
+                let key = emit_type_ref(&key.type_.borrow(), options);
+                quote! {
+                    _ps_rt::Map<#key, #interior>
+                }
+            } else if let Some(len) = array_type.fixed_length().filter(|_| options.fixed_arrays) {
+                let len = proc_macro2::Literal::usize_unsuffixed(len);
+                quote! {
+                    [#interior; #len]
+                }
+            } else {
+                quote! {
+                    ::std::vec::Vec<#interior>
+                }
             }
         }
         Type::Foreign(f) => f.obj.type_ref(),
@@ -323,19 +1210,30 @@ pub fn emit_type_ref(item: &Type) -> TokenStream {
         Type::Bool => emit_ident("bool"),
         Type::Ref(field) => match &*field.target.type_.borrow() {
             Type::Foreign(c) => c.obj.type_ref(),
-            _ => emit_ident(&global_name(&field.target.name)),
+            _ => options.emit_type_path(&field.target),
         },
     }
 }
 
+/// Emits the type of a container member, boxed if it holds its own container
+fn emit_field_type_ref(field: &Field, options: &CompileOptions) -> TokenStream {
+    let type_ref = emit_type_ref(&field.type_.borrow(), options);
+    if field.is_boxed.get() {
+        quote! { ::std::boxed::Box<#type_ref> }
+    } else {
+        type_ref
+    }
+}
+
 fn generate_container_fields_recur(
     access: TokenStream,
     item: &ContainerType,
     conditional: bool,
+    options: &CompileOptions,
     fields: &mut Vec<TokenStream>,
 ) {
-    for (name, field) in &item.items {
-        if field.is_pad.get() {
+    for field in item.items.values() {
+        if field.is_pad.get() || field.is_fixed.get() {
             continue;
         }
         match &*field.type_.borrow() {
@@ -344,21 +1242,26 @@ fn generate_container_fields_recur(
                     access.clone(),
                     sub_item,
                     conditional || field.condition.borrow().is_some(),
+                    options,
                     fields,
                 );
             }
             _ => {
-                let name_ident = format_ident!("{}", name);
-                let type_ref = emit_type_ref(&field.type_.borrow());
+                let name_ident = options.emit_member(field);
+                let type_ref = options
+                    .mapped_type(field)
+                    .unwrap_or_else(|| emit_field_type_ref(field, options));
                 let type_ref = if conditional || field.condition.borrow().is_some() {
                     quote! {
-                        Option<#type_ref>
+                        ::core::option::Option<#type_ref>
                     }
                 } else {
                     type_ref
                 };
+                let attributes = emit_rust_attributes(field);
 
                 fields.push(quote! {
+                    #attributes
                     #access #name_ident: #type_ref,
                 });
             }
@@ -366,64 +1269,119 @@ fn generate_container_fields_recur(
     }
 }
 
-fn generate_container_fields(access: TokenStream, item: &ContainerType) -> TokenStream {
+/// Whether `#[derive(Default)]` does not cover a type, which is true of Rust arrays of more than 32 elements
+fn needs_array_default(type_: &Type, options: &CompileOptions) -> bool {
+    match type_ {
+        Type::Array(array) if options.fixed_arrays => match array.fixed_length() {
+            Some(len) => len > 32 || needs_array_default(&array.element, options),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+fn emit_default_value(type_: &Type, options: &CompileOptions) -> TokenStream {
+    match type_ {
+        Type::Array(array) if needs_array_default(type_, options) => {
+            let element = emit_default_value(&array.element, options);
+            quote! { std::array::from_fn(|_| #element) }
+        }
+        _ => quote! { Default::default() },
+    }
+}
+
+/// Collects the default value of each field, returning whether any needs more than `Default::default()`
+fn generate_container_defaults_recur(
+    item: &ContainerType,
+    conditional: bool,
+    options: &CompileOptions,
+    defaults: &mut Vec<TokenStream>,
+) -> bool {
+    let mut needed = false;
+    for field in item.items.values() {
+        if field.is_pad.get() || field.is_fixed.get() {
+            continue;
+        }
+        let conditional = conditional || field.condition.borrow().is_some();
+        match &*field.type_.borrow() {
+            Type::Container(sub_item) => {
+                needed |=
+                    generate_container_defaults_recur(sub_item, conditional, options, defaults);
+            }
+            type_ => {
+                let name_ident = options.emit_member(field);
+                let value = if conditional || options.mapped_type(field).is_some() {
+                    quote! { Default::default() }
+                } else {
+                    needed |= needs_array_default(type_, options);
+                    emit_default_value(type_, options)
+                };
+                defaults.push(quote! {
+                    #name_ident: #value,
+                });
+            }
+        }
+    }
+    needed
+}
+
+fn generate_container_fields(
+    access: TokenStream,
+    item: &ContainerType,
+    options: &CompileOptions,
+) -> TokenStream {
     let mut fields = vec![];
-    generate_container_fields_recur(access, item, false, &mut fields);
+    generate_container_fields_recur(access, item, false, options, &mut fields);
     flatten(fields)
 }
 
 pub fn generate_container(
-    name: &str,
+    container: &Field,
     item: &ContainerType,
     options: &CompileOptions,
 ) -> TokenStream {
-    let name_ident = format_ident!("{}", global_name(name));
+    let name_ident = options.emit_type_name(container);
+    let path = options.emit_type_path(container);
+    let extra_derives = extra_derives(container);
+    let attributes = emit_rust_attributes(container);
     if item.is_enum.get() {
-        let derives = options.emit_enum_derives(&[]);
+        let derives = options.emit_enum_derives(&extra_derives);
         let mut fields = vec![];
-        for (name, field) in &item.items {
-            let name_ident = format_ident!("{}", name);
+        for field in item.items.values() {
+            let name_ident = options.emit_variant(field);
+            let variant_attributes = emit_rust_attributes(field);
             let type_ = field.type_.borrow();
             let type_ref = match &*type_ {
                 Type::Container(sub_container) => {
-                    let subfields = generate_container_fields(quote! {}, &**sub_container);
+                    let subfields = generate_container_fields(quote! {}, &**sub_container, options);
                     quote! {
                         {
                             #subfields
                         }
                     }
                 }
-                type_ => {
-                    let emitted = emit_type_ref(type_);
+                _ => {
+                    let emitted = emit_field_type_ref(field, options);
                     quote! { (#emitted) }
                 }
             };
 
             fields.push(quote! {
+                #variant_attributes
                 #name_ident#type_ref,
             });
         }
         let fields = flatten(fields);
 
-        let default_impl = if options.enum_derives.iter().any(|x| x == "Default") {
-            let (default_field, field) =
-                item.items.first().expect("missing enum entry for default");
-            let default_field = format_ident!("{}", default_field);
+        let default_impl = if options.derives_enum_default(&extra_derives) {
+            let (_, field) = item.items.first().expect("missing enum entry for default");
+            let default_field = options.emit_variant(field);
 
             let type_ = field.type_.borrow();
             let default_value = match &*type_ {
                 Type::Container(sub_container) => {
                     let mut fields = vec![];
-                    for (name, field) in sub_container.flatten_view() {
-                        if field.is_pad.get() {
-                            continue;
-                        }
-                        let name_ident = format_ident!("{}", name);
-
-                        fields.push(quote! {
-                            #name_ident: Default::default(),
-                        });
-                    }
+                    generate_container_defaults_recur(sub_container, false, options, &mut fields);
                     let fields = flatten(fields);
                     quote! {
                         {
@@ -431,45 +1389,97 @@ pub fn generate_container(
                         }
                     }
                 }
-                _ => {
-                    quote! { (Default::default()) }
+                type_ => {
+                    let default_value = emit_default_value(type_, options);
+                    quote! { (#default_value) }
                 }
             };
 
-            quote! {
-                impl Default for #name_ident {
+            emit_impls(quote! {
+                impl Default for #path {
                     fn default() -> Self {
                         Self::#default_field#default_value
                     }
                 }
-            }
+            })
         } else {
             quote! {}
         };
 
         quote! {
             #derives
+            #attributes
             pub enum #name_ident {
                 #fields
             }
 
             #default_impl
         }
-    } else {
-        let derives = options.emit_struct_derives(&[]);
-        let fields = generate_container_fields(quote! { pub }, item);
+    } else if let Some(layout) = &item.set {
+        let derives = options.emit_struct_derives(&extra_derives);
+        let mut fields = vec![];
+        generate_container_fields_recur(quote! { pub }, item, true, options, &mut fields);
+        let tag = emit_type_ref(&Type::Scalar(layout.tag), options);
+        fields.push(quote! {
+            pub unknown: Vec<(#tag, Vec<u8>)>,
+        });
+        if layout.preserve_order {
+            fields.push(quote! {
+                pub order: Vec<#tag>,
+            });
+        }
+        let fields = flatten(fields);
 
         quote! {
             #derives
+            #attributes
             pub struct #name_ident {
                 #fields
             }
         }
+    } else {
+        let fields = generate_container_fields(quote! { pub }, item, options);
+        let mut defaults = vec![];
+        if generate_container_defaults_recur(item, false, options, &mut defaults)
+            && options.derives_struct_default(&extra_derives)
+        {
+            let derives = options.emit_struct_derives_except_default(&extra_derives);
+            let defaults = flatten(defaults);
+            let default_impl = emit_impls(quote! {
+                impl Default for #path {
+                    fn default() -> Self {
+                        Self {
+                            #defaults
+                        }
+                    }
+                }
+            });
+            quote! {
+                #derives
+                #attributes
+                pub struct #name_ident {
+                    #fields
+                }
+
+                #default_impl
+            }
+        } else {
+            let derives = options.emit_struct_derives(&extra_derives);
+            quote! {
+                #derives
+                #attributes
+                pub struct #name_ident {
+                    #fields
+                }
+            }
+        }
     }
 }
 
-pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> TokenStream {
-    let name_ident = format_ident!("{}", global_name(name));
+pub fn generate_enum(field: &Field, item: &EnumType, options: &CompileOptions) -> TokenStream {
+    let name_ident = options.emit_type_name(field);
+    let path = options.emit_type_path(field);
+    let extra_derives = extra_derives(field);
     let mut fields = vec![];
     let mut from_repr_matches = vec![];
     let mut to_repr_matches = vec![];
@@ -481,7 +1491,7 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
     let rep = format_ident!("{}", item.rep.scalar.to_string());
 
     for (name, value) in item.items.iter() {
-        let discriminant_ident = format_ident!("{}", name);
+        let discriminant_ident = options.emit_enum_variant(name);
         match value {
             EnumValue::Value(value) => {
                 let value = eval_const_expression(&value.value);
@@ -495,17 +1505,17 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
                         #discriminant_ident,
                     });
                     from_repr_matches.push(quote! {
-                        #value => Ok(#name_ident::#discriminant_ident),
+                        #value => Ok(Self::#discriminant_ident),
                     });
                     to_repr_matches.push(quote! {
-                        #name_ident::#discriminant_ident => #value,
+                        Self::#discriminant_ident => #value,
                     });
                 } else {
                     fields.push(quote! {
                         #discriminant_ident = #value,
                     });
                     from_repr_matches.push(quote! {
-                        #value => Ok(#name_ident::#discriminant_ident),
+                        #value => Ok(Self::#discriminant_ident),
                     });
                 }
             }
@@ -514,10 +1524,10 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
                     #discriminant_ident(#rep),
                 });
                 from_repr_matches.push(quote! {
-                    value => Ok(#name_ident::#discriminant_ident(value)),
+                    value => Ok(Self::#discriminant_ident(value)),
                 });
                 to_repr_matches.push(quote! {
-                    #name_ident::#discriminant_ident(value) => value,
+                    Self::#discriminant_ident(value) => value,
                 });
             }
         }
@@ -527,15 +1537,18 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
     let from_repr_matches = flatten(from_repr_matches);
 
     let rep_size = item.rep.scalar.size() as usize;
-    let derives = options.emit_enum_derives(&["Clone", "Copy"]);
+    let mut derives = vec!["Clone", "Copy"];
+    derives.extend(extra_derives.iter().copied());
+    let derives = options.emit_enum_derives(&derives);
+    let attributes = emit_rust_attributes(field);
 
-    let format_string = format!("illegal enum value '{{}}' for enum '{}'", name);
+    let format_string = format!("illegal enum value '{{}}' for enum '{}'", field.name);
 
-    let default_impl = if options.enum_derives.iter().any(|x| x == "Default") {
+    let default_impl = if options.derives_enum_default(&extra_derives) {
         let (default_field, _) = item.items.first().expect("missing enum entry for default");
-        let default_field = format_ident!("{}", default_field);
+        let default_field = options.emit_enum_variant(default_field);
         quote! {
-            impl Default for #name_ident {
+            impl Default for #path {
                 fn default() -> Self {
                     Self::#default_field
                 }
@@ -566,14 +1579,8 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
         }
     };
 
-    quote! {
-        #repr
-        #derives
-        pub enum #name_ident {
-            #fields
-        }
-
-        impl #name_ident {
+    let impls = emit_impls(quote! {
+        impl #path {
             pub fn from_repr(repr: #rep) -> Result<Self> {
                 match repr {
                     #from_repr_matches
@@ -595,15 +1602,27 @@ pub fn generate_enum(name: &str, item: &EnumType, options: &CompileOptions) -> T
         }
 
         #default_impl
+    });
+
+    quote! {
+        #repr
+        #derives
+        #attributes
+        pub enum #name_ident {
+            #fields
+        }
+
+        #impls
     }
 }
 
 pub fn generate_bitfield(
-    bitfield_name: &str,
+    bitfield: &Field,
     item: &BitfieldType,
     options: &CompileOptions,
 ) -> TokenStream {
-    let name_ident = format_ident!("{}", global_name(bitfield_name));
+    let name_ident = options.emit_type_name(bitfield);
+    let path = options.emit_type_path(bitfield);
     let mut fields = vec![];
     let mut funcs = vec![];
     let mut all_fields = ConstInt::parse(item.rep.scalar, "0", crate::Span::default()).unwrap();
@@ -611,7 +1630,7 @@ pub fn generate_bitfield(
 
     for (name, cons) in item.items.iter() {
         let name_ident = format_ident!("{}", name.to_snake().to_uppercase());
-        let get_name = format_ident!("{}", name.to_snake());
+        let get_name = ident(&name.to_snake());
         let set_name = format_ident!("set_{}", name.to_snake());
         let value = eval_const_expression(&cons.value);
         if value.is_none() {
@@ -646,20 +1665,19 @@ pub fn generate_bitfield(
 
     let rep = format_ident!("{}", item.rep.scalar.to_string());
     let rep_size = item.rep.scalar.size() as usize;
-    let derives = options.emit_struct_derives(&["Clone", "Copy", "Default"]);
+    let mut derives = vec!["Clone", "Copy", "Default"];
+    derives.extend(extra_derives(bitfield));
+    let derives = options.emit_struct_derives(&derives);
+    let attributes = emit_rust_attributes(bitfield);
 
     let format_string = format!(
         "illegal bitfield value '{{}}' for bitfield '{}'",
-        bitfield_name
+        bitfield.name
     );
     let all_fields = ConstValue::Int(all_fields).emit();
 
-    quote! {
-        #[repr(transparent)]
-        #derives
-        pub struct #name_ident(pub #rep);
-
-        impl #name_ident {
+    let impls = emit_impls(quote! {
+        impl #path {
             #fields
             pub const ALL: Self = Self(#all_fields);
             pub const ZERO: Self = Self(0);
@@ -683,50 +1701,59 @@ pub fn generate_bitfield(
             #funcs
         }
 
-        impl core::ops::BitOr for #name_ident {
+        impl core::ops::BitOr for #path {
             type Output = Self;
             fn bitor(self, rhs: Self) -> Self {
                 Self(self.0 | rhs.0)
             }
         }
 
-        impl core::ops::BitOrAssign for #name_ident {
+        impl core::ops::BitOrAssign for #path {
             fn bitor_assign(&mut self, rhs: Self) {
                 *self = *self | rhs;
             }
         }
 
-        impl core::ops::BitAnd for #name_ident {
+        impl core::ops::BitAnd for #path {
             type Output = Self;
             fn bitand(self, rhs: Self) -> Self {
                 Self(self.0 & rhs.0)
             }
         }
 
-        impl core::ops::BitAndAssign for #name_ident {
+        impl core::ops::BitAndAssign for #path {
             fn bitand_assign(&mut self, rhs: Self) {
                 *self = *self & rhs;
             }
         }
 
-        impl core::ops::BitXor for #name_ident {
+        impl core::ops::BitXor for #path {
             type Output = Self;
             fn bitxor(self, rhs: Self) -> Self {
                 Self(self.0 ^ rhs.0)
             }
         }
 
-        impl core::ops::BitXorAssign for #name_ident {
+        impl core::ops::BitXorAssign for #path {
             fn bitxor_assign(&mut self, rhs: Self) {
                 *self = *self ^ rhs;
             }
         }
 
-        impl core::ops::Not for #name_ident {
+        impl core::ops::Not for #path {
             type Output = Self;
             fn not(self) -> Self {
                 Self(!self.0)
             }
         }
+    });
+
+    quote! {
+        #[repr(transparent)]
+        #derives
+        #attributes
+        pub struct #name_ident(pub #rep);
+
+        #impls
     }
 }
@@ -0,0 +1,209 @@
+use super::*;
+use proc_macro2::Ident;
+
+/// Rust keywords, which are escaped as raw identifiers, i.e. `r#type`
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Keywords which cannot be raw identifiers, which are suffixed with `_` instead
+const RESERVED: &[&str] = &["self", "Self", "super", "crate", "_"];
+
+/// Whether `name` is in the namespace of the modules, parameters and variables of the generated code, `_ps` and `_ps_*`
+fn is_generated(name: &str) -> bool {
+    name == "_ps" || name.starts_with("_ps_")
+}
+
+/// The identifier of `name` from a spec, escaping it if it is reserved in Rust or by the generated code
+pub(crate) fn ident(name: &str) -> Ident {
+    if RESERVED.contains(&name) || is_generated(name) {
+        format_ident!("{}_", name)
+    } else if KEYWORDS.contains(&name) {
+        format_ident!("r#{}", name)
+    } else {
+        format_ident!("{}", name)
+    }
+}
+
+pub(crate) fn emit_ident(name: &str) -> TokenStream {
+    let ident = ident(name);
+    quote! {
+        #ident
+    }
+}
+
+/// Emits a reference to the user type `name`, which is qualified as the generated helpers take precedence over user types in implementations
+pub(crate) fn emit_type_path(name: &str) -> TokenStream {
+    let ident = ident(name);
+    quote! {
+        self::#ident
+    }
+}
+
+/// Splits a name into its words at underscores and case changes, i.e. `HTTPHeader_len` into `HTTP`, `Header` and `len`
+fn words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && match i.checked_sub(1).map(|i| chars[i]) {
+                Some(previous) if previous.is_uppercase() => {
+                    matches!(next, Some(x) if x.is_lowercase())
+                }
+                Some(_) => true,
+                None => false,
+            };
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The leading underscores of `name`, which are kept by case conversions
+fn underscores(name: &str) -> &str {
+    &name[..name.len() - name.trim_start_matches('_').len()]
+}
+
+/// `name` in `UpperCamelCase`
+pub(crate) fn to_upper_camel(name: &str) -> String {
+    let mut out = underscores(name).to_string();
+    for word in words(name) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            out.push_str(&chars.as_str().to_lowercase());
+        }
+    }
+    out
+}
+
+/// `name` in `snake_case`
+pub(crate) fn to_snake(name: &str) -> String {
+    let words = words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>();
+    format!("{}{}", underscores(name), words.join("_"))
+}
+
+/// The name given by `@rust_name("name")`, which is kept as written
+fn rust_name(field: &Field) -> Option<String> {
+    field
+        .attributes_named("rust_name")
+        .find_map(Attribute::string_argument)
+}
+
+/// The name of the Rust type of a type declaration or of an enum container variant, which is its own unless given by `@rust_name("Name")`, in `UpperCamelCase` with `rust_case`
+pub fn global_name(field: &Field, rust_case: bool) -> String {
+    match rust_name(field) {
+        Some(name) => name,
+        None if rust_case => to_upper_camel(&field.name),
+        None => field.name.clone(),
+    }
+}
+
+/// The name of the Rust struct field of a container field, which is its own unless given by `@rust_name("name")`, in `snake_case` with `rust_case`
+pub fn field_name(field: &Field, rust_case: bool) -> String {
+    match rust_name(field) {
+        Some(name) => name,
+        None if rust_case => to_snake(&field.name),
+        None => field.name.clone(),
+    }
+}
+
+impl CompileOptions {
+    pub(crate) fn type_name(&self, field: &Field) -> String {
+        global_name(field, self.rust_case)
+    }
+
+    /// Emits the name of a type declaration where it is declared
+    pub(crate) fn emit_type_name(&self, field: &Field) -> TokenStream {
+        emit_ident(&self.type_name(field))
+    }
+
+    /// Emits a reference to the Rust type of a type declaration
+    pub(crate) fn emit_type_path(&self, field: &Field) -> TokenStream {
+        emit_type_path(&self.type_name(field))
+    }
+
+    /// Emits the name of an enum container variant or match container arm
+    pub(crate) fn emit_variant(&self, field: &Field) -> TokenStream {
+        emit_ident(&global_name(field, self.rust_case))
+    }
+
+    /// Emits the name of a variant of an `enum` type
+    pub(crate) fn emit_enum_variant(&self, name: &str) -> TokenStream {
+        if self.rust_case {
+            emit_ident(&to_upper_camel(name))
+        } else {
+            emit_ident(name)
+        }
+    }
+
+    /// Emits the name of a container field as a member of its struct or enum variant
+    pub(crate) fn emit_member(&self, field: &Field) -> TokenStream {
+        emit_ident(&field_name(field, self.rust_case))
+    }
+
+    /// Emits the name of a container field or type argument as a variable
+    pub(crate) fn emit_field(&self, name: &str) -> TokenStream {
+        if self.rust_case {
+            emit_ident(&to_snake(name))
+        } else {
+            emit_ident(name)
+        }
+    }
+
+    /// Emits the binding of a container field in a struct pattern to the variable of its name
+    pub(crate) fn emit_binding(&self, field: &Field) -> TokenStream {
+        let member = self.emit_member(field);
+        let variable = self.emit_field(&field.name);
+        if member.to_string() == variable.to_string() {
+            member
+        } else {
+            quote! { #member: #variable }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case() {
+        assert_eq!(to_upper_camel("http_header"), "HttpHeader");
+        assert_eq!(to_upper_camel("HTTPHeader"), "HttpHeader");
+        assert_eq!(to_upper_camel("Packet"), "Packet");
+        assert_eq!(to_snake("HTTPHeader_len"), "http_header_len");
+        assert_eq!(to_snake("payloadLen"), "payload_len");
+        assert_eq!(to_snake("_unused"), "_unused");
+        assert_eq!(to_snake("v2Data"), "v2_data");
+    }
+
+    #[test]
+    fn test_ident() {
+        assert_eq!(ident("type").to_string(), "r#type");
+        assert_eq!(ident("Self").to_string(), "Self_");
+        assert_eq!(ident("header").to_string(), "header");
+        assert_eq!(ident("_ps_reader").to_string(), "_ps_reader_");
+        assert_eq!(ident("_psalm").to_string(), "_psalm");
+    }
+}
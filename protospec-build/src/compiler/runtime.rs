@@ -0,0 +1,109 @@
+use super::*;
+
+/// Emits implementations of the runtime crate's `Decode` and `Encode` traits, delegating to the inherent methods
+pub fn prepare_runtime_impls(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
+    let runtime = match &options.runtime {
+        Some(runtime) => syn::parse_str::<syn::Path>(runtime).expect("invalid runtime path"),
+        None => return quote! {},
+    };
+    let ident = options.emit_type_path(field);
+    let mut types = vec![];
+    let mut names = vec![];
+    for argument in field.arguments.borrow().iter() {
+        let type_ref = emit_type_ref(&argument.type_, options);
+        types.push(if argument.default_value.is_some() {
+            quote! { ::core::option::Option<#type_ref>, }
+        } else {
+            quote! { #type_ref, }
+        });
+        names.push(options.emit_field(&argument.name));
+    }
+    let args_type = flatten(types);
+    let bindings = flatten_separated(names.iter().cloned(), quote! { , });
+    let bindings = if names.len() == 1 {
+        quote! { (#bindings,) }
+    } else {
+        quote! { (#bindings) }
+    };
+    let argument_names = emit_argument_names(field, options);
+    // seekable types decode from `Seek` streams, which the traits do not require
+    let seekable = field.is_seekable.get();
+    // with anyhow, the inherent methods return a different error type than the traits
+    let returned = |call: TokenStream| {
+        if options.use_anyhow {
+            quote! { Ok(#call?) }
+        } else {
+            call
+        }
+    };
+    let decode_sync = returned(quote! { Self::decode_sync(_ps_reader #argument_names) });
+    let encode_sync = returned(quote! { Self::encode_sync(self, _ps_writer #argument_names) });
+    let encoded_len = returned(quote! { Self::encoded_len(self #argument_names) });
+    let decode_async = returned(quote! { Self::decode_async(_ps_reader #argument_names).await });
+    let encode_async =
+        returned(quote! { Self::encode_async(self, _ps_writer #argument_names).await });
+
+    let decode = if seekable {
+        quote! {}
+    } else {
+        quote! {
+            impl #runtime::Decode for #ident {
+                type Args = (#args_type);
+
+                fn decode_sync<R: Read + BufRead>(_ps_reader: &mut R, _ps_args: Self::Args) -> #runtime::Result<Self> {
+                    let #bindings = _ps_args;
+                    #decode_sync
+                }
+            }
+        }
+    };
+    let encode = quote! {
+        impl #runtime::Encode for #ident {
+            type Args = (#args_type);
+
+            fn encode_sync<W: std::io::Write>(&self, _ps_writer: &mut W, _ps_args: Self::Args) -> #runtime::Result<()> {
+                let #bindings = _ps_args;
+                #encode_sync
+            }
+
+            fn encoded_len(&self, _ps_args: Self::Args) -> #runtime::Result<usize> {
+                let #bindings = _ps_args;
+                #encoded_len
+            }
+        }
+    };
+    if !options.include_async {
+        return quote! {
+            #decode
+            #encode
+        };
+    }
+    let decode_async_impl = if seekable {
+        quote! {}
+    } else {
+        quote! {
+            impl #runtime::AsyncDecode for #ident {
+                fn decode_async<R: tokio::io::AsyncBufRead + Send + Sync + Unpin>(_ps_reader: &mut R, _ps_args: Self::Args) -> impl std::future::Future<Output = #runtime::Result<Self>> + Send {
+                    async move {
+                        let #bindings = _ps_args;
+                        #decode_async
+                    }
+                }
+            }
+        }
+    };
+    quote! {
+        #decode
+        #encode
+        #decode_async_impl
+
+        impl #runtime::AsyncEncode for #ident {
+            fn encode_async<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(&self, _ps_writer: &mut W, _ps_args: Self::Args) -> impl std::future::Future<Output = #runtime::Result<()>> + Send {
+                async move {
+                    let #bindings = _ps_args;
+                    #encode_async
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+/// Parses a Rust type given by `@rust_type` or `type_map`, `None` if it is invalid. Both are checked before compiling
+pub(crate) fn parse_type(type_: &str) -> Option<TokenStream> {
+    let type_: syn::Type = syn::parse_str(type_).ok()?;
+    Some(quote! { #type_ })
+}
+
+/// The paths the generated code qualifies types by, those declared in the spec and the standard types
+const QUALIFIERS: &[&str] = &[
+    "self :: ",
+    ":: std :: vec :: ",
+    ":: std :: string :: ",
+    ":: std :: boxed :: ",
+    ":: core :: option :: ",
+];
+
+/// The tokens of a Rust type, with the types declared in the spec and the standard types by their names alone
+fn unqualified(type_: &TokenStream) -> String {
+    QUALIFIERS
+        .iter()
+        .fold(type_.to_string(), |type_, path| type_.replace(path, ""))
+}
+
+impl CompileOptions {
+    /// The Rust type a container field is held as in place of its own, given by `@rust_type("Type")` or `type_map`. Boxed fields are never mapped
+    pub(crate) fn mapped_type(&self, field: &Field) -> Option<TokenStream> {
+        if field.is_boxed.get() {
+            return None;
+        }
+        if let Some(mapped) = field
+            .attributes_named("rust_type")
+            .find_map(Attribute::string_argument)
+        {
+            return parse_type(&mapped);
+        }
+        if self.type_map.is_empty() {
+            return None;
+        }
+        let type_ref = unqualified(&emit_type_ref(&field.type_.borrow(), self));
+        self.type_map
+            .iter()
+            .find(|(from, _)| {
+                parse_type(from).map(|from| unqualified(&from)) == Some(type_ref.clone())
+            })
+            .and_then(|(_, to)| parse_type(to))
+    }
+}
+
+/// The Rust type a mapped field is borrowed as to be encoded as `type_`: a slice of the elements of arrays other than maps, the borrowed form of foreign types, and the type itself otherwise
+fn emit_borrowed_type_ref(type_: &Type, options: &CompileOptions) -> TokenStream {
+    match type_ {
+        Type::Array(array_type) if array_type.map_key_field().is_none() => {
+            let element = emit_type_ref(&array_type.element, options);
+            quote! { [#element] }
+        }
+        Type::Foreign(f) => f.obj.borrowed_type_ref(),
+        Type::Ref(field) => match &*field.target.type_.borrow() {
+            Type::Foreign(f) => f.obj.borrowed_type_ref(),
+            _ => emit_type_ref(type_, options),
+        },
+        _ => emit_type_ref(type_, options),
+    }
+}
+
+/// Emits `value`, a field held as its mapped type, converted back to `type_` to be encoded.
+/// Copyable types are converted from a clone with `From`, others are borrowed with `AsRef` as the type given by [`emit_borrowed_type_ref`], as a place the encoder takes a reference to or an `Option` of references if `optional`
+pub(crate) fn emit_unmapped(
+    value: TokenStream,
+    type_: &Type,
+    optional: bool,
+    options: &CompileOptions,
+) -> TokenStream {
+    if type_.copyable() {
+        let type_ref = emit_type_ref(type_, options);
+        return if optional {
+            quote! { #value.clone().map(<#type_ref>::from) }
+        } else {
+            quote! { <#type_ref>::from(#value.clone()) }
+        };
+    }
+    let borrowed = emit_borrowed_type_ref(type_, options);
+    if optional {
+        quote! { #value.as_ref().map(<_ as ::core::convert::AsRef<#borrowed>>::as_ref) }
+    } else {
+        quote! { (*<_ as ::core::convert::AsRef<#borrowed>>::as_ref(&#value)) }
+    }
+}
@@ -0,0 +1,165 @@
+use super::*;
+
+fn emit_constraint_check<F: Fn(&Field) -> TokenStream>(
+    name: &str,
+    constraint: &Expression,
+    value: TokenStream,
+    options: &CompileOptions,
+    access: &F,
+) -> TokenStream {
+    let resolver = |f: &Arc<Field>| -> TokenStream {
+        if f.name == name {
+            return value.clone();
+        }
+        if f.is_fixed.get() {
+            if let Some(fixed) = &*f.calculated.borrow() {
+                return emit_expression(fixed, options, &|_: &Arc<Field>| {
+                    unreachable!("fixed values cannot reference fields")
+                });
+            }
+        }
+        let place = access(f);
+        if options.mapped_type(f).is_some() {
+            emit_unmapped(
+                place,
+                &f.type_.borrow(),
+                f.condition.borrow().is_some(),
+                options,
+            )
+        } else {
+            place
+        }
+    };
+    let constraint = emit_expression(constraint, options, &resolver);
+    let message = format!("constraint violated for field {}", name);
+    quote! {
+        if !(#constraint) {
+            return Err(encode_error(#message).into());
+        }
+    }
+}
+
+fn validate_container<F: Fn(&Field) -> TokenStream>(
+    container: &ContainerType,
+    options: &CompileOptions,
+    access: &F,
+    conditional: bool,
+    checks: &mut Vec<TokenStream>,
+) {
+    for (name, field) in container.items.iter() {
+        let conditional = conditional || field.condition.borrow().is_some();
+        if let Type::Container(sub_container) = &*field.type_.borrow() {
+            validate_container(sub_container, options, access, conditional, checks);
+            continue;
+        }
+        let constraint = field.constraint.borrow();
+        let constraint = match &*constraint {
+            Some(constraint) if constraint.stream_intrinsics().is_empty() => constraint,
+            _ => continue,
+        };
+        let mut place = access(field);
+        if options.mapped_type(field).is_some() {
+            place = emit_unmapped(place, &field.type_.borrow(), conditional, options);
+        }
+        let ident = options.emit_field(name);
+        let value = if conditional {
+            quote! { (*#ident) }
+        } else {
+            place.clone()
+        };
+        let check = emit_constraint_check(name, constraint, value, options, access);
+        checks.push(if conditional {
+            quote! {
+                if let Some(#ident) = (#place).as_ref() {
+                    #check
+                }
+            }
+        } else {
+            check
+        });
+    }
+}
+
+/// Emits the body of `validate`, checking every field constraint against an in-memory value
+pub fn prepare_validate(field: &Arc<Field>, options: &CompileOptions) -> TokenStream {
+    let type_ = field.type_.borrow();
+    let container = match &*type_ {
+        Type::Container(container) => container,
+        _ => return quote! { Ok(()) },
+    };
+    if !container.is_enum.get() {
+        let mut checks = vec![];
+        validate_container(
+            container,
+            options,
+            &|field: &Field| {
+                let name = options.emit_member(field);
+                quote! { self.#name }
+            },
+            container.set.is_some(),
+            &mut checks,
+        );
+        let checks = flatten(checks);
+        return quote! {
+            #checks
+            Ok(())
+        };
+    }
+    let mut arms = vec![];
+    for (name, child) in container.items.iter() {
+        let variant = options.emit_variant(child);
+        let mut checks = vec![];
+        let access = |field: &Field| {
+            let name = options.emit_field(&field.name);
+            quote! { (*#name) }
+        };
+        match &*child.type_.borrow() {
+            Type::Container(sub_container) => {
+                validate_container(sub_container, options, &access, false, &mut checks);
+                let bindings = flatten_separated(
+                    sub_container
+                        .flatten_view()
+                        .filter(|(_, x)| !x.is_pad.get() && !x.is_fixed.get())
+                        .map(|(_, x)| options.emit_binding(&x)),
+                    quote! { , },
+                );
+                let checks = flatten(checks);
+                arms.push(quote! {
+                    Self::#variant { #bindings } => {
+                        #checks
+                    }
+                });
+            }
+            _ => {
+                let constraint = child.constraint.borrow();
+                // constraints on the stream position can only be checked while encoding
+                if let Some(constraint) = constraint
+                    .as_ref()
+                    .filter(|x| x.stream_intrinsics().is_empty())
+                {
+                    checks.push(emit_constraint_check(
+                        name,
+                        constraint,
+                        access(child),
+                        options,
+                        &access,
+                    ));
+                }
+                let checks = flatten(checks);
+                let ident = options.emit_field(name);
+                arms.push(quote! {
+                    Self::#variant(#ident) => {
+                        #checks
+                    }
+                });
+            }
+        }
+    }
+    let arms = flatten(arms);
+    quote! {
+        match self {
+            #arms
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,16 @@
 use proc_macro2::TokenStream;
 
-use crate::{asg::Type, FFIArgument, FFIArgumentValue};
+use crate::{asg::Type, result::StdResult, FFIArgument, FFIArgumentValue};
+
+/// Positional state of the stream currently being decoded or encoded
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StreamIntrinsic {
+    Offset,
+    Remaining,
+    Eof,
+    // a byte ahead of the stream, read without consuming it
+    Peek,
+}
 
 pub type ForeignFunctionObj = Box<dyn ForeignFunction + Send + Sync + 'static>;
 
@@ -10,4 +20,19 @@ pub trait ForeignFunction {
     fn return_type(&self) -> Type;
 
     fn call(&self, arguments: &[FFIArgumentValue]) -> TokenStream;
+
+    /// Checks the types of the arguments of a call, returning why they are invalid if they are
+    fn check_arguments(&self, _arguments: &[Type]) -> StdResult<(), String> {
+        Ok(())
+    }
+
+    /// If true, calculated fields using this function are recomputed and verified when decoding
+    fn verify_on_decode(&self) -> bool {
+        false
+    }
+
+    /// If set, calls to this function read the position of the enclosing container's stream
+    fn stream_intrinsic(&self) -> Option<StreamIntrinsic> {
+        None
+    }
 }
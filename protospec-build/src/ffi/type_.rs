@@ -1,7 +1,8 @@
 use proc_macro2::TokenStream;
 
 use crate::{
-    asg::{Type, TypeArgument},
+    asg::{EncodedSize, Type, TypeArgument},
+    ast::ScalarType,
     PartialScalarType, PartialType,
 };
 
@@ -57,10 +58,16 @@ pub trait ForeignType {
     /// Emits this type as a Rust type
     fn type_ref(&self) -> TokenStream;
 
+    /// Emits the Rust type [`ForeignType::encoding_gen`] and [`ForeignType::encoded_len_gen`] also accept a reference to in place of one to [`ForeignType::type_ref`], i.e. `str` for `String`.
+    /// Fields mapped to another Rust type are borrowed as it through `AsRef` to be encoded.
+    fn borrowed_type_ref(&self) -> TokenStream {
+        self.type_ref()
+    }
+
     /**
      *  output code should be a term expression that:
-     *   1. the expression should read its input from an implicit identifier `reader` as a `&mut R` where R: Read
-     *   2. can read an arbitrary number of bytes from `reader`
+     *   1. the expression should read its input from the expression `source` as a `&mut R` where R: Read
+     *   2. can read an arbitrary number of bytes from `source`
      *   3. returns a value of the foreign type
      */
     fn decoding_gen(
@@ -74,7 +81,7 @@ pub trait ForeignType {
     /**
      * output code should be a single statement that:
      *  1. takes an expression `field_ref` as a reference to a value of the foreign type
-     *  2. the statement should write its output to an implicit identifier `writer` as a `&mut W` where W: Write
+     *  2. the statement should write its output to the expression `target` as a `&mut W` where W: Write
      */
     fn encoding_gen(
         &self,
@@ -84,10 +91,41 @@ pub trait ForeignType {
         is_async: bool,
     ) -> TokenStream;
 
+    /**
+     * output code, if any, should be an expression that:
+     *  1. takes an expression `field_ref` as a reference to a value of the foreign type
+     *  2. evaluates to the `usize` number of bytes [`ForeignType::encoding_gen`] writes for it
+     *
+     * if `None`, types holding this type are encoded to measure their length
+     */
+    fn encoded_len_gen(
+        &self,
+        _field_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+    ) -> Option<TokenStream> {
+        None
+    }
+
     /// All arguments that can be passed to this type to describe characteristics (i.e. string length)
     /// All optional arguments must come at the end of the list of arguments.
     fn arguments(&self) -> Vec<TypeArgument>;
 
     /// If true, this type can be freely copied
     fn copyable(&self) -> bool;
+
+    /// If true, this type implements `Eq`, `Hash` and `Ord`, so that it can key `+map` arrays
+    fn hashable(&self) -> bool {
+        self.scalar_type().is_some()
+    }
+
+    /// If this type holds a plain integer, the scalar type it is represented as.
+    /// Such types coerce and cast like that scalar, i.e. they can be used as array lengths.
+    fn scalar_type(&self) -> Option<ScalarType> {
+        None
+    }
+
+    /// The number of bytes values of this type encode to, if known statically
+    fn encoded_size(&self) -> EncodedSize {
+        EncodedSize::Unbounded
+    }
 }
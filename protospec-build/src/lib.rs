@@ -48,6 +48,18 @@ pub struct Options {
     pub include_async: bool,
     pub use_anyhow: bool,
     pub debug_mode: bool,
+    /// Report checksum mismatches on stderr instead of failing to decode
+    pub warn_on_checksum_mismatch: bool,
+    /// Path of the map type `+map` arrays decode into
+    pub map_type: String,
+    /// Represent arrays of constant length as `[T; N]` rather than `Vec<T>`, on by default
+    pub fixed_arrays: bool,
+    /// Path of the `protospec` runtime crate, see [`CompileOptions::runtime`]. With `include_async`, its `async` feature is needed
+    pub runtime: Option<String>,
+    /// See [`CompileOptions::rust_case`]
+    pub rust_case: bool,
+    /// See [`CompileOptions::type_map`]
+    pub type_map: Vec<(String, String)>,
 }
 
 impl Default for Options {
@@ -56,6 +68,7 @@ impl Default for Options {
             format_output: true,
             include_async: false,
             debug_mode: false,
+            warn_on_checksum_mismatch: false,
             enum_derives: vec![
                 "Eq".to_string(),
                 "PartialEq".to_string(),
@@ -71,6 +84,29 @@ impl Default for Options {
                 "Default".to_string(),
             ],
             use_anyhow: false,
+            map_type: "indexmap::IndexMap".to_string(),
+            fixed_arrays: true,
+            runtime: Some("protospec".to_string()),
+            rust_case: false,
+            type_map: vec![],
+        }
+    }
+}
+
+impl Options {
+    pub fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            enum_derives: self.enum_derives.clone(),
+            struct_derives: self.struct_derives.clone(),
+            include_async: self.include_async,
+            use_anyhow: self.use_anyhow,
+            debug_mode: self.debug_mode,
+            warn_on_checksum_mismatch: self.warn_on_checksum_mismatch,
+            map_type: self.map_type.clone(),
+            fixed_arrays: self.fixed_arrays,
+            runtime: self.runtime.clone(),
+            rust_case: self.rust_case,
+            type_map: self.type_map.clone(),
         }
     }
 }
@@ -93,14 +129,8 @@ pub fn compile_spec(name: &str, spec: &str, options: &Options) -> AsgResult<()>
     let resolver = PreludeImportResolver(NullImportResolver);
     let program =
         asg::Program::from_ast(&parse(spec).map_err(|x| -> Error { x.into() })?, &resolver)?;
-    let compiler_options = CompileOptions {
-        enum_derives: options.enum_derives.clone(),
-        struct_derives: options.struct_derives.clone(),
-        include_async: options.include_async,
-        use_anyhow: options.use_anyhow,
-        debug_mode: options.debug_mode,
-    };
-    let compiled = compiler::compile_program(&program, &compiler_options);
+    program.check_rust_names(options.rust_case)?;
+    let compiled = compiler::compile_program(&program, &options.compile_options());
     let mut compiled = compiled.to_string();
     if options.format_output {
         compiled = rustfmt(&compiled);
@@ -0,0 +1,88 @@
+use super::*;
+
+/// Any `@name` or `@name(arguments)` attributes
+pub fn parse_attributes(t: &mut TokenIter) -> ParseResult<Vec<Attribute>> {
+    let mut attributes = vec![];
+    while let Some(start) = t.eat(Token::At) {
+        let mut attribute = parse_attribute(t)?;
+        attribute.span = start.span + attribute.span;
+        attributes.push(attribute);
+    }
+    Ok(attributes)
+}
+
+fn parse_attribute(t: &mut TokenIter) -> ParseResult<Attribute> {
+    let name = parse_attribute_name(t)?;
+    let mut span = name.span;
+    let arguments = if t.eat(Token::LeftParen).is_some() {
+        let mut arguments = vec![];
+        loop {
+            if let Some(token) = t.eat(Token::RightParen) {
+                span = span + token.span;
+                break;
+            }
+            arguments.push(parse_attribute_argument(t)?);
+            if t.eat(Token::Comma).is_none() {
+                span = span + t.expect(Token::RightParen)?;
+                break;
+            }
+        }
+        Some(arguments)
+    } else {
+        None
+    };
+    Ok(Attribute {
+        name,
+        arguments,
+        span,
+    })
+}
+
+/// An identifier, or a keyword such as `u8` or `default`, which are common in attributes passed through to backends
+fn parse_attribute_name(t: &mut TokenIter) -> ParseResult<Ident> {
+    let token = t.expect_any()?;
+    let name = match &token.token {
+        Token::Ident(name) => name.clone(),
+        Token::Int(_) | Token::String(_) => {
+            return Err(ParseError::Unexpected(token, "attribute name".to_string()))
+        }
+        keyword => {
+            let name = keyword.to_string().trim().to_string();
+            if !name.bytes().all(|x| x.is_ascii_alphanumeric() || x == b'_') {
+                return Err(ParseError::Unexpected(token, "attribute name".to_string()));
+            }
+            name
+        }
+    };
+    Ok(Ident {
+        name,
+        span: token.span,
+    })
+}
+
+fn parse_attribute_argument(t: &mut TokenIter) -> ParseResult<AttributeArgument> {
+    if is_attribute_literal(t.peek()?) {
+        return Ok(AttributeArgument::Literal(parse_primary_expression(t)?));
+    }
+    let attribute = parse_attribute(t)?;
+    if attribute.arguments.is_none() && t.eat(Token::Equal).is_some() {
+        if !is_attribute_literal(t.peek()?) {
+            return Err(ParseError::Unexpected(
+                t.expect_any()?,
+                "integer, string, or boolean literal".to_string(),
+            ));
+        }
+        return Ok(AttributeArgument::Value(
+            attribute.name,
+            parse_primary_expression(t)?,
+        ));
+    }
+    Ok(AttributeArgument::Attribute(attribute))
+}
+
+fn is_attribute_literal(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Int(_) | Token::String(_) | Token::True | Token::False
+    )
+}
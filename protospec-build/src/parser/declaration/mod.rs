@@ -13,8 +13,15 @@ mod type_declaration;
 use type_declaration::*;
 
 pub fn parse_declaration(t: &mut TokenIter) -> ParseResult<Declaration> {
+    let attributes = parse_attributes(t)?;
+    if !attributes.is_empty() && !t.peek_token(Token::Type)? {
+        return Err(ParseError::Unexpected(
+            t.expect_any()?,
+            "'type'".to_string(),
+        ));
+    }
     let declaration = match t.peek()? {
-        Token::Type => Declaration::Type(parse_type_declaration(t)?),
+        Token::Type => Declaration::Type(parse_type_declaration(t, attributes)?),
         Token::Import => Declaration::Import(parse_import_declaration(t)?),
         Token::ImportFfi => Declaration::Ffi(parse_ffi_declaration(t)?),
         Token::Const => Declaration::Const(parse_const_declaration(t)?),
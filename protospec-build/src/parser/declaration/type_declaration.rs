@@ -1,6 +1,9 @@
 use super::*;
 
-pub fn parse_type_declaration(t: &mut TokenIter) -> ParseResult<TypeDeclaration> {
+pub fn parse_type_declaration(
+    t: &mut TokenIter,
+    attributes: Vec<Attribute>,
+) -> ParseResult<TypeDeclaration> {
     let start = t.expect(Token::Type)?;
     let name = t.expect_ident()?;
     let mut arguments = vec![];
@@ -36,5 +39,6 @@ pub fn parse_type_declaration(t: &mut TokenIter) -> ParseResult<TypeDeclaration>
         name,
         value,
         arguments,
+        attributes,
     })
 }
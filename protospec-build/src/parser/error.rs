@@ -19,7 +19,24 @@ pub enum ParseError {
     BitfieldMissingRep(Span),
     #[error("unknown container directive '{0}' @ {1}'")]
     UnknownContainerDirective(String, Span),
+    #[error("unknown array terminator policy '{0}' @ {1}'")]
+    UnknownTerminatorPolicy(String, Span),
 
     #[error("unknown")]
     Unknown(#[from] crate::Error),
 }
+
+impl ParseError {
+    /// Location in the source of the error, if known
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            ParseError::Unexpected(token, _) => Some(&token.span),
+            ParseError::EmptyLengthConstraint(span)
+            | ParseError::EnumMissingRep(span)
+            | ParseError::BitfieldMissingRep(span)
+            | ParseError::UnknownContainerDirective(_, span)
+            | ParseError::UnknownTerminatorPolicy(_, span) => Some(span),
+            ParseError::TokenError(_) | ParseError::UnexpectedEOF | ParseError::Unknown(_) => None,
+        }
+    }
+}
@@ -22,7 +22,7 @@ mod rel;
 use rel::*;
 
 mod shift;
-use shift::*;
+pub use shift::parse_shift_expression;
 
 mod add;
 use add::*;
@@ -40,7 +40,7 @@ mod array_index;
 use array_index::*;
 
 mod primary;
-use primary::*;
+pub use primary::parse_primary_expression;
 
 pub fn parse_expression(t: &mut TokenIter) -> ParseResult<Expression> {
     let expr = parse_or_expression(t)?;
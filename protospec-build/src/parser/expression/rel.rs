@@ -19,5 +19,36 @@ pub fn parse_rel_expression(t: &mut TokenIter) -> ParseResult<Expression> {
             right: Box::new(right),
         })
     }
+    if t.eat(Token::In).is_some() {
+        expr = parse_range_membership(t, expr)?;
+    }
     Ok(expr)
 }
+
+/// `x in a..b` and `x in a..=b`, desugared to `x >= a && x < b` and `x >= a && x <= b`
+fn parse_range_membership(t: &mut TokenIter, expr: Expression) -> ParseResult<Expression> {
+    let low = parse_shift_expression(t)?;
+    let inclusive = t.expect_oneof(&[Token::DotDot, Token::DotDotEq])?.token == Token::DotDotEq;
+    let high = parse_shift_expression(t)?;
+    let span = *expr.span() + *high.span();
+    Ok(Expression::Binary(BinaryExpression {
+        span,
+        op: BinaryOp::And,
+        left: Box::new(Expression::Binary(BinaryExpression {
+            span: *expr.span() + *low.span(),
+            op: BinaryOp::Gte,
+            left: Box::new(expr.clone()),
+            right: Box::new(low),
+        })),
+        right: Box::new(Expression::Binary(BinaryExpression {
+            span,
+            op: if inclusive {
+                BinaryOp::Lte
+            } else {
+                BinaryOp::Lt
+            },
+            left: Box::new(expr),
+            right: Box::new(high),
+        })),
+    }))
+}
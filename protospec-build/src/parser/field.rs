@@ -4,9 +4,12 @@ pub fn parse_field(t: &mut TokenIter) -> ParseResult<Field> {
     let start = t.peek_span()?;
 
     let type_ = parse_type(t)?;
+    let pointer = parse_pointer_clause(t)?;
     let FieldComponents {
         calculated,
+        fixed,
         flags,
+        constraint,
         condition,
         transforms,
     } = parse_field_components(t)?;
@@ -18,21 +21,38 @@ pub fn parse_field(t: &mut TokenIter) -> ParseResult<Field> {
             .or_else(|| condition.as_ref().map(|x| *x.span() + start))
             .unwrap_or(start),
         type_,
+        pointer,
         calculated,
+        fixed,
+        constraint,
         condition,
         transforms,
         flags,
+        attributes: vec![],
     };
     Ok(out)
 }
 
 struct FieldComponents {
     calculated: Option<Box<Expression>>,
-    flags: Vec<Ident>,
+    fixed: Option<Box<Expression>>,
+    flags: Vec<Flag>,
+    constraint: Option<Box<Expression>>,
     condition: Option<Box<Expression>>,
     transforms: Vec<Transform>,
 }
 
+fn parse_pointer_clause(t: &mut TokenIter) -> ParseResult<Option<Box<Expression>>> {
+    Ok(if t.eat(Token::At).is_some() {
+        t.expect(Token::LeftParen)?;
+        let pointer = parse_expression(t)?;
+        t.expect(Token::RightParen)?;
+        Some(Box::new(pointer))
+    } else {
+        None
+    })
+}
+
 fn parse_conditional_clause(t: &mut TokenIter) -> ParseResult<Option<Box<Expression>>> {
     Ok(if t.eat(Token::LeftCurly).is_some() {
         let condition = parse_expression(t)?;
@@ -52,11 +72,33 @@ fn parse_calculated_clause(t: &mut TokenIter) -> ParseResult<Option<Box<Expressi
     })
 }
 
+fn parse_constraint_clause(t: &mut TokenIter) -> ParseResult<Option<Box<Expression>>> {
+    Ok(if t.eat(Token::Where).is_some() {
+        let constraint = parse_expression(t)?;
+        Some(Box::new(constraint))
+    } else {
+        None
+    })
+}
+
+fn parse_fixed_clause(t: &mut TokenIter) -> ParseResult<Option<Box<Expression>>> {
+    Ok(if t.eat(Token::Eq).is_some() {
+        let fixed = parse_expression(t)?;
+        Some(Box::new(fixed))
+    } else {
+        None
+    })
+}
+
 fn parse_field_components(t: &mut TokenIter) -> ParseResult<FieldComponents> {
+    let fixed = parse_fixed_clause(t)?;
+
     let calculated = parse_calculated_clause(t)?;
 
     let flags = parse_flags(t)?;
 
+    let constraint = parse_constraint_clause(t)?;
+
     let condition = parse_conditional_clause(t)?;
 
     let mut transforms = vec![];
@@ -92,7 +134,9 @@ fn parse_field_components(t: &mut TokenIter) -> ParseResult<FieldComponents> {
 
     Ok(FieldComponents {
         calculated,
+        fixed,
         flags,
+        constraint,
         condition,
         transforms,
     })
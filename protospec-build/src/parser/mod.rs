@@ -19,6 +19,9 @@ use types::*;
 mod field;
 use field::*;
 
+mod attribute;
+use attribute::*;
+
 mod program;
 use program::*;
 
@@ -46,10 +49,16 @@ fn parse_arguments(t: &mut TokenIter, span: &mut Span) -> ParseResult<Vec<Expres
     Ok(arguments)
 }
 
-fn parse_flags(t: &mut TokenIter) -> ParseResult<Vec<Ident>> {
+fn parse_flags(t: &mut TokenIter) -> ParseResult<Vec<Flag>> {
     let mut out = vec![];
     while t.eat(Token::Plus).is_some() {
-        out.push(t.expect_ident()?);
+        let Ident { name, mut span } = t.expect_ident()?;
+        let arguments = parse_arguments(t, &mut span)?;
+        out.push(Flag {
+            name,
+            arguments,
+            span,
+        });
     }
     Ok(out)
 }
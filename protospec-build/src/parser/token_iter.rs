@@ -32,6 +32,11 @@ impl TokenIter {
             .ok_or(ParseError::UnexpectedEOF)
     }
 
+    /// Peeks `offset` tokens past the next one
+    pub fn peek_token_at(&self, offset: usize, token: Token) -> bool {
+        self.inner.len() > offset && self.inner[self.inner.len() - 1 - offset].token == token
+    }
+
     pub fn peek_span(&self) -> ParseResult<Span> {
         self.inner
             .last()
@@ -1,7 +1,44 @@
 use super::*;
 
 pub fn parse_length_constraint(t: &mut TokenIter) -> ParseResult<LengthConstraint> {
+    if let Some(start) = t.eat(Token::DotDotEq) {
+        let until = parse_until(t, start.span, true)?;
+        return Ok(LengthConstraint {
+            span: until.span,
+            expandable: true,
+            inner: None,
+            until: Some(until),
+            bytes: false,
+            terminator: TerminatorPolicy::default(),
+        });
+    }
+    if t.peek_token(Token::Ident("bytes".to_string()))? && t.peek_token_at(1, Token::Colon) {
+        let start = t.expect_ident()?.span;
+        t.expect(Token::Colon)?;
+        let expression = parse_expression(t)?;
+        return Ok(LengthConstraint {
+            span: start + *expression.span(),
+            expandable: false,
+            inner: Some(Box::new(expression)),
+            until: None,
+            bytes: true,
+            terminator: TerminatorPolicy::default(),
+        });
+    }
     let start = t.eat(Token::DotDot);
+    if let Some(start) = &start {
+        if t.peek_token(Token::Until)? {
+            let until = parse_until(t, start.span, false)?;
+            return Ok(LengthConstraint {
+                span: until.span,
+                expandable: true,
+                inner: None,
+                until: Some(until),
+                bytes: false,
+                terminator: TerminatorPolicy::default(),
+            });
+        }
+    }
     let expression = if t.peek_token(Token::RightSquare)? {
         None
     } else {
@@ -12,6 +49,12 @@ pub fn parse_length_constraint(t: &mut TokenIter) -> ParseResult<LengthConstrain
         return Err(ParseError::EmptyLengthConstraint(t.peek_span()?));
     }
 
+    let terminator = if start.is_some() && expression.is_some() {
+        parse_terminator_policy(t)?
+    } else {
+        TerminatorPolicy::default()
+    };
+
     Ok(LengthConstraint {
         span: start
             .as_ref()
@@ -20,5 +63,43 @@ pub fn parse_length_constraint(t: &mut TokenIter) -> ParseResult<LengthConstrain
             .unwrap(),
         expandable: start.is_some(),
         inner: expression,
+        until: None,
+        bytes: false,
+        terminator,
+    })
+}
+
+fn parse_terminator_policy(t: &mut TokenIter) -> ParseResult<TerminatorPolicy> {
+    let mut policy = TerminatorPolicy::default();
+    while t.eat(Token::Comma).is_some() {
+        policy.alternatives.push(parse_expression(t)?);
+    }
+    if t.eat(Token::Semicolon).is_some() {
+        loop {
+            let flag = t.expect_ident()?;
+            match &*flag.name {
+                "leave" => policy.leave = true,
+                "eof" => policy.eof = true,
+                _ => return Err(ParseError::UnknownTerminatorPolicy(flag.name, flag.span)),
+            }
+            if t.eat(Token::Comma).is_none() {
+                break;
+            }
+        }
+    }
+    Ok(policy)
+}
+
+fn parse_until(t: &mut TokenIter, start: Span, inclusive: bool) -> ParseResult<ArrayUntil> {
+    t.expect(Token::Until)?;
+    t.expect(Token::BitOr)?;
+    let element = t.expect_ident()?;
+    t.expect(Token::BitOr)?;
+    let predicate = parse_expression(t)?;
+    Ok(ArrayUntil {
+        span: start + *predicate.span(),
+        inclusive,
+        element,
+        predicate: Box::new(predicate),
     })
 }
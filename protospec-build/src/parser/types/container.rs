@@ -13,6 +13,19 @@ pub fn parse_container(t: &mut TokenIter) -> ParseResult<Container> {
 
     let flags = parse_flags(t)?;
 
+    let discriminant = if t.eat(Token::Match).is_some() {
+        Some(Box::new(parse_expression(t)?))
+    } else {
+        None
+    };
+
+    let set = if discriminant.is_none() && matches!(t.peek()?, Token::Ident(name) if name == "set")
+    {
+        Some(Box::new(parse_record_layout(t)?))
+    } else {
+        None
+    };
+
     t.expect(Token::LeftCurly)?;
 
     let mut items = vec![];
@@ -30,6 +43,16 @@ pub fn parse_container(t: &mut TokenIter) -> ParseResult<Container> {
                     }
                     continue;
                 }
+                "align" => {
+                    t.expect(Token::LeftParen)?;
+                    let alignment = parse_expression(t)?;
+                    t.expect(Token::RightParen)?;
+                    items.push(ContainerItem::Align(alignment));
+                    if t.eat(Token::Comma).is_none() {
+                        break;
+                    }
+                    continue;
+                }
                 _ => {
                     return Err(ParseError::UnknownContainerDirective(
                         ident.name.clone(),
@@ -39,10 +62,28 @@ pub fn parse_container(t: &mut TokenIter) -> ParseResult<Container> {
             }
         }
 
+        let attributes = parse_attributes(t)?;
+
+        let patterns = if discriminant.is_some() {
+            let patterns = parse_patterns(t)?;
+            t.expect(Token::FatArrow)?;
+            Some(patterns)
+        } else if set.is_some() {
+            let tag = parse_expression(t)?;
+            t.expect(Token::FatArrow)?;
+            Some(vec![Pattern::Value(Box::new(tag))])
+        } else {
+            None
+        };
+
         let ident = t.expect_ident()?;
         t.expect(Token::Colon)?;
-        let type_ = parse_field(t)?;
-        items.push(ContainerItem::Field(ident, type_));
+        let mut type_ = parse_field(t)?;
+        type_.attributes = attributes;
+        items.push(match patterns {
+            Some(patterns) => ContainerItem::Arm(patterns, ident, type_),
+            None => ContainerItem::Field(ident, type_),
+        });
         if !t.eat(Token::Comma).is_some() {
             break;
         }
@@ -53,7 +94,54 @@ pub fn parse_container(t: &mut TokenIter) -> ParseResult<Container> {
     Ok(Container {
         span: start + end,
         length,
+        discriminant,
+        set,
         flags,
         items,
     })
 }
+
+/// `set(tag_type, length_type)`
+fn parse_record_layout(t: &mut TokenIter) -> ParseResult<RecordLayout> {
+    let start = t.expect_ident()?.span;
+    t.expect(Token::LeftParen)?;
+    let tag = parse_type(t)?;
+    t.expect(Token::Comma)?;
+    let length = parse_type(t)?;
+    let end = t.expect(Token::RightParen)?;
+    Ok(RecordLayout {
+        tag,
+        length,
+        span: start + end,
+    })
+}
+
+/// `pattern | pattern | ...`, where a pattern is `_`, a value, or a range `a..b` or `a..=b`
+fn parse_patterns(t: &mut TokenIter) -> ParseResult<Vec<Pattern>> {
+    let mut patterns = vec![];
+    loop {
+        if let Token::Ident(name) = t.peek()? {
+            if name == "_" {
+                let ident = t.expect_ident()?;
+                patterns.push(Pattern::Wildcard(ident.span));
+                if t.eat(Token::BitOr).is_none() {
+                    break;
+                }
+                continue;
+            }
+        }
+        let value = parse_shift_expression(t)?;
+        match t.eat_any(&[Token::DotDot, Token::DotDotEq]) {
+            Some(SpannedToken { token, .. }) => patterns.push(Pattern::Range {
+                low: Box::new(value),
+                high: Box::new(parse_shift_expression(t)?),
+                inclusive: token == Token::DotDotEq,
+            }),
+            None => patterns.push(Pattern::Value(Box::new(value))),
+        }
+        if t.eat(Token::BitOr).is_none() {
+            break;
+        }
+    }
+    Ok(patterns)
+}
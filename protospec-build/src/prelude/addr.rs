@@ -0,0 +1,22 @@
+use super::*;
+
+/// a special function used for generating encoders that returns the absolute offset a pointer field is laid out at, back-patched once known
+pub struct AddrFunction;
+
+impl ForeignFunction for AddrFunction {
+    fn arguments(&self) -> Vec<FFIArgument> {
+        vec![FFIArgument {
+            name: "target".to_string(),
+            type_: None,
+            optional: false,
+        }]
+    }
+
+    fn return_type(&self) -> Type {
+        Type::Scalar(ScalarType::U64.into())
+    }
+
+    fn call(&self, _arguments: &[FFIArgumentValue]) -> TokenStream {
+        unreachable!("addr() is only encoded as a pointer offset")
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{Endian, EndianScalarType};
+
+use super::*;
+
+/// The number of fields a checksum function can cover
+const MAX_CHECKSUM_INPUTS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChecksumAlgorithm {
+    /// CRC-32/ISO-HDLC, as used by zlib, PNG and ethernet
+    Crc32,
+    /// CRC-32/ISCSI (Castagnoli)
+    Crc32c,
+    /// CRC-16/ARC
+    Crc16,
+    /// CRC-16/MODBUS
+    Crc16Modbus,
+    /// CRC-16/IBM-3740, commonly called CRC-16/CCITT-FALSE
+    Crc16Ccitt,
+    /// CRC-16/XMODEM
+    Crc16Xmodem,
+    /// CRC-16/KERMIT
+    Crc16Kermit,
+    Adler32,
+    Fletcher16,
+    /// Fletcher-32 over little-endian 16-bit words, an odd trailing byte is zero padded
+    Fletcher32,
+    Xor8,
+    Sum8,
+    // requires sha1 crate
+    Sha1,
+    // requires sha2 crate
+    Sha256,
+}
+
+/// A checksum over the encoded form of its arguments.
+/// Calculated fields using a checksum are filled in when encoding and verified when decoding.
+pub struct ChecksumFunction(pub ChecksumAlgorithm);
+
+impl ChecksumFunction {
+    /// Emits statements appending the encoded form of `value` to an implicit `data: Vec<u8>`
+    fn input(&self, type_: &Type, value: TokenStream) -> TokenStream {
+        match type_ {
+            Type::Scalar(EndianScalarType {
+                endian: Endian::Little,
+                ..
+            }) => quote! {
+                data.extend_from_slice(&(#value).to_le_bytes()[..]);
+            },
+            Type::Scalar(_) | Type::F32 | Type::F64 => quote! {
+                data.extend_from_slice(&(#value).to_be_bytes()[..]);
+            },
+            Type::Bool => quote! {
+                data.push(if #value { 1u8 } else { 0u8 });
+            },
+            Type::Enum(e) if e.rep.is_little_endian() => quote! {
+                data.extend_from_slice(&(#value).to_repr().to_le_bytes()[..]);
+            },
+            Type::Enum(_) => quote! {
+                data.extend_from_slice(&(#value).to_repr().to_be_bytes()[..]);
+            },
+            Type::Bitfield(e) if e.rep.is_little_endian() => quote! {
+                data.extend_from_slice(&(#value).0.to_le_bytes()[..]);
+            },
+            Type::Bitfield(_) => quote! {
+                data.extend_from_slice(&(#value).0.to_be_bytes()[..]);
+            },
+            Type::Array(array) => {
+                let element = self.input(&array.element, quote! { (*x) });
+                quote! {
+                    for x in (#value).iter() {
+                        #element
+                    }
+                }
+            }
+            Type::Ref(type_ref) => match &*type_ref.target.type_.borrow() {
+                Type::Foreign(f) => {
+                    let encoding =
+                        f.obj
+                            .encoding_gen(quote! { data }, quote! { (#value) }, vec![], false);
+                    quote! {
+                        {
+                            use std::io::Write;
+                            #encoding
+                        }
+                    }
+                }
+                _ => quote! {
+                    (#value).encode_sync(&mut data)?;
+                },
+            },
+            Type::Container(_) | Type::Foreign(_) => {
+                unreachable!("invalid type for checksum, rejected by check_input")
+            }
+        }
+    }
+
+    /// Checks that `type_` can be encoded by `input`
+    fn check_input(type_: &Type) -> StdResult<(), String> {
+        match type_ {
+            Type::Array(array) => Self::check_input(&array.element),
+            Type::Ref(type_ref) if !type_ref.arguments.is_empty() => Err(format!(
+                "cannot checksum type '{}', which has arguments",
+                type_ref.target.name
+            )),
+            Type::Container(_) | Type::Foreign(_) => {
+                Err("expected a field or top level type".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Emits an expression computing the checksum of an implicit `data: Vec<u8>`
+    fn digest(&self) -> TokenStream {
+        match self.0 {
+            ChecksumAlgorithm::Crc32 => reflected_crc(
+                quote! { u32 },
+                0xedb8_8320u32,
+                0xffff_ffffu32,
+                0xffff_ffffu32,
+            ),
+            ChecksumAlgorithm::Crc32c => reflected_crc(
+                quote! { u32 },
+                0x82f6_3b78u32,
+                0xffff_ffffu32,
+                0xffff_ffffu32,
+            ),
+            ChecksumAlgorithm::Crc16 => reflected_crc(quote! { u16 }, 0xa001u16, 0u16, 0u16),
+            ChecksumAlgorithm::Crc16Modbus => {
+                reflected_crc(quote! { u16 }, 0xa001u16, 0xffffu16, 0u16)
+            }
+            ChecksumAlgorithm::Crc16Kermit => reflected_crc(quote! { u16 }, 0x8408u16, 0u16, 0u16),
+            ChecksumAlgorithm::Crc16Ccitt => quote! {
+                {
+                    let mut crc = 0xffffu16;
+                    for byte in data.iter() {
+                        crc ^= (*byte as u16) << 8;
+                        for _ in 0..8 {
+                            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+                        }
+                    }
+                    crc
+                }
+            },
+            ChecksumAlgorithm::Crc16Xmodem => quote! {
+                {
+                    let mut crc = 0u16;
+                    for byte in data.iter() {
+                        crc ^= (*byte as u16) << 8;
+                        for _ in 0..8 {
+                            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+                        }
+                    }
+                    crc
+                }
+            },
+            ChecksumAlgorithm::Adler32 => quote! {
+                {
+                    let (mut a, mut b) = (1u32, 0u32);
+                    for byte in data.iter() {
+                        a = (a + *byte as u32) % 65521;
+                        b = (b + a) % 65521;
+                    }
+                    (b << 16) | a
+                }
+            },
+            ChecksumAlgorithm::Fletcher16 => quote! {
+                {
+                    let (mut a, mut b) = (0u16, 0u16);
+                    for byte in data.iter() {
+                        a = (a + *byte as u16) % 255;
+                        b = (b + a) % 255;
+                    }
+                    (b << 8) | a
+                }
+            },
+            ChecksumAlgorithm::Fletcher32 => quote! {
+                {
+                    let (mut a, mut b) = (0u32, 0u32);
+                    for word in data.chunks(2) {
+                        let word = word[0] as u32 | (*word.get(1).unwrap_or(&0) as u32) << 8;
+                        a = (a + word) % 65535;
+                        b = (b + a) % 65535;
+                    }
+                    (b << 16) | a
+                }
+            },
+            ChecksumAlgorithm::Xor8 => quote! {
+                data.iter().fold(0u8, |sum, x| sum ^ *x)
+            },
+            ChecksumAlgorithm::Sum8 => quote! {
+                data.iter().fold(0u8, |sum, x| sum.wrapping_add(*x))
+            },
+            ChecksumAlgorithm::Sha1 => quote! {
+                <sha1::Sha1 as sha1::Digest>::digest(&data[..]).to_vec()
+            },
+            ChecksumAlgorithm::Sha256 => quote! {
+                <sha2::Sha256 as sha2::Digest>::digest(&data[..]).to_vec()
+            },
+        }
+    }
+}
+
+fn reflected_crc<T: ToTokens>(
+    type_: TokenStream,
+    polynomial: T,
+    init: T,
+    final_xor: T,
+) -> TokenStream {
+    quote! {
+        {
+            let mut crc: #type_ = #init;
+            for byte in data.iter() {
+                crc ^= *byte as #type_;
+                for _ in 0..8 {
+                    crc = if crc & 1 != 0 { (crc >> 1) ^ #polynomial } else { crc >> 1 };
+                }
+            }
+            crc ^ #final_xor
+        }
+    }
+}
+
+impl ForeignFunction for ChecksumFunction {
+    fn arguments(&self) -> Vec<FFIArgument> {
+        (0..MAX_CHECKSUM_INPUTS)
+            .map(|i| FFIArgument {
+                name: format!("input{}", i),
+                type_: None,
+                optional: i > 0,
+            })
+            .collect()
+    }
+
+    fn return_type(&self) -> Type {
+        let digest_length = match self.0 {
+            ChecksumAlgorithm::Sha1 => 20u64,
+            ChecksumAlgorithm::Sha256 => 32u64,
+            ChecksumAlgorithm::Crc32
+            | ChecksumAlgorithm::Crc32c
+            | ChecksumAlgorithm::Adler32
+            | ChecksumAlgorithm::Fletcher32 => return Type::Scalar(ScalarType::U32.into()),
+            ChecksumAlgorithm::Crc16
+            | ChecksumAlgorithm::Crc16Modbus
+            | ChecksumAlgorithm::Crc16Ccitt
+            | ChecksumAlgorithm::Crc16Xmodem
+            | ChecksumAlgorithm::Crc16Kermit
+            | ChecksumAlgorithm::Fletcher16 => return Type::Scalar(ScalarType::U16.into()),
+            ChecksumAlgorithm::Xor8 | ChecksumAlgorithm::Sum8 => {
+                return Type::Scalar(ScalarType::U8.into())
+            }
+        };
+        Type::Array(Box::new(ArrayType {
+            element: Box::new(Type::Scalar(ScalarType::U8.into())),
+            length: LengthConstraint {
+                expandable: false,
+                value: Some(Expression::Int(digest_length.into())),
+                until: None,
+                bytes: false,
+                terminator: TerminatorPolicy::default(),
+            },
+            map: None,
+        }))
+    }
+
+    fn call(&self, arguments: &[FFIArgumentValue]) -> TokenStream {
+        let inputs: TokenStream = arguments
+            .iter()
+            .filter(|x| x.present)
+            .map(|x| self.input(&x.type_, x.value.clone()))
+            .collect();
+        let digest = self.digest();
+        quote! {
+            {
+                let mut data: ::std::vec::Vec<u8> = vec![];
+                #inputs
+                #digest
+            }
+        }
+    }
+
+    fn check_arguments(&self, arguments: &[Type]) -> StdResult<(), String> {
+        arguments.iter().try_for_each(Self::check_input)
+    }
+
+    fn verify_on_decode(&self) -> bool {
+        true
+    }
+}
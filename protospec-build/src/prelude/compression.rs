@@ -0,0 +1,352 @@
+use super::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compression {
+    // requires flate2 crate
+    Zlib,
+    /// raw deflate, without zlib or gzip framing
+    // requires flate2 crate
+    Deflate,
+    // requires zstd crate
+    Zstd,
+    /// LZ4 frame format
+    // requires lz4_flex crate
+    Lz4,
+    // requires brotli crate
+    Brotli,
+    /// Snappy framing format
+    // requires snap crate
+    Snappy,
+}
+
+/// A compression transform taking an optional compression level and an optional expected decompressed size.
+/// When present, decoding more than the expected size is an error, guarding against decompression bombs.
+/// Async codegen requires the async-compression crate (except for snappy, which is buffered, then decompressed no further than the expected size).
+pub struct CompressionTransform(pub Compression);
+
+/// Wraps a `&mut AsyncWrite` such that shutting down an encoder around it finishes the encoder without shutting down the wrapped writer
+pub(super) fn async_write_shield(input_stream: TokenStream) -> TokenStream {
+    quote! {
+        {
+            struct Shield<'a, W: AsyncWrite + Unpin + ?Sized>(&'a mut W);
+            impl<'a, W: AsyncWrite + Unpin + ?Sized> AsyncWrite for Shield<'a, W> {
+                fn poll_write(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                    buf: &[u8],
+                ) -> std::task::Poll<std::io::Result<usize>> {
+                    std::pin::Pin::new(&mut *self.0).poll_write(cx, buf)
+                }
+
+                fn poll_flush(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    std::pin::Pin::new(&mut *self.0).poll_flush(cx)
+                }
+
+                fn poll_shutdown(
+                    mut self: std::pin::Pin<&mut Self>,
+                    cx: &mut std::task::Context<'_>,
+                ) -> std::task::Poll<std::io::Result<()>> {
+                    std::pin::Pin::new(&mut *self.0).poll_flush(cx)
+                }
+            }
+            Shield(#input_stream)
+        }
+    }
+}
+
+impl ForeignTransform for CompressionTransform {
+    fn decoding_gen(
+        &self,
+        input_stream: TokenStream,
+        arguments: Vec<TokenStream>,
+        is_async: bool,
+    ) -> TokenStream {
+        let decoder = if is_async {
+            match self.0 {
+                Compression::Zlib => quote! {
+                    async_compression::tokio::bufread::ZlibDecoder::new(#input_stream)
+                },
+                Compression::Deflate => quote! {
+                    async_compression::tokio::bufread::DeflateDecoder::new(#input_stream)
+                },
+                Compression::Zstd => quote! {
+                    async_compression::tokio::bufread::ZstdDecoder::new(#input_stream)
+                },
+                Compression::Lz4 => quote! {
+                    async_compression::tokio::bufread::Lz4Decoder::new(#input_stream)
+                },
+                Compression::Brotli => quote! {
+                    async_compression::tokio::bufread::BrotliDecoder::new(#input_stream)
+                },
+                Compression::Snappy => {
+                    // decompresses one byte past the expected size at most, which the size guard rejects
+                    let limit = match arguments.get(1) {
+                        Some(expected_size) => quote! { (#expected_size as u64).saturating_add(1) },
+                        None => quote! { u64::MAX },
+                    };
+                    quote! {
+                        {
+                            let mut compressed = vec![];
+                            #input_stream.read_to_end(&mut compressed).await?;
+                            let mut decompressed = vec![];
+                            std::io::Read::read_to_end(
+                                &mut std::io::Read::take(
+                                    snap::read::FrameDecoder::new(&compressed[..]),
+                                    #limit,
+                                ),
+                                &mut decompressed,
+                            )?;
+                            Cursor::new(decompressed)
+                        }
+                    }
+                }
+            }
+        } else {
+            match self.0 {
+                Compression::Zlib => quote! {
+                    flate2::bufread::ZlibDecoder::new(#input_stream)
+                },
+                Compression::Deflate => quote! {
+                    flate2::bufread::DeflateDecoder::new(#input_stream)
+                },
+                Compression::Zstd => quote! {
+                    zstd::stream::read::Decoder::with_buffer(#input_stream)?
+                },
+                Compression::Lz4 => quote! {
+                    lz4_flex::frame::FrameDecoder::new(#input_stream)
+                },
+                Compression::Brotli => quote! {
+                    brotli::Decompressor::new(#input_stream, 4096)
+                },
+                Compression::Snappy => quote! {
+                    snap::read::FrameDecoder::new(#input_stream)
+                },
+            }
+        };
+        let decoder = match arguments.get(1) {
+            Some(expected_size) if is_async => quote! {
+                {
+                    struct SizeGuard<R: AsyncRead + Unpin> {
+                        inner: R,
+                        remaining: u64,
+                    }
+                    impl<R: AsyncRead + Unpin> AsyncRead for SizeGuard<R> {
+                        fn poll_read(
+                            mut self: std::pin::Pin<&mut Self>,
+                            cx: &mut std::task::Context<'_>,
+                            buf: &mut tokio::io::ReadBuf<'_>,
+                        ) -> std::task::Poll<std::io::Result<()>> {
+                            let this = &mut *self;
+                            let filled = buf.filled().len();
+                            match std::pin::Pin::new(&mut this.inner).poll_read(cx, buf) {
+                                std::task::Poll::Ready(Ok(())) => {
+                                    let read = (buf.filled().len() - filled) as u64;
+                                    if read > this.remaining {
+                                        return std::task::Poll::Ready(Err(std::io::Error::new(
+                                            std::io::ErrorKind::InvalidData,
+                                            "decompressed data exceeds expected size",
+                                        )));
+                                    }
+                                    this.remaining -= read;
+                                    std::task::Poll::Ready(Ok(()))
+                                }
+                                other => other,
+                            }
+                        }
+                    }
+                    SizeGuard {
+                        inner: #decoder,
+                        remaining: #expected_size as u64,
+                    }
+                }
+            },
+            Some(expected_size) => quote! {
+                {
+                    struct SizeGuard<R: Read> {
+                        inner: R,
+                        remaining: u64,
+                    }
+                    impl<R: Read> Read for SizeGuard<R> {
+                        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                            let read = self.inner.read(buf)?;
+                            if read as u64 > self.remaining {
+                                return Err(std::io::Error::new(
+                                    std::io::ErrorKind::InvalidData,
+                                    "decompressed data exceeds expected size",
+                                ));
+                            }
+                            self.remaining -= read as u64;
+                            Ok(read)
+                        }
+                    }
+                    SizeGuard {
+                        inner: #decoder,
+                        remaining: #expected_size as u64,
+                    }
+                }
+            },
+            None => decoder,
+        };
+        if is_async {
+            quote! {
+                tokio::io::BufReader::new(#decoder)
+            }
+        } else {
+            quote! {
+                std::io::BufReader::new(#decoder)
+            }
+        }
+    }
+
+    fn encoding_gen(
+        &self,
+        input_stream: TokenStream,
+        arguments: Vec<TokenStream>,
+        is_async: bool,
+    ) -> TokenStream {
+        let level = arguments.into_iter().next();
+        if is_async {
+            let input_stream = async_write_shield(input_stream);
+            let level = match level {
+                Some(level) => quote! { async_compression::Level::Precise(#level as _) },
+                None => quote! { async_compression::Level::Default },
+            };
+            return match self.0 {
+                Compression::Zlib => quote! {
+                    async_compression::tokio::write::ZlibEncoder::with_quality(#input_stream, #level)
+                },
+                Compression::Deflate => quote! {
+                    async_compression::tokio::write::DeflateEncoder::with_quality(#input_stream, #level)
+                },
+                Compression::Zstd => quote! {
+                    async_compression::tokio::write::ZstdEncoder::with_quality(#input_stream, #level)
+                },
+                Compression::Lz4 => quote! {
+                    async_compression::tokio::write::Lz4Encoder::with_quality(#input_stream, #level)
+                },
+                Compression::Brotli => quote! {
+                    async_compression::tokio::write::BrotliEncoder::with_quality(#input_stream, #level)
+                },
+                Compression::Snappy => quote! {
+                    {
+                        // buffers the whole input, then compresses it on shutdown
+                        struct SnappyEncoder<W: AsyncWrite + Unpin> {
+                            inner: W,
+                            buffer: ::std::vec::Vec<u8>,
+                            compressed: ::core::option::Option<std::io::Cursor<::std::vec::Vec<u8>>>,
+                        }
+                        impl<W: AsyncWrite + Unpin> AsyncWrite for SnappyEncoder<W> {
+                            fn poll_write(
+                                mut self: std::pin::Pin<&mut Self>,
+                                _cx: &mut std::task::Context<'_>,
+                                buf: &[u8],
+                            ) -> std::task::Poll<std::io::Result<usize>> {
+                                self.buffer.extend_from_slice(buf);
+                                std::task::Poll::Ready(Ok(buf.len()))
+                            }
+
+                            fn poll_flush(
+                                self: std::pin::Pin<&mut Self>,
+                                _cx: &mut std::task::Context<'_>,
+                            ) -> std::task::Poll<std::io::Result<()>> {
+                                std::task::Poll::Ready(Ok(()))
+                            }
+
+                            fn poll_shutdown(
+                                mut self: std::pin::Pin<&mut Self>,
+                                cx: &mut std::task::Context<'_>,
+                            ) -> std::task::Poll<std::io::Result<()>> {
+                                let this = &mut *self;
+                                if this.compressed.is_none() {
+                                    let mut encoder = snap::write::FrameEncoder::new(vec![]);
+                                    std::io::Write::write_all(&mut encoder, &this.buffer[..])?;
+                                    let compressed = encoder.into_inner().map_err(|e| e.into_error())?;
+                                    this.compressed = Some(std::io::Cursor::new(compressed));
+                                }
+                                let compressed = this.compressed.as_mut().unwrap();
+                                while (compressed.position() as usize) < compressed.get_ref().len() {
+                                    let remaining = &compressed.get_ref()[compressed.position() as usize..];
+                                    match std::pin::Pin::new(&mut this.inner).poll_write(cx, remaining) {
+                                        std::task::Poll::Ready(Ok(0)) => {
+                                            return std::task::Poll::Ready(Err(std::io::ErrorKind::WriteZero.into()))
+                                        }
+                                        std::task::Poll::Ready(Ok(n)) => {
+                                            compressed.set_position(compressed.position() + n as u64)
+                                        }
+                                        std::task::Poll::Ready(Err(e)) => return std::task::Poll::Ready(Err(e)),
+                                        std::task::Poll::Pending => return std::task::Poll::Pending,
+                                    }
+                                }
+                                std::pin::Pin::new(&mut this.inner).poll_shutdown(cx)
+                            }
+                        }
+                        SnappyEncoder {
+                            inner: #input_stream,
+                            buffer: vec![],
+                            compressed: None,
+                        }
+                    }
+                },
+            };
+        }
+        match self.0 {
+            Compression::Zlib | Compression::Deflate => {
+                let encoder = if self.0 == Compression::Zlib {
+                    quote! { flate2::write::ZlibEncoder }
+                } else {
+                    quote! { flate2::write::DeflateEncoder }
+                };
+                let level = match level {
+                    Some(level) => quote! { flate2::Compression::new(#level as u32) },
+                    None => quote! { flate2::Compression::default() },
+                };
+                quote! {
+                    #encoder::new(#input_stream, #level)
+                }
+            }
+            Compression::Zstd => {
+                let level = level.unwrap_or_else(|| quote! { 0 });
+                quote! {
+                    zstd::stream::write::Encoder::new(#input_stream, #level as i32)?.auto_finish()
+                }
+            }
+            Compression::Lz4 => quote! {
+                lz4_flex::frame::FrameEncoder::new(#input_stream).auto_finish()
+            },
+            Compression::Brotli => {
+                let level = level.unwrap_or_else(|| quote! { 11 });
+                quote! {
+                    brotli::CompressorWriter::new(#input_stream, 4096, #level as u32, 22)
+                }
+            }
+            Compression::Snappy => quote! {
+                snap::write::FrameEncoder::new(#input_stream)
+            },
+        }
+    }
+
+    fn arguments(&self) -> Vec<FFIArgument> {
+        vec![
+            FFIArgument {
+                name: "level".to_string(),
+                type_: Some(Type::Scalar(
+                    if self.0 == Compression::Zstd {
+                        ScalarType::I32
+                    } else {
+                        ScalarType::U32
+                    }
+                    .into(),
+                )),
+                optional: true,
+            },
+            FFIArgument {
+                name: "expected_size".to_string(),
+                type_: Some(Type::Scalar(ScalarType::U64.into())),
+                optional: true,
+            },
+        ]
+    }
+}
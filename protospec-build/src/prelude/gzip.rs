@@ -27,6 +27,7 @@ impl ForeignTransform for GzipTransform {
         is_async: bool,
     ) -> TokenStream {
         if is_async {
+            let input_stream = async_write_shield(input_stream);
             quote! {
                 async_compression::tokio::write::GzipEncoder::new(#input_stream)
             }
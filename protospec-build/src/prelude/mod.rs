@@ -17,6 +17,10 @@ pub use base64::*;
 mod gzip;
 pub use gzip::*;
 
+// requires a crate per format, see the module
+mod compression;
+pub use compression::*;
+
 mod var;
 pub use var::*;
 
@@ -32,6 +36,9 @@ pub use len::*;
 mod blen;
 pub use blen::*;
 
+mod addr;
+pub use addr::*;
+
 mod pad;
 pub use pad::*;
 
@@ -41,6 +48,12 @@ pub use bits::*;
 mod sum;
 pub use sum::*;
 
+mod checksum;
+pub use checksum::*;
+
+mod position;
+pub use position::*;
+
 pub fn map_async(is_async: bool) -> TokenStream {
     if is_async {
         quote! { .await }
@@ -0,0 +1,46 @@
+use super::*;
+
+/// `offset()`, `remaining()`, `eof()` and `peek(n)`, bound by the coder to the position of the enclosing container's stream
+pub struct StreamPositionFunction(pub StreamIntrinsic);
+
+impl ForeignFunction for StreamPositionFunction {
+    fn arguments(&self) -> Vec<FFIArgument> {
+        match self.0 {
+            StreamIntrinsic::Peek => vec![FFIArgument {
+                name: "offset".to_string(),
+                type_: Some(Type::Scalar(ScalarType::U64.into())),
+                optional: true,
+            }],
+            _ => vec![],
+        }
+    }
+
+    fn return_type(&self) -> Type {
+        match self.0 {
+            StreamIntrinsic::Offset | StreamIntrinsic::Remaining => {
+                Type::Scalar(ScalarType::U64.into())
+            }
+            StreamIntrinsic::Eof => Type::Bool,
+            StreamIntrinsic::Peek => Type::Scalar(ScalarType::U8.into()),
+        }
+    }
+
+    fn call(&self, arguments: &[FFIArgumentValue]) -> TokenStream {
+        match self.0 {
+            StreamIntrinsic::Offset => quote! { stream_offset },
+            StreamIntrinsic::Remaining => quote! { stream_remaining },
+            StreamIntrinsic::Eof => quote! { stream_eof },
+            StreamIntrinsic::Peek => match arguments.first() {
+                Some(offset) if offset.present => {
+                    let offset = &offset.value;
+                    quote! { stream_peek[(#offset) as usize] }
+                }
+                _ => quote! { stream_peek[0] },
+            },
+        }
+    }
+
+    fn stream_intrinsic(&self) -> Option<StreamIntrinsic> {
+        Some(self.0)
+    }
+}
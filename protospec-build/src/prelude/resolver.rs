@@ -16,6 +16,12 @@ impl<T: ImportResolver + 'static> ImportResolver for PreludeImportResolver<T> {
         Ok(match transform {
             "base64" => Some(Box::new(Base64Transform)),
             "gzip" => Some(Box::new(GzipTransform)),
+            "zlib" => Some(Box::new(CompressionTransform(Compression::Zlib))),
+            "deflate" => Some(Box::new(CompressionTransform(Compression::Deflate))),
+            "zstd" => Some(Box::new(CompressionTransform(Compression::Zstd))),
+            "lz4" => Some(Box::new(CompressionTransform(Compression::Lz4))),
+            "brotli" => Some(Box::new(CompressionTransform(Compression::Brotli))),
+            "snappy" => Some(Box::new(CompressionTransform(Compression::Snappy))),
             x => self.0.resolve_ffi_transform(x)?,
         })
     }
@@ -27,6 +33,38 @@ impl<T: ImportResolver + 'static> ImportResolver for PreludeImportResolver<T> {
             "v32" => Some(Box::new(VarInt::new(ScalarType::I32))),
             "v64" => Some(Box::new(VarInt::new(ScalarType::I64))),
             "v128" => Some(Box::new(VarInt::new(ScalarType::I128))),
+            "zigzag32" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::I32,
+                VarIntEncoding::ZigZag,
+            ))),
+            "zigzag64" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::I64,
+                VarIntEncoding::ZigZag,
+            ))),
+            "sleb32" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::I32,
+                VarIntEncoding::Sleb128,
+            ))),
+            "sleb64" | "sleb128" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::I64,
+                VarIntEncoding::Sleb128,
+            ))),
+            "vlq32" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::U32,
+                VarIntEncoding::Vlq,
+            ))),
+            "vlq64" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::U64,
+                VarIntEncoding::Vlq,
+            ))),
+            "quic_var" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::U64,
+                VarIntEncoding::Quic,
+            ))),
+            "compact_size" => Some(Box::new(VarInt::with_encoding(
+                ScalarType::U64,
+                VarIntEncoding::CompactSize,
+            ))),
             "utf8" => Some(Box::new(Utf8)),
             "utf16" => Some(Box::new(Utf16)),
             x => self.0.resolve_ffi_type(x)?,
@@ -36,10 +74,29 @@ impl<T: ImportResolver + 'static> ImportResolver for PreludeImportResolver<T> {
     fn resolve_ffi_function(&self, name: &str) -> Result<Option<ForeignFunctionObj>> {
         Ok(match name {
             "blen" => Some(Box::new(BLenFunction)),
+            "addr" => Some(Box::new(AddrFunction)),
             "len" => Some(Box::new(LenFunction)),
             "pad" => Some(Box::new(PadFunction)),
             "bits" => Some(Box::new(BitsFunction)),
             "sum" => Some(Box::new(SumFunction)),
+            "crc32" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc32))),
+            "crc32c" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc32c))),
+            "crc16" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc16))),
+            "crc16_modbus" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc16Modbus))),
+            "crc16_ccitt" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc16Ccitt))),
+            "crc16_xmodem" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc16Xmodem))),
+            "crc16_kermit" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Crc16Kermit))),
+            "adler32" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Adler32))),
+            "fletcher16" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Fletcher16))),
+            "fletcher32" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Fletcher32))),
+            "xor8" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Xor8))),
+            "sum8" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Sum8))),
+            "sha1" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Sha1))),
+            "sha256" => Some(Box::new(ChecksumFunction(ChecksumAlgorithm::Sha256))),
+            "offset" => Some(Box::new(StreamPositionFunction(StreamIntrinsic::Offset))),
+            "remaining" => Some(Box::new(StreamPositionFunction(StreamIntrinsic::Remaining))),
+            "eof" => Some(Box::new(StreamPositionFunction(StreamIntrinsic::Eof))),
+            "peek" => Some(Box::new(StreamPositionFunction(StreamIntrinsic::Peek))),
             x => self.0.resolve_ffi_function(x)?,
         })
     }
@@ -54,6 +111,13 @@ impl<T: ImportResolver + 'static> ImportResolver for PreludeImportResolver<T> {
             "blen".to_string(),
             self.resolve_ffi_function("blen")?.unwrap(),
         );
+        out.insert(
+            "addr".to_string(),
+            self.resolve_ffi_function("addr")?.unwrap(),
+        );
+        for name in &["offset", "remaining", "eof", "peek"] {
+            out.insert(name.to_string(), self.resolve_ffi_function(name)?.unwrap());
+        }
         Ok(out)
     }
 }
@@ -17,7 +17,11 @@ impl ForeignType for Utf16 {
     }
 
     fn type_ref(&self) -> TokenStream {
-        quote! { String }
+        quote! { ::std::string::String }
+    }
+
+    fn borrowed_type_ref(&self) -> TokenStream {
+        quote! { str }
     }
 
     fn decoding_gen(
@@ -32,7 +36,7 @@ impl ForeignType for Utf16 {
         quote! {
             let #output_ref = {
                 let t_count = #len as usize;
-                let mut t: Vec<u16> = Vec::with_capacity(t_count);
+                let mut t: ::std::vec::Vec<u16> = ::std::vec::Vec::with_capacity(t_count);
                 unsafe { t.set_len(t_count); }
                 let t_borrow = &mut t[..];
                 let t_borrow2 = unsafe {
@@ -41,7 +45,7 @@ impl ForeignType for Utf16 {
                     slice::from_raw_parts_mut(ptr, len)
                 };
                 #source.read_exact(&mut t_borrow2[..])#async_?;
-                String::from_utf16(&t[..])?
+                ::std::string::String::from_utf16(&t[..])?
             };
         }
     }
@@ -63,16 +67,30 @@ impl ForeignType for Utf16 {
         }
     }
 
+    fn encoded_len_gen(
+        &self,
+        field_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+    ) -> Option<TokenStream> {
+        Some(quote! { (#field_ref.encode_utf16().count() * 2) })
+    }
+
     fn arguments(&self) -> Vec<TypeArgument> {
         vec![TypeArgument {
             name: "length".to_string(),
             type_: Type::Scalar(ScalarType::U64.into()),
             default_value: None,
             can_resolve_auto: true,
+            is_discriminant: false,
+            in_discriminant: false,
         }]
     }
 
     fn copyable(&self) -> bool {
         false
     }
+
+    fn hashable(&self) -> bool {
+        true
+    }
 }
@@ -17,7 +17,11 @@ impl ForeignType for Utf8 {
     }
 
     fn type_ref(&self) -> TokenStream {
-        quote! { String }
+        quote! { ::std::string::String }
+    }
+
+    fn borrowed_type_ref(&self) -> TokenStream {
+        quote! { str }
     }
 
     fn decoding_gen(
@@ -32,7 +36,7 @@ impl ForeignType for Utf8 {
             quote! {
                 let #output_ref = {
                     let t_count = #len as usize;
-                    let mut t: Vec<u8> = Vec::with_capacity(t_count);
+                    let mut t: ::std::vec::Vec<u8> = ::std::vec::Vec::with_capacity(t_count);
                     unsafe { t.set_len(t_count); }
                     let t_borrow = &mut t[..];
                     let t_borrow2 = unsafe {
@@ -41,18 +45,22 @@ impl ForeignType for Utf8 {
                         slice::from_raw_parts_mut(ptr, len)
                     };
                     #source.read_exact(&mut t_borrow2[..])#async_?;
-                    String::from_utf8(t)?
+                    ::std::string::String::from_utf8(t)?
                 };
             }
+        } else if !is_async {
+            quote! {
+                let #output_ref = string::read_cstring(#source)?;
+            }
         } else {
             quote! {
                 let #output_ref = {
-                    let mut t: Vec<u8> = vec![];
+                    let mut t: ::std::vec::Vec<u8> = vec![];
                     #source.read_until(0u8, &mut t)#async_?;
                     if t.len() > 0 && t[t.len() - 1] == 0u8 {
                         t.truncate(t.len() - 1);
                     }
-                    String::from_utf8(t)?
+                    ::std::string::String::from_utf8(t)?
                 };
             }
         }
@@ -72,6 +80,10 @@ impl ForeignType for Utf8 {
                     #target.write_all(#field_ref.as_bytes())#async_?;
                 }
             }
+        } else if !is_async {
+            quote! {
+                string::write_cstring(#target, &#field_ref[..])?;
+            }
         } else {
             quote! {
                 {
@@ -82,16 +94,34 @@ impl ForeignType for Utf8 {
         }
     }
 
+    fn encoded_len_gen(
+        &self,
+        field_ref: TokenStream,
+        arguments: Vec<TokenStream>,
+    ) -> Option<TokenStream> {
+        if arguments.is_empty() {
+            Some(quote! { (#field_ref.len() + 1) })
+        } else {
+            Some(quote! { #field_ref.len() })
+        }
+    }
+
     fn arguments(&self) -> Vec<TypeArgument> {
         vec![TypeArgument {
             name: "length".to_string(),
             type_: Type::Scalar(ScalarType::U64.into()),
             default_value: Some(u64::MAX.into()),
             can_resolve_auto: true,
+            is_discriminant: false,
+            in_discriminant: false,
         }]
     }
 
     fn copyable(&self) -> bool {
         false
     }
+
+    fn hashable(&self) -> bool {
+        true
+    }
 }
@@ -1,14 +1,49 @@
-use crate::{emit_type_ref, PartialScalarType, PartialType};
+use crate::{emit_type_ref, CompileOptions, PartialScalarType, PartialType};
 
 use super::*;
 
+/// The wire format used by a [`VarInt`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VarIntEncoding {
+    /// Unsigned little-endian base-128, signed values are reinterpreted as unsigned
+    Leb128,
+    /// Signed little-endian base-128, sign extended from the final group (DWARF, WASM)
+    Sleb128,
+    /// Zigzag mapped signed values, then unsigned LEB128 (protobuf `sint32`/`sint64`)
+    ZigZag,
+    /// Big-endian base-128 (MIDI, ASN.1 OIDs)
+    Vlq,
+    /// 2-bit length prefixed big-endian integer (QUIC, RFC 9000 section 16)
+    Quic,
+    /// Bitcoin CompactSize, a 1 byte integer or a marker byte followed by a little-endian u16, u32 or u64
+    CompactSize,
+}
+
+impl VarIntEncoding {
+    fn name(&self) -> &'static str {
+        match self {
+            VarIntEncoding::Leb128 => "LEB128",
+            VarIntEncoding::Sleb128 => "SLEB128",
+            VarIntEncoding::ZigZag => "zigzag",
+            VarIntEncoding::Vlq => "VLQ",
+            VarIntEncoding::Quic => "QUIC varint",
+            VarIntEncoding::CompactSize => "CompactSize",
+        }
+    }
+}
+
 pub struct VarInt {
     scalar_type: ScalarType,
     unsigned: Type,
+    encoding: VarIntEncoding,
 }
 
 impl VarInt {
     pub fn new(scalar_type: ScalarType) -> Self {
+        VarInt::with_encoding(scalar_type, VarIntEncoding::Leb128)
+    }
+
+    pub fn with_encoding(scalar_type: ScalarType, encoding: VarIntEncoding) -> Self {
         VarInt {
             scalar_type,
             unsigned: Type::Scalar(
@@ -23,6 +58,94 @@ impl VarInt {
                 }
                 .into(),
             ),
+            encoding,
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        self.scalar_type.size() as u32 * 8
+    }
+
+    /// Whether LEB128 and zigzag integers are coded by the `varint` codec, which is synchronous and limited to 64 bits
+    fn uses_codec(&self, is_async: bool) -> bool {
+        !is_async && self.bits() <= 64
+    }
+
+    /// Emits an expression reading an unsigned LEB128 integer into `self.unsigned` with the `varint` codec
+    fn read_leb128(&self, source: &TokenStream) -> TokenStream {
+        let unsigned = emit_type_ref(&self.unsigned, &CompileOptions::default());
+        if self.bits() == 64 {
+            return quote! { varint::read_leb128(#source)? };
+        }
+        let overflow = format!(
+            "{} varint overflows {}",
+            self.encoding.name(),
+            self.scalar_type
+        );
+        quote! {
+            {
+                let value = varint::read_leb128(#source)?;
+                if value > #unsigned::MAX as u64 {
+                    return Err(decode_error(#overflow).into());
+                }
+                value as #unsigned
+            }
+        }
+    }
+
+    /// Emits an expression reading an unsigned LEB128 integer into `self.unsigned`
+    fn decode_leb128(&self, source: &TokenStream, is_async: bool) -> TokenStream {
+        let unsigned = emit_type_ref(&self.unsigned, &CompileOptions::default());
+        let bits = self.bits();
+        let async_ = map_async(is_async);
+        let overflow = format!(
+            "{} varint overflows {}",
+            self.encoding.name(),
+            self.scalar_type
+        );
+        let overlong = format!("overlong {} varint", self.encoding.name());
+        quote! {
+            {
+                let mut buf = [0u8; 1];
+                let mut output: #unsigned = 0;
+                let mut shift = 0u32;
+                loop {
+                    #source.read_exact(&mut buf[..])#async_?;
+                    let bits = (buf[0] & 127) as #unsigned;
+                    if shift >= #bits || (bits << shift) >> shift != bits {
+                        return Err(decode_error(#overflow).into());
+                    }
+                    output |= bits << shift;
+                    if buf[0] & 128 == 0 {
+                        if buf[0] == 0 && shift > 0 {
+                            return Err(decode_error(#overlong).into());
+                        }
+                        break;
+                    }
+                    shift += 7;
+                }
+                output
+            }
+        }
+    }
+
+    /// Emits a statement writing `value` (of type `self.unsigned`) as an unsigned LEB128 integer
+    fn encode_leb128(
+        &self,
+        target: &TokenStream,
+        value: TokenStream,
+        is_async: bool,
+    ) -> TokenStream {
+        let async_ = map_async(is_async);
+        quote! {
+            {
+                let mut value = #value;
+                while (value & !0b1111111) != 0 {
+                    #target.write_all(&[(value as u8 & 127) | 128])#async_?;
+                    value >>= 7;
+                }
+                #target.write_all(&[value as u8])#async_?;
+            }
         }
     }
 }
@@ -60,7 +183,10 @@ impl ForeignType for VarInt {
     }
 
     fn type_ref(&self) -> TokenStream {
-        emit_type_ref(&Type::Scalar(self.scalar_type.into()))
+        emit_type_ref(
+            &Type::Scalar(self.scalar_type.into()),
+            &CompileOptions::default(),
+        )
     }
 
     fn decoding_gen(
@@ -71,24 +197,136 @@ impl ForeignType for VarInt {
         is_async: bool,
     ) -> TokenStream {
         let inner = self.type_ref();
-        let end = (self.scalar_type.size() as f64 / 7.0).ceil() as usize;
-        let inner_unsigned = emit_type_ref(&self.unsigned);
+        let bits = self.bits();
         let async_ = map_async(is_async);
-        quote! {
-            let #output_ref = {
-                let mut i = 0usize;
-                let mut buf = [0xffu8; 1];
-                let mut output: #inner = 0;
-                while (buf[0] & 128) == 128 {
+        let overflow = format!(
+            "{} varint overflows {}",
+            self.encoding.name(),
+            self.scalar_type
+        );
+        let overlong = format!("overlong {} varint", self.encoding.name());
+        match self.encoding {
+            VarIntEncoding::Leb128 if self.uses_codec(is_async) => {
+                let decoded = self.read_leb128(&source);
+                quote! {
+                    let #output_ref = #decoded as #inner;
+                }
+            }
+            VarIntEncoding::ZigZag if self.uses_codec(is_async) => {
+                let decoded = self.read_leb128(&source);
+                quote! {
+                    let #output_ref = varint::zigzag_decode(#decoded as u64) as #inner;
+                }
+            }
+            VarIntEncoding::Leb128 => {
+                let decoded = self.decode_leb128(&source, is_async);
+                quote! {
+                    let #output_ref = #decoded as #inner;
+                }
+            }
+            VarIntEncoding::ZigZag => {
+                let decoded = self.decode_leb128(&source, is_async);
+                quote! {
+                    let #output_ref = {
+                        let value = #decoded;
+                        ((value >> 1) as #inner) ^ -((value & 1) as #inner)
+                    };
+                }
+            }
+            VarIntEncoding::Sleb128 => quote! {
+                let #output_ref = {
+                    let mut buf = [0u8; 1];
+                    let mut output: #inner = 0;
+                    let mut shift = 0u32;
+                    let mut previous = 0u8;
+                    loop {
+                        #source.read_exact(&mut buf[..])#async_?;
+                        let byte = buf[0];
+                        if shift >= #bits {
+                            return Err(decode_error(#overflow).into());
+                        }
+                        if shift + 7 > #bits {
+                            // bits past the end of the output must all be copies of its sign bit
+                            let high = (byte & 127) >> (#bits - shift - 1);
+                            if byte & 128 != 0 || (high != 0 && high != 127u8 >> (#bits - shift - 1)) {
+                                return Err(decode_error(#overflow).into());
+                            }
+                        }
+                        output |= ((byte & 127) as #inner) << shift;
+                        shift += 7;
+                        if byte & 128 == 0 {
+                            if shift > 7 && ((byte == 0 && previous & 64 == 0) || (byte == 127 && previous & 64 != 0)) {
+                                return Err(decode_error(#overlong).into());
+                            }
+                            if shift < #bits && byte & 64 != 0 {
+                                output |= !0 << shift;
+                            }
+                            break;
+                        }
+                        previous = byte;
+                    }
+                    output
+                };
+            },
+            VarIntEncoding::Vlq => quote! {
+                let #output_ref = {
+                    let mut buf = [0u8; 1];
+                    let mut output: #inner = 0;
                     #source.read_exact(&mut buf[..])#async_?;
-                    output |= ((buf[0] as #inner_unsigned & 127) << (i * 7)) as #inner;
-                    i += 1;
-                    if i > #end {
-                        break;
+                    if buf[0] == 128 {
+                        return Err(decode_error(#overlong).into());
                     }
-                }
-                output
-            };
+                    loop {
+                        if output >> (#bits - 7) != 0 {
+                            return Err(decode_error(#overflow).into());
+                        }
+                        output = (output << 7) | (buf[0] & 127) as #inner;
+                        if buf[0] & 128 == 0 {
+                            break;
+                        }
+                        #source.read_exact(&mut buf[..])#async_?;
+                    }
+                    output
+                };
+            },
+            // RFC 9000 explicitly allows values to be encoded in more bytes than necessary, so overlong encodings are accepted
+            VarIntEncoding::Quic => quote! {
+                let #output_ref = {
+                    let mut buf = [0u8; 8];
+                    #source.read_exact(&mut buf[..1])#async_?;
+                    let length = 1usize << (buf[0] >> 6);
+                    buf[0] &= 63;
+                    #source.read_exact(&mut buf[1..length])#async_?;
+                    let mut output = 0u64;
+                    for byte in &buf[..length] {
+                        output = (output << 8) | *byte as u64;
+                    }
+                    output
+                };
+            },
+            VarIntEncoding::CompactSize => quote! {
+                let #output_ref = {
+                    let mut buf = [0u8; 8];
+                    #source.read_exact(&mut buf[..1])#async_?;
+                    let (length, minimum) = match buf[0] {
+                        0xfd => (2usize, 0xfdu64),
+                        0xfe => (4, 0x1_0000),
+                        0xff => (8, 0x1_0000_0000),
+                        _ => (0, 0),
+                    };
+                    if length == 0 {
+                        buf[0] as u64
+                    } else {
+                        buf[0] = 0;
+                        #source.read_exact(&mut buf[..length])#async_?;
+                        let output = u64::from_le_bytes(buf);
+                        if output < minimum {
+                            return Err(decode_error(#overlong).into());
+                        }
+                        output
+                    }
+                };
+            },
         }
     }
 
@@ -99,20 +337,189 @@ impl ForeignType for VarInt {
         _arguments: Vec<TokenStream>,
         is_async: bool,
     ) -> TokenStream {
-        let inner_unsigned = emit_type_ref(&self.unsigned);
+        let inner_unsigned = emit_type_ref(&self.unsigned, &CompileOptions::default());
+        let bits = self.bits();
         let async_ = map_async(is_async);
-        quote! {
-            {
-                let mut value = #field_ref.clone() as #inner_unsigned;
-                while (value & !0b1111111) != 0 {
-                    #target.write_all(&[(value as u8 & 127) | 128])#async_?;
-                    value >>= 7;
+        match self.encoding {
+            VarIntEncoding::Leb128 if self.uses_codec(is_async) => quote! {
+                varint::write_leb128(#target, #field_ref.clone() as #inner_unsigned as u64)?;
+            },
+            VarIntEncoding::ZigZag if self.uses_codec(is_async) => quote! {
+                varint::write_leb128(#target, varint::zigzag_encode(#field_ref.clone() as i64))?;
+            },
+            VarIntEncoding::Leb128 => self.encode_leb128(
+                &target,
+                quote! { #field_ref.clone() as #inner_unsigned },
+                is_async,
+            ),
+            VarIntEncoding::ZigZag => {
+                let shift = bits - 1;
+                self.encode_leb128(
+                    &target,
+                    quote! {
+                        {
+                            let value = #field_ref.clone();
+                            ((value << 1) ^ (value >> #shift)) as #inner_unsigned
+                        }
+                    },
+                    is_async,
+                )
+            }
+            VarIntEncoding::Sleb128 => quote! {
+                {
+                    let mut value = #field_ref.clone();
+                    loop {
+                        let byte = (value & 127) as u8;
+                        value >>= 7;
+                        if (value == 0 && byte & 64 == 0) || (value == -1 && byte & 64 != 0) {
+                            #target.write_all(&[byte])#async_?;
+                            break;
+                        }
+                        #target.write_all(&[byte | 128])#async_?;
+                    }
+                }
+            },
+            VarIntEncoding::Vlq => {
+                let max_length = bits.div_ceil(7) as usize;
+                quote! {
+                    {
+                        let mut value = #field_ref.clone();
+                        let mut buf = [0u8; #max_length];
+                        let mut start = #max_length;
+                        loop {
+                            start -= 1;
+                            buf[start] = (value & 127) as u8 | if start == #max_length - 1 { 0 } else { 128 };
+                            value >>= 7;
+                            if value == 0 {
+                                break;
+                            }
+                        }
+                        #target.write_all(&buf[start..])#async_?;
+                    }
                 }
-                #target.write_all(&[value as u8])#async_?;
             }
+            VarIntEncoding::Quic => quote! {
+                {
+                    let value = #field_ref.clone();
+                    if value < 1 << 6 {
+                        #target.write_all(&[value as u8])#async_?;
+                    } else if value < 1 << 14 {
+                        #target.write_all(&(value as u16 | 0x4000).to_be_bytes())#async_?;
+                    } else if value < 1 << 30 {
+                        #target.write_all(&(value as u32 | 0x8000_0000).to_be_bytes())#async_?;
+                    } else if value < 1 << 62 {
+                        #target.write_all(&(value | 0xc000_0000_0000_0000).to_be_bytes())#async_?;
+                    } else {
+                        return Err(encode_error("value too large for QUIC varint").into());
+                    }
+                }
+            },
+            VarIntEncoding::CompactSize => quote! {
+                {
+                    let value = #field_ref.clone();
+                    if value < 0xfd {
+                        #target.write_all(&[value as u8])#async_?;
+                    } else if value <= 0xffff {
+                        #target.write_all(&[0xfd])#async_?;
+                        #target.write_all(&(value as u16).to_le_bytes())#async_?;
+                    } else if value <= 0xffff_ffff {
+                        #target.write_all(&[0xfe])#async_?;
+                        #target.write_all(&(value as u32).to_le_bytes())#async_?;
+                    } else {
+                        #target.write_all(&[0xff])#async_?;
+                        #target.write_all(&value.to_le_bytes())#async_?;
+                    }
+                }
+            },
         }
     }
 
+    fn encoded_len_gen(
+        &self,
+        field_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+    ) -> Option<TokenStream> {
+        let inner_unsigned = emit_type_ref(&self.unsigned, &CompileOptions::default());
+        let bits = self.bits();
+        // the number of 7 bit groups of `value`
+        let groups = |value: TokenStream| {
+            quote! {
+                {
+                    let mut value = #value;
+                    let mut len = 1usize;
+                    while (value & !0b1111111) != 0 {
+                        value >>= 7;
+                        len += 1;
+                    }
+                    len
+                }
+            }
+        };
+        Some(match self.encoding {
+            VarIntEncoding::Leb128 if self.uses_codec(false) => quote! {
+                varint::leb128_len(#field_ref.clone() as #inner_unsigned as u64)
+            },
+            VarIntEncoding::ZigZag if self.uses_codec(false) => quote! {
+                varint::leb128_len(varint::zigzag_encode(#field_ref.clone() as i64))
+            },
+            VarIntEncoding::Leb128 | VarIntEncoding::Vlq => {
+                groups(quote! { #field_ref.clone() as #inner_unsigned })
+            }
+            VarIntEncoding::ZigZag => {
+                let shift = bits - 1;
+                groups(quote! {
+                    {
+                        let value = #field_ref.clone();
+                        ((value << 1) ^ (value >> #shift)) as #inner_unsigned
+                    }
+                })
+            }
+            VarIntEncoding::Sleb128 => quote! {
+                {
+                    let mut value = #field_ref.clone();
+                    let mut len = 1usize;
+                    loop {
+                        let byte = (value & 127) as u8;
+                        value >>= 7;
+                        if (value == 0 && byte & 64 == 0) || (value == -1 && byte & 64 != 0) {
+                            break;
+                        }
+                        len += 1;
+                    }
+                    len
+                }
+            },
+            VarIntEncoding::Quic => quote! {
+                {
+                    let value = #field_ref.clone();
+                    if value < 1 << 6 {
+                        1usize
+                    } else if value < 1 << 14 {
+                        2
+                    } else if value < 1 << 30 {
+                        4
+                    } else {
+                        8
+                    }
+                }
+            },
+            VarIntEncoding::CompactSize => quote! {
+                {
+                    let value = #field_ref.clone();
+                    if value < 0xfd {
+                        1usize
+                    } else if value <= 0xffff {
+                        3
+                    } else if value <= 0xffff_ffff {
+                        5
+                    } else {
+                        9
+                    }
+                }
+            },
+        })
+    }
+
     fn arguments(&self) -> Vec<TypeArgument> {
         vec![]
     }
@@ -120,4 +527,19 @@ impl ForeignType for VarInt {
     fn copyable(&self) -> bool {
         true
     }
+
+    fn scalar_type(&self) -> Option<ScalarType> {
+        Some(self.scalar_type)
+    }
+
+    fn encoded_size(&self) -> EncodedSize {
+        EncodedSize::Bounded(match self.encoding {
+            VarIntEncoding::Leb128
+            | VarIntEncoding::Sleb128
+            | VarIntEncoding::ZigZag
+            | VarIntEncoding::Vlq => (self.bits() as u64).div_ceil(7),
+            VarIntEncoding::Quic => 8,
+            VarIntEncoding::CompactSize => 9,
+        })
+    }
 }
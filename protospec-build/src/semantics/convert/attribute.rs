@@ -0,0 +1,98 @@
+use super::*;
+
+impl Scope {
+    /// Converts the attributes of a type declaration, if `toplevel`, or of a container field, checking the arguments of those read by the Rust backend
+    pub(super) fn convert_attributes(
+        attributes: &[ast::Attribute],
+        toplevel: bool,
+    ) -> AsgResult<Vec<Attribute>> {
+        let mut out = vec![];
+        for attribute in attributes {
+            let attribute = Scope::convert_attribute(attribute)?;
+            let invalid = |message: &str| {
+                Err(AsgError::InvalidAttribute(
+                    attribute.name.clone(),
+                    message.to_string(),
+                    attribute.span,
+                ))
+            };
+            match &*attribute.name {
+                "derive" if !toplevel => return invalid("only types can derive traits"),
+                "derive" if attribute.name_arguments().is_none() => {
+                    return invalid("expected the names of the traits to derive")
+                }
+                "rust_name" => match attribute.string_argument() {
+                    Some(name) if syn::parse_str::<syn::Ident>(&name).is_ok() => (),
+                    _ => return invalid("expected a string of a Rust identifier"),
+                },
+                "rust_type" if toplevel => return invalid("only container fields can be mapped"),
+                "rust_type" => match attribute.string_argument() {
+                    Some(type_) if syn::parse_str::<syn::Type>(&type_).is_ok() => (),
+                    _ => return invalid("expected a string of a Rust type"),
+                },
+                "rust"
+                    if attribute
+                        .arguments
+                        .as_deref()
+                        .unwrap_or_default()
+                        .is_empty() =>
+                {
+                    return invalid("expected the Rust attributes to add")
+                }
+                _ => (),
+            }
+            out.push(attribute);
+        }
+        Ok(out)
+    }
+
+    fn convert_attribute(attribute: &ast::Attribute) -> AsgResult<Attribute> {
+        let arguments = match &attribute.arguments {
+            Some(arguments) => Some(
+                arguments
+                    .iter()
+                    .map(|argument| {
+                        Ok(match argument {
+                            ast::AttributeArgument::Attribute(attribute) => {
+                                AttributeArgument::Attribute(Scope::convert_attribute(attribute)?)
+                            }
+                            ast::AttributeArgument::Value(name, value) => AttributeArgument::Value(
+                                name.name.clone(),
+                                Scope::convert_attribute_literal(value)?,
+                            ),
+                            ast::AttributeArgument::Literal(value) => {
+                                AttributeArgument::Literal(Scope::convert_attribute_literal(value)?)
+                            }
+                        })
+                    })
+                    .collect::<AsgResult<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+        Ok(Attribute {
+            name: attribute.name.name.clone(),
+            arguments,
+            span: attribute.span,
+        })
+    }
+
+    fn convert_attribute_literal(value: &ast::Expression) -> AsgResult<AttributeLiteral> {
+        Ok(match value {
+            ast::Expression::Int(value) => {
+                match ConstInt::parse(ScalarType::U64, &value.value, value.span)? {
+                    ConstInt::U64(value) => AttributeLiteral::Int(value),
+                    _ => unreachable!("int parsed as another type"),
+                }
+            }
+            ast::Expression::Str(value) => AttributeLiteral::Str(value.content.clone()),
+            ast::Expression::Bool(value) => AttributeLiteral::Bool(value.value),
+            value => {
+                return Err(AsgError::UnexpectedType(
+                    "expression".to_string(),
+                    "integer, string, or boolean literal".to_string(),
+                    *value.span(),
+                ))
+            }
+        })
+    }
+}
@@ -26,6 +26,17 @@ impl Scope {
             &function.arguments[..],
         )?;
 
+        let argument_types = arguments
+            .iter()
+            .filter_map(|x| x.get_type())
+            .collect::<Vec<_>>();
+        function
+            .inner
+            .check_arguments(&argument_types[..])
+            .map_err(|reason| {
+                AsgError::InvalidFunctionArguments(function.name.clone(), reason, expr.span)
+            })?;
+
         Ok(CallExpression {
             function,
             arguments,
@@ -10,26 +10,32 @@ impl Scope {
         let type_ = target
             .get_type()
             .ok_or_else(|| AsgError::UninferredType(*expr.target.span()))?;
-        let member =
-            match &*type_.resolved() {
-                Type::Bitfield(bitfield) => bitfield
-                    .items
-                    .get(&expr.member.name)
-                    .cloned()
-                    .ok_or_else(|| {
-                        AsgError::BitfieldMemberUndefined(
-                            expr.member.name.clone(),
-                            expr.member.span,
-                        )
-                    })?,
-                t => {
-                    return Err(AsgError::UnexpectedType(
-                        t.to_string(),
-                        "bitfield".to_string(),
-                        *expr.target.span(),
-                    ))?
-                }
-            };
+        let member = match &*type_.resolved() {
+            Type::Bitfield(bitfield) => bitfield
+                .items
+                .get(&expr.member.name)
+                .cloned()
+                .map(Member::Flag)
+                .ok_or_else(|| {
+                    AsgError::BitfieldMemberUndefined(expr.member.name.clone(), expr.member.span)
+                })?,
+            Type::Container(container) => container
+                .flatten_view()
+                .find(|(name, field)| {
+                    name == &expr.member.name && !field.is_pad.get() && !field.is_fixed.get()
+                })
+                .map(|(_, field)| Member::Field(field))
+                .ok_or_else(|| {
+                    AsgError::ContainerMemberUndefined(expr.member.name.clone(), expr.member.span)
+                })?,
+            t => {
+                return Err(AsgError::UnexpectedType(
+                    t.to_string(),
+                    "bitfield or container".to_string(),
+                    *expr.target.span(),
+                ))?
+            }
+        };
 
         Ok(MemberExpression {
             target: Box::new(target),
@@ -24,6 +24,8 @@ mod call;
 
 mod member;
 
+mod size_of;
+
 impl Scope {
     pub fn convert_expr(
         self_: &Arc<RefCell<Scope>>,
@@ -63,6 +65,9 @@ impl Scope {
                 expr,
                 expected_type,
             )?),
+            Call(expr) if expr.function.name == "sizeof" => {
+                Expression::SizeOf(Self::convert_size_of_expression(self_, expr)?)
+            }
             Call(expr) => {
                 Expression::Call(Self::convert_call_expression(self_, expr, expected_type)?)
             }
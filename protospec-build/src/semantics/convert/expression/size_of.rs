@@ -0,0 +1,26 @@
+use super::*;
+
+impl Scope {
+    pub(super) fn convert_size_of_expression(
+        self_: &Arc<RefCell<Scope>>,
+        expr: &ast::CallExpression,
+    ) -> AsgResult<SizeOfExpression> {
+        let name = match &expr.arguments[..] {
+            [ast::Expression::Ref(name)] => name,
+            _ => return Err(AsgError::InvalidSizeOf(expr.span)),
+        };
+        let scope = self_.borrow();
+        let target = scope
+            .program
+            .borrow()
+            .types
+            .get(&name.name)
+            .cloned()
+            .ok_or_else(|| AsgError::UnresolvedType(name.name.clone(), name.span))?;
+
+        Ok(SizeOfExpression {
+            target,
+            span: expr.span,
+        })
+    }
+}
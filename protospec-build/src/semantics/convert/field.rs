@@ -37,6 +37,8 @@ impl Scope {
                         .map(|expr| Scope::convert_expr(&sub_scope, expr, target_type.into()))
                         .transpose()?,
                     can_resolve_auto: false,
+                    is_discriminant: false,
+                    in_discriminant: false,
                 });
             }
         }
@@ -56,7 +58,7 @@ impl Scope {
             TypePurpose::FieldInterior
         };
 
-        let asg_type = Scope::convert_ast_type(&sub_scope, &field.type_.raw_type, purpose)?;
+        let mut asg_type = Scope::convert_ast_type(&sub_scope, &field.type_.raw_type, purpose)?;
 
         let condition = if let Some(condition) = &field.condition {
             Some(Scope::convert_expr(
@@ -68,6 +70,38 @@ impl Scope {
             None
         };
 
+        if let Type::Array(array) = &asg_type {
+            if (array.length.terminator.leave
+                && (into.toplevel || !field.transforms.is_empty() || field.pointer.is_some()))
+                || leaves_terminator(&array.element)
+            {
+                return Err(AsgError::InvalidTerminatorPolicy(
+                    into.name.clone(),
+                    field.span,
+                ));
+            }
+        }
+
+        if let Some(flag) = field.flags.iter().find(|x| x.name == "map") {
+            convert_map_key(&mut asg_type, into, flag)?;
+        }
+
+        let pointer = if let Some(pointer) = &field.pointer {
+            if into.toplevel
+                || !field.transforms.is_empty()
+                || matches!(&asg_type, Type::Container(_))
+            {
+                return Err(AsgError::InvalidPointerField(into.name.clone(), field.span));
+            }
+            Some(Scope::convert_expr(
+                sub_scope,
+                pointer,
+                PartialType::Scalar(PartialScalarType::Defaults(ScalarType::U64)),
+            )?)
+        } else {
+            None
+        };
+
         let mut transforms = vec![];
         for ast::Transform {
             name,
@@ -111,6 +145,17 @@ impl Scope {
         }
         for flag in field.flags.iter() {
             match &*flag.name {
+                "auto"
+                    if !into.toplevel
+                        && field.calculated.is_none()
+                        && field.fixed.is_none()
+                        && flag.arguments.is_empty() =>
+                {
+                    ()
+                }
+                "map" => (),
+                // `+unknown` arms of a `match` are removed on conversion, there is no discriminant to keep elsewhere
+                "unknown" => return Err(AsgError::InvalidUnknownVariant(flag.span)),
                 x => return Err(AsgError::InvalidFlag(x.to_string(), flag.span)),
             }
         }
@@ -136,6 +181,7 @@ impl Scope {
 
         into.type_.replace(asg_type);
         into.condition.replace(condition);
+        into.pointer.replace(pointer);
         into.transforms.replace(transforms);
 
         Ok(())
@@ -160,6 +206,227 @@ impl Scope {
 
         into.calculated.replace(calculated);
 
+        if let Some(fixed) = &field.fixed {
+            if into.toplevel || field.calculated.is_some() {
+                return Err(AsgError::InvalidFixedField(into.name.clone(), field.span));
+            }
+            let fixed = Scope::convert_expr(&sub_scope, &**fixed, field_type.clone().into())?;
+            if !fixed.referenced_fields().is_empty() {
+                return Err(AsgError::InvalidFixedField(into.name.clone(), field.span));
+            }
+            into.calculated.replace(Some(fixed));
+            into.is_fixed.set(true);
+        }
+
+        if let Some(constraint) = &field.constraint {
+            if into.toplevel || matches!(&*field_type, Type::Container(_)) {
+                return Err(AsgError::InvalidConstraint(into.name.clone(), field.span));
+            }
+            let constraint =
+                Scope::convert_expr(&sub_scope, &**constraint, PartialType::Type(Type::Bool))?;
+            into.constraint.replace(Some(constraint));
+        }
+
         Ok(())
     }
+
+    /// Resolves a `+auto` field into a calculated `len` or `blen` of the sibling field that uses it as its length, or `addr` of the one that uses it as its pointer
+    pub fn convert_auto_field(
+        sub_scope: &Arc<RefCell<Scope>>,
+        items: &IndexMap<String, Arc<Field>>,
+        into: &Arc<Field>,
+        span: Span,
+    ) -> AsgResult<()> {
+        let (target, function_name) = match find_auto_target(into, items) {
+            Some(x) => x,
+            None => {
+                return Err(match find_match_reference(into, items) {
+                    Some(type_name) => AsgError::UnderivableTag(into.name.clone(), type_name, span),
+                    None => AsgError::AutoFieldUnused(into.name.clone(), span),
+                })
+            }
+        };
+        let field_type = into.type_.borrow().clone();
+        if function_name == "tag" {
+            return Scope::convert_auto_tag(into, target, span);
+        }
+        if !Type::Scalar(ScalarType::U64.into()).can_cast_to(&field_type) {
+            return Err(AsgError::TypeNotAutoCompatible(
+                field_type.to_string(),
+                span,
+            ));
+        }
+        let function = sub_scope
+            .borrow()
+            .program
+            .borrow()
+            .functions
+            .get(function_name)
+            .ok_or_else(|| AsgError::UnresolvedFunction(function_name.to_string(), span))?
+            .clone();
+
+        into.calculated
+            .replace(Some(Expression::Cast(CastExpression {
+                inner: Box::new(Expression::Call(CallExpression {
+                    function,
+                    arguments: vec![Expression::FieldRef(target)],
+                    span,
+                })),
+                type_: field_type,
+                span,
+            })));
+        Ok(())
+    }
+
+    /// A `+auto` field passed as the discriminant of a `match` container is derived from the variant held by `target`
+    fn convert_auto_tag(into: &Arc<Field>, target: Arc<Field>, span: Span) -> AsgResult<()> {
+        let field_type = into.type_.borrow().clone();
+        let tag_type = match &*target.type_.borrow() {
+            Type::Ref(type_ref) => type_ref
+                .target
+                .arguments
+                .borrow()
+                .iter()
+                .find(|x| x.is_discriminant)
+                .map(|x| x.type_.clone()),
+            _ => None,
+        };
+        let tag_type =
+            tag_type.ok_or_else(|| AsgError::AutoFieldUnused(into.name.clone(), span))?;
+        if !field_type.assignable_from(&tag_type) {
+            return Err(AsgError::TypeNotAutoCompatible(
+                field_type.to_string(),
+                span,
+            ));
+        }
+        into.calculated.replace(Some(Expression::Tag(TagExpression {
+            target,
+            type_: field_type,
+            span,
+        })));
+        Ok(())
+    }
+}
+
+fn leaves_terminator(type_: &Type) -> bool {
+    match type_ {
+        Type::Array(array) => array.length.terminator.leave || leaves_terminator(&array.element),
+        _ => false,
+    }
+}
+
+/// Keys the elements of an array field by one of their fields, checked once all types are converted
+fn convert_map_key(type_: &mut Type, field: &Arc<Field>, flag: &ast::Flag) -> AsgResult<()> {
+    let invalid = || AsgError::InvalidMapField(field.name.clone(), flag.span);
+    let array = match type_ {
+        Type::Array(array)
+            if !field.toplevel
+                && array.length.until.is_none()
+                && !(array.length.expandable && array.length.value.is_some())
+                && matches!(&*array.element, Type::Ref(_)) =>
+        {
+            array
+        }
+        _ => return Err(invalid()),
+    };
+    let (key, duplicates) = match &flag.arguments[..] {
+        [ast::Expression::Ref(key)] => (key, DuplicateKeys::Error),
+        [ast::Expression::Ref(key), ast::Expression::Ref(policy)] => (
+            key,
+            match &*policy.name {
+                "error" => DuplicateKeys::Error,
+                "first" => DuplicateKeys::First,
+                "last" => DuplicateKeys::Last,
+                _ => return Err(invalid()),
+            },
+        ),
+        _ => return Err(invalid()),
+    };
+    array.map = Some(MapKey {
+        key: key.name.clone(),
+        duplicates,
+    });
+    Ok(())
+}
+
+fn refers_to(expr: &Expression, field: &Arc<Field>) -> bool {
+    match expr {
+        Expression::Cast(cast) => refers_to(&cast.inner, field),
+        Expression::FieldRef(target) => Arc::ptr_eq(target, field),
+        _ => false,
+    }
+}
+
+/// Finds the name of a `match` container `field` is passed to in a way its tag cannot be derived from, within arrays or expressions
+fn find_match_reference(
+    field: &Arc<Field>,
+    items: &IndexMap<String, Arc<Field>>,
+) -> Option<String> {
+    fn find(type_: &Type, field: &Arc<Field>) -> Option<String> {
+        match type_ {
+            Type::Array(array) => find(&array.element, field),
+            Type::Ref(type_ref) => {
+                let arguments = type_ref.target.arguments.borrow();
+                type_ref
+                    .arguments
+                    .iter()
+                    .zip(arguments.iter())
+                    .any(|(argument, target_argument)| {
+                        (target_argument.is_discriminant || target_argument.in_discriminant)
+                            && argument
+                                .referenced_fields()
+                                .iter()
+                                .any(|x| Arc::ptr_eq(x, field))
+                    })
+                    .then(|| type_ref.target.name.clone())
+            }
+            _ => None,
+        }
+    }
+    items
+        .values()
+        .find_map(|item| find(&item.type_.borrow(), field))
+}
+
+/// Finds the field whose length or pointer is given by `field`, and the function that measures it
+fn find_auto_target(
+    field: &Arc<Field>,
+    items: &IndexMap<String, Arc<Field>>,
+) -> Option<(Arc<Field>, &'static str)> {
+    for item in items.values() {
+        if matches!(&*item.pointer.borrow(), Some(pointer) if refers_to(pointer, field)) {
+            return Some((item.clone(), "addr"));
+        }
+        match &*item.type_.borrow() {
+            Type::Container(container) => {
+                if matches!(&container.length, Some(length) if refers_to(length, field)) {
+                    return Some((item.clone(), "blen"));
+                }
+                if let Some(found) = find_auto_target(field, &container.items) {
+                    return Some(found);
+                }
+            }
+            Type::Array(array) if !array.length.expandable => {
+                if matches!(&array.length.value, Some(length) if refers_to(length, field)) {
+                    let function = if array.length.bytes { "blen" } else { "len" };
+                    return Some((item.clone(), function));
+                }
+            }
+            Type::Ref(type_ref) => {
+                let arguments = type_ref.target.arguments.borrow();
+                for (argument, target_argument) in type_ref.arguments.iter().zip(arguments.iter()) {
+                    if target_argument.can_resolve_auto && refers_to(argument, field) {
+                        return Some((item.clone(), "len"));
+                    }
+                    if target_argument.is_discriminant
+                        && matches!(argument, Expression::FieldRef(target) if Arc::ptr_eq(target, field))
+                    {
+                        return Some((item.clone(), "tag"));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    None
 }
@@ -4,17 +4,33 @@ impl Scope {
     pub fn convert_length(
         self_: &Arc<RefCell<Scope>>,
         typ: &ast::LengthConstraint,
+        element: &Type,
     ) -> AsgResult<LengthConstraint> {
-        Ok(LengthConstraint {
+        let terminator_type = || {
+            PartialType::Array(Some(Box::new(PartialType::Scalar(
+                PartialScalarType::Some(ScalarType::U8),
+            ))))
+        };
+        let alternatives = typ
+            .terminator
+            .alternatives
+            .iter()
+            .map(|x| Scope::convert_expr(self_, x, terminator_type()))
+            .collect::<AsgResult<Vec<_>>>()?;
+        let length = LengthConstraint {
+            until: typ
+                .until
+                .as_ref()
+                .map(|until| Scope::convert_until(self_, until, element))
+                .transpose()?,
             expandable: typ.expandable,
+            bytes: typ.bytes,
             value: if let Some(inner) = &typ.inner {
                 Some(Scope::convert_expr(
                     self_,
                     inner,
                     if typ.expandable {
-                        PartialType::Array(Some(Box::new(PartialType::Scalar(
-                            PartialScalarType::Some(ScalarType::U8),
-                        ))))
+                        terminator_type()
                     } else {
                         Type::Scalar(ScalarType::U64.into()).into()
                     },
@@ -22,6 +38,65 @@ impl Scope {
             } else {
                 None
             },
+            terminator: TerminatorPolicy {
+                alternatives,
+                leave: typ.terminator.leave,
+                eof: typ.terminator.eof,
+            },
+        };
+        check_terminators(&length, typ.span)?;
+        Ok(length)
+    }
+
+    /// Converts the predicate of `.. until |element| predicate` in a scope declaring the element
+    fn convert_until(
+        self_: &Arc<RefCell<Scope>>,
+        until: &ast::ArrayUntil,
+        element: &Type,
+    ) -> AsgResult<ArrayUntil> {
+        let element = Arc::new(Input {
+            name: until.element.name.clone(),
+            type_: element.clone(),
+        });
+        let sub_scope = Arc::new(RefCell::new(Scope {
+            parent_scope: Some(self_.clone()),
+            program: self_.borrow().program.clone(),
+            declared_fields: IndexMap::new(),
+            declared_inputs: IndexMap::new(),
+        }));
+        sub_scope
+            .borrow_mut()
+            .declared_inputs
+            .insert(element.name.clone(), element.clone());
+        Ok(ArrayUntil {
+            element,
+            predicate: Scope::convert_expr(
+                &sub_scope,
+                &until.predicate,
+                PartialType::Type(Type::Bool),
+            )?,
+            inclusive: until.inclusive,
+        })
+    }
+}
+
+/// Literal terminators must not be prefixes of one another, as matching the longer one would read past the end of the shorter
+fn check_terminators(length: &LengthConstraint, span: Span) -> AsgResult<()> {
+    let literals = length
+        .value
+        .iter()
+        .chain(length.terminator.alternatives.iter())
+        .filter_map(|x| match x {
+            Expression::Str(x) => Some(&x.content),
+            _ => None,
         })
+        .collect::<Vec<_>>();
+    for (i, terminator) in literals.iter().enumerate() {
+        for other in &literals[i + 1..] {
+            if terminator.starts_with(other) || other.starts_with(terminator) {
+                return Err(AsgError::OverlappingTerminators(span));
+            }
+        }
     }
+    Ok(())
 }
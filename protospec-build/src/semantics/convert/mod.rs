@@ -6,6 +6,8 @@ pub use ffi_arguments::*;
 mod field;
 pub use field::*;
 
+mod attribute;
+
 mod type_;
 pub use type_::*;
 
@@ -0,0 +1,62 @@
+use super::*;
+
+impl Program {
+    /// Checks that constraints only reference conditional fields if they are the constrained field itself, and no fields decoded after it
+    pub(super) fn check_constraints(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            if let Type::Container(container) = &*field.type_.borrow() {
+                check_container_constraints(container)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_container_constraints(container: &ContainerType) -> AsgResult<()> {
+    // a variant of an enum container is checked on its own, once it is known to be present
+    if container.is_enum.get() {
+        for child in container.items.values() {
+            if let Type::Container(variant) = &*child.type_.borrow() {
+                check_fields(variant.flatten_view_optional(false))?;
+            }
+        }
+        return Ok(());
+    }
+    check_fields(container.flatten_view_optional(container.set.is_some()))
+}
+
+fn check_fields(fields: Vec<(String, Arc<Field>, bool)>) -> AsgResult<()> {
+    for (index, (name, field, _)) in fields.iter().enumerate() {
+        let constraint = field.constraint.borrow();
+        let constraint = match &*constraint {
+            Some(constraint) => constraint,
+            None => continue,
+        };
+        for referenced in constraint.referenced_fields() {
+            if referenced.name == *name {
+                continue;
+            }
+            let conditional = fields
+                .iter()
+                .any(|(_, x, optional)| *optional && Arc::ptr_eq(x, &referenced));
+            if conditional {
+                return Err(AsgError::ConditionalConstraintReference(
+                    name.clone(),
+                    referenced.name.clone(),
+                    field.span,
+                ));
+            }
+            let later = fields[index + 1..]
+                .iter()
+                .any(|(_, x, _)| Arc::ptr_eq(x, &referenced));
+            if later {
+                return Err(AsgError::LaterConstraintReference(
+                    name.clone(),
+                    referenced.name.clone(),
+                    field.span,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -37,12 +37,20 @@ impl Scope {
                             obj,
                         }))),
                         calculated: RefCell::new(None),
+                        constraint: RefCell::new(None),
                         condition: RefCell::new(None),
+                        pointer: RefCell::new(None),
                         transforms: RefCell::new(vec![]),
                         span: ffi.span,
                         toplevel: true,
                         is_maybe_cyclical: Cell::new(false),
+                        is_boxed: Cell::new(false),
                         is_pad: Cell::new(false),
+                        is_fixed: Cell::new(false),
+                        is_seekable: Cell::new(false),
+                        is_tag: Cell::new(false),
+                        encoded_size: Cell::new(None),
+                        attributes: vec![],
                     });
 
                     program
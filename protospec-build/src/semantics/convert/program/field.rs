@@ -21,11 +21,19 @@ impl Scope {
             span: type_.value.span,
             type_: RefCell::new(Type::Bool), // placeholder
             condition: RefCell::new(None),
+            pointer: RefCell::new(None),
             calculated: RefCell::new(None),
+            constraint: RefCell::new(None),
             transforms: RefCell::new(vec![]),
             toplevel: true,
             is_maybe_cyclical: Cell::new(false),
+            is_boxed: Cell::new(false),
             is_pad: Cell::new(false),
+            is_fixed: Cell::new(false),
+            is_seekable: Cell::new(false),
+            is_tag: Cell::new(false),
+            encoded_size: Cell::new(None),
+            attributes: Scope::convert_attributes(&type_.attributes, true)?,
         });
 
         program
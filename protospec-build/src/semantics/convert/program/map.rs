@@ -0,0 +1,47 @@
+use super::*;
+
+impl Program {
+    /// Checks that the elements of `+map` arrays have their key field, which can key a map
+    pub(super) fn check_map_keys(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            check_map_type(field, &field.type_.borrow())?;
+        }
+        Ok(())
+    }
+}
+
+fn check_map_type(field: &Arc<Field>, type_: &Type) -> AsgResult<()> {
+    match type_ {
+        Type::Container(container) => {
+            for child in container.items.values() {
+                check_map_type(child, &child.type_.borrow())?;
+            }
+        }
+        Type::Array(array) => {
+            if let Some(map) = &array.map {
+                let key = array.map_key_field();
+                let valid = match &key {
+                    Some(key) if key.condition.borrow().is_none() => {
+                        match key.type_.borrow().resolved().as_ref() {
+                            Type::Scalar(_) | Type::Bool => true,
+                            Type::Foreign(foreign) => foreign.obj.hashable(),
+                            Type::Array(key) => matches!(&*key.element, Type::Scalar(_)),
+                            _ => false,
+                        }
+                    }
+                    _ => false,
+                };
+                if !valid {
+                    return Err(AsgError::InvalidMapKey(
+                        map.key.clone(),
+                        field.name.clone(),
+                        field.span,
+                    ));
+                }
+            }
+            check_map_type(field, &array.element)?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
@@ -10,6 +10,20 @@ mod field;
 
 mod const_;
 
+mod pointer;
+
+mod map;
+
+mod constraint;
+
+mod position;
+
+mod recursion;
+
+mod size;
+
+mod naming;
+
 impl Program {
     pub fn from_ast<'a, T: ImportResolver + 'static>(
         ast: &ast::Program,
@@ -119,6 +133,22 @@ impl Program {
                     &field,
                     Some(&type_.arguments[..]),
                 )?);
+                // marked ahead of converting types, as referencing types may be declared first
+                if let ast::RawType::Container(container) = &type_.value.type_.raw_type {
+                    match container.discriminant.as_deref() {
+                        Some(ast::Expression::Ref(name)) => {
+                            for argument in field.arguments.borrow_mut().iter_mut() {
+                                argument.is_discriminant = argument.name == name.name;
+                            }
+                        }
+                        Some(discriminant) => {
+                            for argument in field.arguments.borrow_mut().iter_mut() {
+                                argument.in_discriminant = mentions(discriminant, &argument.name);
+                            }
+                        }
+                        None => (),
+                    }
+                }
             }
 
             // convert rest
@@ -134,6 +164,34 @@ impl Program {
             .into_inner();
 
         program.scan_cycles();
+        program.box_recursive_fields()?;
+        program.scan_seekable();
+        program.check_pointer_fields()?;
+        program.check_map_keys()?;
+        program.check_constraints()?;
+        program.check_stream_positions()?;
+        program.check_sizes()?;
         Ok(program)
     }
 }
+
+/// Whether `expr` reads the variable `name`
+fn mentions(expr: &ast::Expression, name: &str) -> bool {
+    match expr {
+        ast::Expression::Binary(expr) => mentions(&expr.left, name) || mentions(&expr.right, name),
+        ast::Expression::Unary(expr) => mentions(&expr.inner, name),
+        ast::Expression::Cast(expr) => mentions(&expr.inner, name),
+        ast::Expression::ArrayIndex(expr) => {
+            mentions(&expr.array, name) || mentions(&expr.index, name)
+        }
+        ast::Expression::Ternary(expr) => {
+            mentions(&expr.condition, name)
+                || mentions(&expr.if_true, name)
+                || mentions(&expr.if_false, name)
+        }
+        ast::Expression::Call(expr) => expr.arguments.iter().any(|x| mentions(x, name)),
+        ast::Expression::Member(expr) => mentions(&expr.target, name),
+        ast::Expression::Ref(ident) => ident.name == name,
+        _ => false,
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+use crate::compiler::{field_name, global_name, ident, to_snake, to_upper_camel};
+use case::CaseExt;
+
+/// The names given each Rust identifier in a namespace, i.e. the fields of a struct
+#[derive(Default)]
+struct RustNames(IndexMap<String, String>);
+
+impl RustNames {
+    /// Adds `name`, which is `rust_name` in Rust, rejecting it if another name has the same identifier, or if `unique` and it is declared again
+    fn insert(&mut self, rust_name: &str, name: &str, unique: bool, span: Span) -> AsgResult<()> {
+        let rust_name = ident(rust_name).to_string();
+        match self.0.get(&rust_name) {
+            Some(other) if unique || other != name => Err(AsgError::DuplicateRustName(
+                other.clone(),
+                name.to_string(),
+                rust_name,
+                span,
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.0.insert(rust_name, name.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    fn insert_field(&mut self, rust_name: &str, field: &Field) -> AsgResult<()> {
+        self.insert(rust_name, &field.name, true, field.span)
+    }
+}
+
+impl Program {
+    /// Checks that no two declarations are given the same Rust identifier once escaped, and converted if `rust_case`, whether as types, fields of a struct or enum variant, variants of an enum, flags of a bitfield, or variables of a type's functions
+    pub fn check_rust_names(&self, rust_case: bool) -> AsgResult<()> {
+        let mut types = RustNames::default();
+        for field in self.types.values() {
+            let type_ = field.type_.borrow();
+            if matches!(&*type_, Type::Foreign(_)) {
+                continue;
+            }
+            types.insert_field(&global_name(field, rust_case), field)?;
+            check_variables(field, rust_case)?;
+            match &*type_ {
+                Type::Container(container) => check_container(container, rust_case)?,
+                Type::Enum(enum_) => {
+                    let mut variants = RustNames::default();
+                    for name in enum_.items.keys() {
+                        let rust_name = match rust_case {
+                            true => to_upper_camel(name),
+                            false => name.clone(),
+                        };
+                        variants.insert(&rust_name, name, true, field.span)?;
+                    }
+                }
+                Type::Bitfield(bitfield) => {
+                    let mut flags = RustNames::default();
+                    for name in bitfield.items.keys() {
+                        flags.insert(&name.to_snake(), name, true, field.span)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks the variables of the functions of a type, which are its arguments and the fields it decodes, named as written or in `snake_case`
+fn check_variables(field: &Field, rust_case: bool) -> AsgResult<()> {
+    let variable = |name: &str| match rust_case {
+        true => to_snake(name),
+        false => name.to_string(),
+    };
+    let mut variables = RustNames::default();
+    for argument in field.arguments.borrow().iter() {
+        variables.insert(&variable(&argument.name), &argument.name, false, field.span)?;
+    }
+    if let Type::Container(container) = &*field.type_.borrow() {
+        check_container_variables(container, &variable, &mut variables)?;
+    }
+    Ok(())
+}
+
+fn check_container_variables(
+    container: &ContainerType,
+    variable: &dyn Fn(&str) -> String,
+    variables: &mut RustNames,
+) -> AsgResult<()> {
+    for child in container.items.values() {
+        variables.insert(&variable(&child.name), &child.name, false, child.span)?;
+        if let Type::Container(container) = &*child.type_.borrow() {
+            check_container_variables(container, variable, variables)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_container(container: &ContainerType, rust_case: bool) -> AsgResult<()> {
+    if !container.is_enum.get() {
+        return check_struct(container, rust_case);
+    }
+    let mut variants = RustNames::default();
+    for child in container.items.values() {
+        variants.insert_field(&global_name(child, rust_case), child)?;
+        if let Type::Container(container) = &*child.type_.borrow() {
+            check_struct(container, rust_case)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_struct(container: &ContainerType, rust_case: bool) -> AsgResult<()> {
+    let mut fields = RustNames::default();
+    // set containers hold their unknown records, and possibly their order, next to their members
+    if container.set.is_some() {
+        for name in ["unknown", "order"] {
+            fields.0.insert(name.to_string(), name.to_string());
+        }
+    }
+    for (_, child) in container.flatten_view() {
+        if child.is_pad.get() || child.is_fixed.get() {
+            continue;
+        }
+        fields.insert_field(&field_name(&child, rust_case), &child)?;
+    }
+    Ok(())
+}
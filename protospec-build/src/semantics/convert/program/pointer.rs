@@ -0,0 +1,141 @@
+use super::*;
+
+impl Program {
+    /// Checks that pointer fields, and types using them, are only read directly from a seekable stream
+    pub(super) fn check_pointer_fields(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            check_seekable_field(field, false)?;
+            check_addr_field(field, false)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_seekable_field(field: &Arc<Field>, in_stream: bool) -> AsgResult<()> {
+    let in_stream = in_stream || !field.transforms.borrow().is_empty();
+    check_seekable_type(field, &field.type_.borrow(), in_stream)
+}
+
+fn check_seekable_type(field: &Arc<Field>, type_: &Type, in_stream: bool) -> AsgResult<()> {
+    match type_ {
+        Type::Container(container) => {
+            let in_stream = in_stream || container.length.is_some();
+            for child in container.items.values() {
+                if child.pointer.borrow().is_some() && (in_stream || container.is_enum.get()) {
+                    return Err(AsgError::InvalidPointerField(
+                        child.name.clone(),
+                        child.span,
+                    ));
+                }
+                check_seekable_field(child, in_stream)?;
+            }
+        }
+        // unbounded arrays are buffered ahead of decoding their elements, byte bounded ones are read through a length constrained stream
+        Type::Array(array) => check_seekable_type(
+            field,
+            &array.element,
+            in_stream
+                || array.length.bytes
+                || (array.length.value.is_none() && array.length.until.is_none()),
+        )?,
+        Type::Ref(type_ref) if in_stream && type_ref.target.is_seekable.get() => {
+            return Err(AsgError::SeekableInStream(
+                field.name.clone(),
+                type_ref.target.name.clone(),
+                field.span,
+            ));
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// The expressions of a field and its type, other than its calculated value and those of interior fields
+fn field_expressions(field: &Field) -> Vec<Expression> {
+    let mut out = vec![];
+    out.extend(field.condition.borrow().clone());
+    out.extend(field.constraint.borrow().clone());
+    out.extend(field.pointer.borrow().clone());
+    for transform in field.transforms.borrow().iter() {
+        out.extend(transform.arguments.iter().cloned());
+        out.extend(transform.condition.clone());
+    }
+    match &*field.type_.borrow() {
+        Type::Container(container) => out.extend(container.length.clone()),
+        Type::Array(array) => {
+            out.extend(array.length.value.clone());
+            out.extend(array.length.until.as_ref().map(|x| x.predicate.clone()));
+        }
+        Type::Ref(type_ref) => out.extend(type_ref.arguments.iter().cloned()),
+        _ => (),
+    }
+    out
+}
+
+/// Checks that `addr` is only the calculated value of an integer field written directly to the output, taking a pointer field
+fn check_addr_field(field: &Arc<Field>, in_buffer: bool) -> AsgResult<()> {
+    let invalid =
+        |reason: &str| AsgError::InvalidAddr(field.name.clone(), reason.to_string(), field.span);
+    let in_buffer = in_buffer || !field.transforms.borrow().is_empty();
+    if field_expressions(field)
+        .iter()
+        .any(|x| x.calls_function("addr"))
+    {
+        return Err(invalid("it can only be the calculated value of a field"));
+    }
+    if let Some(calculated) = field
+        .calculated
+        .borrow()
+        .as_ref()
+        .filter(|x| x.calls_function("addr"))
+    {
+        let call = match calculated {
+            Expression::Cast(cast) => &*cast.inner,
+            calculated => calculated,
+        };
+        let target = match call {
+            Expression::Call(call) if call.function.name == "addr" => {
+                match call.arguments.first() {
+                    Some(Expression::FieldRef(target)) => target,
+                    _ => return Err(invalid("it must take a field")),
+                }
+            }
+            _ => return Err(invalid("it must be the whole calculated value")),
+        };
+        if target.pointer.borrow().is_none() {
+            return Err(invalid(&format!(
+                "'{}' is not a pointer field",
+                target.name
+            )));
+        }
+        if !matches!(&*field.type_.borrow(), Type::Scalar(_)) {
+            return Err(invalid("it can only be held by an integer field"));
+        }
+        if in_buffer {
+            return Err(invalid("it cannot be written within a length constrained or transformed container, nor ahead of a `+auto` field's target"));
+        }
+    }
+    if let Type::Container(container) = &*field.type_.borrow() {
+        let in_buffer = in_buffer || container.length.is_some();
+        // items after a `blen` calculated field are buffered until its target is encoded
+        let mut seen = vec![];
+        let mut pending = vec![];
+        for (name, child) in container.items.iter() {
+            check_addr_field(child, in_buffer || !pending.is_empty())?;
+            seen.push(name.clone());
+            if let Type::Container(child_container) = &*child.type_.borrow() {
+                seen.extend(child_container.flatten_view().map(|(name, _)| name));
+            }
+            pending.retain(|x| !seen.contains(x));
+            if let Some(calculated) = &*child.calculated.borrow() {
+                pending.extend(
+                    calculated
+                        .blen_calls()
+                        .into_iter()
+                        .filter(|x| !seen.contains(x)),
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use super::*;
+use crate::StreamIntrinsic;
+
+impl Program {
+    /// Checks that stream position intrinsics are only used where the stream position is known to both coders
+    pub(super) fn check_stream_positions(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            check_field(field, None, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// The position tracking stream expressions of a field are evaluated in
+#[derive(Clone, Copy)]
+struct PositionStream {
+    /// Whether the stream is of a length constrained container
+    constrained: bool,
+    /// Whether a `+auto` field of unknown size is encoded after its target, leaving the position unknown until then
+    pending_auto: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Usage {
+    Condition,
+    VariantCondition,
+    ArrayLength,
+    Pointer,
+    Other,
+}
+
+fn intrinsic_name(intrinsic: StreamIntrinsic) -> &'static str {
+    match intrinsic {
+        StreamIntrinsic::Offset => "offset",
+        StreamIntrinsic::Remaining => "remaining",
+        StreamIntrinsic::Eof => "eof",
+        StreamIntrinsic::Peek => "peek",
+    }
+}
+
+fn check_expr(
+    expr: &Expression,
+    stream: Option<PositionStream>,
+    usage: Usage,
+    span: Span,
+) -> AsgResult<()> {
+    for intrinsic in expr.stream_intrinsics() {
+        let reason = match (stream, intrinsic) {
+            _ if usage == Usage::Pointer => "pointers are offsets from the start of the top level type",
+            (None, _) => "the stream position is only known within a container",
+            (Some(_), StreamIntrinsic::Peek)
+                if usage != Usage::Condition && usage != Usage::VariantCondition =>
+            {
+                "it can only be used in conditions of fields"
+            }
+            (Some(_), StreamIntrinsic::Peek) if expr.peek_len().is_none() => {
+                "its offset must be an integer literal"
+            }
+            (Some(_), StreamIntrinsic::Eof)
+                if usage != Usage::Condition && usage != Usage::ArrayLength =>
+            {
+                "it can only be used in array lengths and conditions of fields other than enum container variants"
+            }
+            (Some(stream), StreamIntrinsic::Remaining) if !stream.constrained => {
+                "it can only be used in a length constrained container"
+            }
+            (Some(stream), _) if stream.pending_auto => {
+                "the stream position is unknown between a `+auto` field of unknown size and the field it is calculated from"
+            }
+            _ => continue,
+        };
+        return Err(AsgError::InvalidStreamPosition(
+            intrinsic_name(intrinsic).to_string(),
+            reason.to_string(),
+            span,
+        ));
+    }
+    Ok(())
+}
+
+fn check_field(field: &Arc<Field>, stream: Option<PositionStream>, variant: bool) -> AsgResult<()> {
+    let span = field.span;
+    if let Some(condition) = &*field.condition.borrow() {
+        let usage = if variant {
+            Usage::VariantCondition
+        } else {
+            Usage::Condition
+        };
+        check_expr(condition, stream, usage, span)?;
+    }
+    for expr in [&field.calculated, &field.constraint] {
+        if let Some(expr) = &*expr.borrow() {
+            check_expr(expr, stream, Usage::Other, span)?;
+        }
+    }
+    if let Some(pointer) = &*field.pointer.borrow() {
+        check_expr(pointer, stream, Usage::Pointer, span)?;
+    }
+    for transform in field.transforms.borrow().iter() {
+        for argument in transform.arguments.iter().chain(transform.condition.iter()) {
+            check_expr(argument, stream, Usage::Other, span)?;
+        }
+    }
+    check_type(field, &field.type_.borrow(), stream)
+}
+
+fn check_type(field: &Arc<Field>, type_: &Type, stream: Option<PositionStream>) -> AsgResult<()> {
+    let span = field.span;
+    match type_ {
+        Type::Container(container) => {
+            if let Some(length) = &container.length {
+                check_expr(length, stream, Usage::Other, span)?;
+            }
+            // top level and length constrained containers count their own position
+            let stream = if field.toplevel || container.length.is_some() {
+                PositionStream {
+                    constrained: container.length.is_some(),
+                    pending_auto: false,
+                }
+            } else {
+                match stream {
+                    Some(stream) => stream,
+                    None => return Ok(()),
+                }
+            };
+            let mut seen = vec![];
+            let mut pending = vec![];
+            for (name, child) in container.items.iter() {
+                let child_stream = PositionStream {
+                    pending_auto: stream.pending_auto || !pending.is_empty(),
+                    ..stream
+                };
+                check_field(child, Some(child_stream), container.is_enum.get())?;
+                seen.push(name.clone());
+                if let Type::Container(child_container) = &*child.type_.borrow() {
+                    seen.extend(child_container.flatten_view().map(|(name, _)| name));
+                }
+                pending.retain(|x| !seen.contains(x));
+                let unsized_auto = !matches!(&*child.type_.borrow(), Type::Scalar(_));
+                if let Some(calculated) =
+                    child.calculated.borrow().as_ref().filter(|_| unsized_auto)
+                {
+                    pending.extend(
+                        calculated
+                            .blen_calls()
+                            .into_iter()
+                            .filter(|x| !seen.contains(x)),
+                    );
+                }
+            }
+        }
+        Type::Array(array) => {
+            if let Some(length) = &array.length.value {
+                check_expr(length, stream, Usage::ArrayLength, span)?;
+            }
+            if let Some(until) = &array.length.until {
+                check_expr(&until.predicate, stream, Usage::Other, span)?;
+            }
+        }
+        Type::Ref(type_ref) => {
+            for argument in type_ref.arguments.iter() {
+                check_expr(argument, stream, Usage::Other, span)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use super::*;
+use std::collections::HashSet;
+
+impl Program {
+    /// Rejects types holding themselves through only required fields, and boxes the fields which end the recursion of the others
+    pub(super) fn box_recursive_fields(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            let mut required = vec![];
+            held_types(&field.type_.borrow(), true, &mut required);
+            if holds(&field.name, required, true) {
+                return Err(AsgError::InfiniteSizeType(field.name.clone(), field.span));
+            }
+        }
+        for field in self.types.values() {
+            if let Type::Container(container) = &*field.type_.borrow() {
+                box_fields(&field.name, container, true);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Boxes the fields of `container` which are not always present and still hold the type named `owner` through fields not yet boxed.
+/// Every cycle has such a field, so required fields are never boxed, and a cycle is boxed once
+fn box_fields(owner: &str, container: &ContainerType, required: bool) {
+    let required = required && !container.is_enum.get() && container.set.is_none();
+    for child in container.items.values() {
+        let child_required = required && child.condition.borrow().is_none();
+        match &*child.type_.borrow() {
+            Type::Container(sub_container) => box_fields(owner, sub_container, child_required),
+            type_ => {
+                let always_held = match type_ {
+                    Type::Array(array) => {
+                        matches!(array.fixed_length(), Some(length) if length > 0)
+                    }
+                    _ => true,
+                };
+                if child_required && always_held {
+                    continue;
+                }
+                let mut held = vec![];
+                held_types(type_, false, &mut held);
+                if holds(owner, held, false) {
+                    child.is_boxed.set(true);
+                }
+            }
+        }
+    }
+}
+
+/// Collects the types stored inline in a type, only through fields always present if `required`
+fn held_types(type_: &Type, required: bool, out: &mut Vec<Arc<Field>>) {
+    match type_ {
+        Type::Ref(type_ref) => out.push(type_ref.target.clone()),
+        // arrays of constant length hold their elements inline, and always hold some unless empty. Other arrays hold their elements in a `Vec`, and may be empty
+        Type::Array(array) if matches!(array.fixed_length(), Some(length) if length > 0 || !required) => {
+            held_types(&array.element, required, out)
+        }
+        // only one variant of an enum is present, and members of a set are optional
+        Type::Container(container)
+            if !(required && (container.is_enum.get() || container.set.is_some())) =>
+        {
+            for child in container.items.values() {
+                // boxed fields hold their type behind a pointer
+                if child.is_pad.get() || child.is_fixed.get() || child.is_boxed.get() {
+                    continue;
+                }
+                if required && child.condition.borrow().is_some() {
+                    continue;
+                }
+                held_types(&child.type_.borrow(), required, out);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Whether any of `types` holds the type named `owner`, directly or through the types they hold
+fn holds(owner: &str, mut types: Vec<Arc<Field>>, required: bool) -> bool {
+    let mut visited = HashSet::new();
+    while let Some(type_) = types.pop() {
+        if type_.name == owner {
+            return true;
+        }
+        if visited.insert(type_.name.clone()) {
+            held_types(&type_.type_.borrow(), required, &mut types);
+        }
+    }
+    false
+}
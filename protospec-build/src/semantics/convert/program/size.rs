@@ -0,0 +1,106 @@
+use super::*;
+
+impl Program {
+    /// Computes the encoded size of each type, and checks that `sizeof` is only taken of types with a fixed size
+    pub(super) fn check_sizes(&self) -> AsgResult<()> {
+        for field in self.types.values() {
+            field.encoded_size();
+        }
+        for const_ in self.consts.values() {
+            check_expression(&const_.value)?;
+        }
+        for field in self.types.values() {
+            check_field(field)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_field(field: &Field) -> AsgResult<()> {
+    for expression in [
+        &field.condition,
+        &field.calculated,
+        &field.constraint,
+        &field.pointer,
+    ] {
+        if let Some(expression) = &*expression.borrow() {
+            check_expression(expression)?;
+        }
+    }
+    for transform in field.transforms.borrow().iter() {
+        transform
+            .arguments
+            .iter()
+            .chain(transform.condition.as_ref())
+            .try_for_each(check_expression)?;
+    }
+    for argument in field.arguments.borrow().iter() {
+        if let Some(default_value) = &argument.default_value {
+            check_expression(default_value)?;
+        }
+    }
+    check_type(&field.type_.borrow())
+}
+
+fn check_type(type_: &Type) -> AsgResult<()> {
+    match type_ {
+        Type::Container(container) => {
+            container
+                .length
+                .iter()
+                .chain(container.discriminant.as_ref())
+                .chain(container.tags.values())
+                .try_for_each(check_expression)?;
+            for child in container.items.values() {
+                check_field(child)?;
+            }
+        }
+        Type::Array(array) => {
+            array
+                .length
+                .value
+                .iter()
+                .chain(array.length.terminator.alternatives.iter())
+                .chain(array.length.until.as_ref().map(|until| &until.predicate))
+                .try_for_each(check_expression)?;
+            check_type(&array.element)?;
+        }
+        Type::Ref(type_ref) => type_ref.arguments.iter().try_for_each(check_expression)?,
+        _ => (),
+    }
+    Ok(())
+}
+
+fn check_expression(expression: &Expression) -> AsgResult<()> {
+    match expression {
+        Expression::Binary(expr) => {
+            check_expression(&expr.left)?;
+            check_expression(&expr.right)
+        }
+        Expression::Unary(expr) => check_expression(&expr.inner),
+        Expression::Cast(expr) => check_expression(&expr.inner),
+        Expression::ArrayIndex(expr) => {
+            check_expression(&expr.array)?;
+            check_expression(&expr.index)
+        }
+        Expression::Ternary(expr) => {
+            check_expression(&expr.condition)?;
+            check_expression(&expr.if_true)?;
+            check_expression(&expr.if_false)
+        }
+        Expression::Call(expr) => expr.arguments.iter().try_for_each(check_expression),
+        Expression::Member(expr) => check_expression(&expr.target),
+        Expression::SizeOf(expr) => match expr.target.encoded_size() {
+            EncodedSize::Fixed(_) => Ok(()),
+            _ => Err(AsgError::SizeOfUnsized(expr.target.name.clone(), expr.span)),
+        },
+        Expression::EnumAccess(_)
+        | Expression::Int(_)
+        | Expression::ConstRef(_)
+        | Expression::InputRef(_)
+        | Expression::FieldRef(_)
+        | Expression::Str(_)
+        | Expression::Bool(_)
+        | Expression::Tag(_) => Ok(()),
+    }
+}
@@ -5,16 +5,17 @@ impl Scope {
         self_: &Arc<RefCell<Scope>>,
         type_: &ast::Array,
     ) -> AsgResult<Type> {
-        let length = Scope::convert_length(self_, &type_.length)?;
         let element = Scope::convert_ast_type(
             self_,
             &type_.interior_type.raw_type,
             TypePurpose::ArrayInterior,
         )?;
+        let length = Scope::convert_length(self_, &type_.length, &element)?;
 
         Ok(Type::Array(Box::new(ArrayType {
             element: Box::new(element),
             length,
+            map: None,
         })))
     }
 }
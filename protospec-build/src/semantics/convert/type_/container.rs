@@ -1,4 +1,4 @@
-use crate::ContainerItem;
+use crate::{BinaryOp, ContainerItem};
 
 use super::*;
 
@@ -20,15 +20,28 @@ impl Scope {
             })
             .transpose()?;
 
-        let mut is_enum = false;
+        let discriminant = type_
+            .discriminant
+            .as_ref()
+            .map(|x| Scope::convert_expr(self_, x, PartialType::Any))
+            .transpose()?;
+
+        let mut is_enum = discriminant.is_some();
+        let mut preserve_order = false;
         for flag in &type_.flags {
             match &*flag.name {
-                "tagged_enum" => is_enum = true,
+                "tagged_enum" if type_.set.is_none() => is_enum = true,
+                "preserve_order" if type_.set.is_some() => preserve_order = true,
                 x => return Err(AsgError::InvalidFlag(x.to_string(), flag.span)),
             }
         }
+        let set = type_
+            .set
+            .as_ref()
+            .map(|x| Scope::convert_set_layout(x, preserve_order))
+            .transpose()?;
 
-        if is_enum && !matches!(purpose, TypePurpose::TypeDefinition(_)) {
+        if (is_enum || set.is_some()) && !matches!(purpose, TypePurpose::TypeDefinition(_)) {
             return Err(AsgError::MustBeToplevel(type_.span));
         }
 
@@ -44,10 +57,52 @@ impl Scope {
 
         let mut field_scopes = vec![];
 
+        let mut tags = IndexMap::new();
+        let mut matched = vec![];
+        let mut set_tags = vec![];
+
         let mut pad_count = 0;
         for item in type_.items.iter() {
             match item {
-                ContainerItem::Field(name, ast_field) => {
+                ContainerItem::Field(..) | ContainerItem::Arm(..) => {
+                    let mut unknown = false;
+                    let (name, ast_field) = match item {
+                        ContainerItem::Field(name, ast_field) => (name, ast_field.clone()),
+                        ContainerItem::Arm(patterns, name, ast_field) if set.is_some() => {
+                            let tag = match patterns.first() {
+                                Some(ast::Pattern::Value(tag)) => tag,
+                                _ => unreachable!("set member without a tag"),
+                            };
+                            let tag = Scope::convert_set_member(
+                                self_,
+                                set.as_ref().unwrap(),
+                                tag,
+                                name,
+                                ast_field,
+                                &mut set_tags,
+                            )?;
+                            tags.insert(name.name.clone(), tag);
+                            (name, ast_field.clone())
+                        }
+                        ContainerItem::Arm(patterns, name, ast_field) => {
+                            let arm = Scope::convert_match_arm(
+                                self_,
+                                (
+                                    type_.discriminant.as_deref().unwrap(),
+                                    discriminant.as_ref().unwrap(),
+                                ),
+                                patterns,
+                                ast_field,
+                                &mut matched,
+                            )?;
+                            if let Some(tag) = arm.tag {
+                                tags.insert(name.name.clone(), tag);
+                            }
+                            unknown = arm.unknown;
+                            (name, arm.field)
+                        }
+                        _ => unreachable!(),
+                    };
                     if let Some(defined) = items.get(&name.name) {
                         return Err(AsgError::ContainerFieldRedefinition(
                             name.name.clone(),
@@ -59,19 +114,48 @@ impl Scope {
                         name: name.name.clone(),
                         type_: RefCell::new(Type::Bool),
                         calculated: RefCell::new(None),
+                        constraint: RefCell::new(None),
                         condition: RefCell::new(None),
+                        pointer: RefCell::new(None),
                         transforms: RefCell::new(vec![]),
                         span: ast_field.span,
                         toplevel: false,
                         arguments: RefCell::new(vec![]),
                         is_maybe_cyclical: Cell::new(false),
+                        is_boxed: Cell::new(false),
                         is_pad: Cell::new(false),
+                        is_fixed: Cell::new(false),
+                        is_seekable: Cell::new(false),
+                        is_tag: Cell::new(false),
+                        encoded_size: Cell::new(None),
+                        attributes: Scope::convert_attributes(&ast_field.attributes, false)?,
                     });
+                    if let Some(attribute) = field_out
+                        .attributes_named("rust_type")
+                        .next()
+                        .filter(|_| is_enum)
+                    {
+                        return Err(AsgError::InvalidAttribute(
+                            attribute.name.clone(),
+                            "only fields of structs can be mapped".to_string(),
+                            attribute.span,
+                        ));
+                    }
 
                     {
                         let sub_scope =
                             Scope::convert_ast_field_arguments(&sub_scope, &field_out, None)?;
-                        Scope::convert_ast_field_mid(&sub_scope, ast_field, &field_out)?;
+                        Scope::convert_ast_field_mid(&sub_scope, &ast_field, &field_out)?;
+                        if unknown {
+                            Scope::capture_discriminant(&field_out, discriminant.as_ref().unwrap());
+                        }
+                        if set.is_some() {
+                            // a member's constraint refers to itself, though it is hidden from other members
+                            sub_scope
+                                .borrow_mut()
+                                .declared_fields
+                                .insert(name.name.clone(), field_out.clone());
+                        }
                         field_scopes.push((field_out.clone(), sub_scope, ast_field.clone()));
                     }
 
@@ -84,16 +168,21 @@ impl Scope {
                         had_unconditional_field = true;
                     }
 
-                    sub_scope
-                        .borrow_mut()
-                        .declared_fields
-                        .insert(name.name.clone(), field_out.clone());
+                    if set.is_none() {
+                        sub_scope
+                            .borrow_mut()
+                            .declared_fields
+                            .insert(name.name.clone(), field_out.clone());
+                    }
                     items.insert(name.name.clone(), field_out);
                 }
-                ContainerItem::Pad(expr) => {
+                ContainerItem::Pad(expr) | ContainerItem::Align(expr) => {
                     if is_enum {
                         return Err(AsgError::EnumContainerPad(*expr.span()));
                     }
+                    if set.is_some() {
+                        return Err(AsgError::SetContainerPad(*expr.span()));
+                    }
                     let name = format!("_pad{}", pad_count);
                     pad_count += 1;
 
@@ -102,6 +191,11 @@ impl Scope {
                         expr,
                         PartialType::Scalar(PartialScalarType::Some(ScalarType::U64)),
                     )?;
+                    let len = if matches!(item, ContainerItem::Align(_)) {
+                        Scope::alignment_padding(&sub_scope, len, *expr.span())?
+                    } else {
+                        len
+                    };
 
                     let field_out = Arc::new(Field {
                         name: name.clone(),
@@ -110,16 +204,28 @@ impl Scope {
                             length: LengthConstraint {
                                 expandable: false,
                                 value: Some(len),
+                                until: None,
+                                bytes: false,
+                                terminator: TerminatorPolicy::default(),
                             },
+                            map: None,
                         }))),
                         calculated: RefCell::new(None),
+                        constraint: RefCell::new(None),
                         condition: RefCell::new(None),
+                        pointer: RefCell::new(None),
                         transforms: RefCell::new(vec![]),
                         span: *expr.span(),
                         toplevel: false,
                         arguments: RefCell::new(vec![]),
                         is_maybe_cyclical: Cell::new(false),
+                        is_boxed: Cell::new(false),
                         is_pad: Cell::new(true),
+                        is_fixed: Cell::new(false),
+                        is_seekable: Cell::new(false),
+                        is_tag: Cell::new(false),
+                        encoded_size: Cell::new(None),
+                        attributes: vec![],
                     });
 
                     items.insert(name.clone(), field_out);
@@ -128,12 +234,56 @@ impl Scope {
         }
         for (out_field, sub_scope, ast_field) in field_scopes {
             Scope::convert_ast_field_end(&sub_scope, &ast_field, &out_field)?;
+            if let Some(flag) = ast_field.flags.iter().find(|x| x.name == "auto") {
+                Scope::convert_auto_field(&sub_scope, &items, &out_field, flag.span)?;
+            }
         }
 
         Ok(Type::Container(Box::new(ContainerType {
             length,
             items,
             is_enum: Cell::new(is_enum),
+            discriminant,
+            tags,
+            set,
         })))
     }
+
+    /// Desugars `.align(n)` into the padding length `(n - offset() % n) % n`
+    fn alignment_padding(
+        self_: &Arc<RefCell<Scope>>,
+        alignment: Expression,
+        span: Span,
+    ) -> AsgResult<Expression> {
+        let offset = self_
+            .borrow()
+            .program
+            .borrow()
+            .functions
+            .get("offset")
+            .ok_or_else(|| AsgError::UnresolvedFunction("offset".to_string(), span))?
+            .clone();
+        let binary = |op, left, right| {
+            Expression::Binary(BinaryExpression {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+                span,
+            })
+        };
+        let offset = Expression::Call(CallExpression {
+            function: offset,
+            arguments: vec![],
+            span,
+        });
+        Ok(binary(
+            BinaryOp::Mod,
+            binary(
+                BinaryOp::Sub,
+                alignment.clone(),
+                binary(BinaryOp::Mod, offset, alignment.clone()),
+            ),
+            alignment,
+        ))
+    }
 }
@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+
+use crate::{eval_const_expression, BinaryOp, ConstValue, ContainerItem, Pattern};
+
+use super::*;
+
+/// Discriminant values matched by an unguarded arm, as `(low, high, inclusive)`
+pub(super) type MatchedRange = (ConstValue, ConstValue, bool);
+
+pub(super) struct MatchArm {
+    pub field: ast::Field,
+    /// The first discriminant value selecting the arm
+    pub tag: Option<Expression>,
+    /// Whether the variant holds the discriminant, as an `+unknown` arm
+    pub unknown: bool,
+}
+
+impl Scope {
+    /// Desugars a `match` arm into its field conditioned on the patterns
+    pub(super) fn convert_match_arm(
+        self_: &Arc<RefCell<Scope>>,
+        discriminant: (&ast::Expression, &Expression),
+        patterns: &[Pattern],
+        field: &ast::Field,
+        matched: &mut Vec<MatchedRange>,
+    ) -> AsgResult<MatchArm> {
+        let (discriminant_ast, discriminant) = discriminant;
+        let discriminant_type = discriminant
+            .get_type()
+            .ok_or_else(|| AsgError::UninferredType(*discriminant_ast.span()))?;
+
+        let binary = |op, left: &ast::Expression, right: &ast::Expression| {
+            ast::Expression::Binary(ast::BinaryExpression {
+                op,
+                span: *left.span() + *right.span(),
+                left: Box::new(left.clone()),
+                right: Box::new(right.clone()),
+            })
+        };
+
+        let mut tag = None;
+        let mut condition: Option<ast::Expression> = None;
+        let mut wildcard = false;
+        let mut ranges = vec![];
+        for pattern in patterns {
+            let (low, high, inclusive, matches) = match pattern {
+                Pattern::Wildcard(_) => {
+                    wildcard = true;
+                    continue;
+                }
+                Pattern::Value(value) => (
+                    &**value,
+                    &**value,
+                    true,
+                    binary(BinaryOp::Eq, discriminant_ast, value),
+                ),
+                Pattern::Range {
+                    low,
+                    high,
+                    inclusive,
+                } => (
+                    &**low,
+                    &**high,
+                    *inclusive,
+                    binary(
+                        BinaryOp::And,
+                        &binary(BinaryOp::Gte, discriminant_ast, low),
+                        &binary(
+                            if *inclusive {
+                                BinaryOp::Lte
+                            } else {
+                                BinaryOp::Lt
+                            },
+                            discriminant_ast,
+                            high,
+                        ),
+                    ),
+                ),
+            };
+            let low_value = Scope::convert_expr(self_, low, discriminant_type.clone().into())?;
+            let high_value = Scope::convert_expr(self_, high, discriminant_type.clone().into())?;
+            let range = (
+                pattern_value(&low_value, *low.span())?,
+                pattern_value(&high_value, *high.span())?,
+                inclusive,
+            );
+            if field.condition.is_none() {
+                if matched
+                    .iter()
+                    .chain(ranges.iter())
+                    .any(|x| overlaps(x, &range))
+                {
+                    return Err(AsgError::OverlappingMatchArms(*low.span() + *high.span()));
+                }
+                ranges.push(range);
+            }
+            if tag.is_none() {
+                tag = Some(low_value);
+            }
+            condition = Some(match condition {
+                Some(condition) => binary(BinaryOp::Or, &condition, &matches),
+                None => matches,
+            });
+        }
+        matched.extend(ranges);
+
+        let condition = if wildcard { None } else { condition };
+        let mut field = field.clone();
+        field.condition = match (condition, field.condition.take()) {
+            (Some(condition), Some(guard)) => {
+                Some(Box::new(binary(BinaryOp::And, &condition, &guard)))
+            }
+            (condition, guard) => condition.map(Box::new).or(guard),
+        };
+
+        let unknown = match field.flags.iter().position(|x| x.name == "unknown") {
+            Some(index) => Some(field.flags.remove(index)),
+            None => None,
+        };
+        if let Some(flag) = &unknown {
+            if !wildcard || patterns.len() > 1 {
+                return Err(AsgError::InvalidUnknownVariant(flag.span));
+            }
+            // the arm's value becomes the `raw` field of a variant struct, next to the `tag` added on conversion
+            let span = field.span;
+            let condition = field.condition.take();
+            let attributes = std::mem::take(&mut field.attributes);
+            field = ast::Field {
+                type_: ast::Type {
+                    raw_type: ast::RawType::Container(ast::Container {
+                        length: None,
+                        discriminant: None,
+                        set: None,
+                        items: vec![ContainerItem::Field(
+                            ast::Ident {
+                                name: "raw".to_string(),
+                                span,
+                            },
+                            field,
+                        )],
+                        flags: vec![],
+                        span,
+                    }),
+                    span,
+                },
+                pointer: None,
+                flags: vec![],
+                calculated: None,
+                fixed: None,
+                constraint: None,
+                condition,
+                transforms: vec![],
+                attributes,
+                span,
+            };
+        }
+
+        Ok(MatchArm {
+            field,
+            tag,
+            unknown: unknown.is_some(),
+        })
+    }
+
+    /// Adds the discriminant to the variant of an `+unknown` arm as its first field, `tag`
+    pub(super) fn capture_discriminant(variant: &Arc<Field>, discriminant: &Expression) {
+        let mut type_ = variant.type_.borrow_mut();
+        let container = match &mut *type_ {
+            Type::Container(container) => container,
+            _ => unreachable!("unknown variant is not a container"),
+        };
+        let tag = Arc::new(Field {
+            name: "tag".to_string(),
+            type_: RefCell::new(discriminant.get_type().unwrap()),
+            calculated: RefCell::new(Some(discriminant.clone())),
+            constraint: RefCell::new(None),
+            condition: RefCell::new(None),
+            pointer: RefCell::new(None),
+            transforms: RefCell::new(vec![]),
+            span: variant.span,
+            toplevel: false,
+            arguments: RefCell::new(vec![]),
+            is_maybe_cyclical: Cell::new(false),
+            is_boxed: Cell::new(false),
+            is_pad: Cell::new(false),
+            is_fixed: Cell::new(false),
+            is_seekable: Cell::new(false),
+            is_tag: Cell::new(true),
+            encoded_size: Cell::new(None),
+            attributes: vec![],
+        });
+        let mut items = IndexMap::new();
+        items.insert(tag.name.clone(), tag);
+        items.extend(container.items.drain(..));
+        container.items = items;
+    }
+}
+
+fn is_constant(expr: &Expression) -> bool {
+    match expr {
+        Expression::Int(_)
+        | Expression::Bool(_)
+        | Expression::ConstRef(_)
+        | Expression::EnumAccess(_) => true,
+        Expression::Cast(x) => is_constant(&x.inner),
+        Expression::Unary(x) => is_constant(&x.inner),
+        Expression::Binary(x) => {
+            x.op != BinaryOp::Elvis && is_constant(&x.left) && is_constant(&x.right)
+        }
+        _ => false,
+    }
+}
+
+pub(super) fn pattern_value(expr: &Expression, span: Span) -> AsgResult<ConstValue> {
+    if !is_constant(expr) {
+        return Err(AsgError::MatchPatternNotConstant(span));
+    }
+    eval_const_expression(expr).ok_or(AsgError::MatchPatternNotConstant(span))
+}
+
+fn below(value: &ConstValue, high: &ConstValue, inclusive: bool) -> bool {
+    match value.partial_cmp(high) {
+        Some(Ordering::Less) => true,
+        Some(Ordering::Equal) => inclusive,
+        Some(Ordering::Greater) => false,
+        None => inclusive && value == high,
+    }
+}
+
+fn overlaps(a: &MatchedRange, b: &MatchedRange) -> bool {
+    below(&a.0, &b.1, b.2) && below(&b.0, &a.1, a.2)
+}
@@ -4,6 +4,10 @@ mod container;
 
 mod enum_;
 
+mod match_;
+
+mod set;
+
 mod bitfield;
 
 mod array;
@@ -0,0 +1,61 @@
+use crate::ConstValue;
+
+use super::match_::pattern_value;
+use super::*;
+
+impl Scope {
+    /// Converts the `set(tag, length)` header of a container
+    pub(super) fn convert_set_layout(
+        layout: &ast::RecordLayout,
+        preserve_order: bool,
+    ) -> AsgResult<SetLayout> {
+        let scalar = |type_: &ast::Type| match &type_.raw_type {
+            ast::RawType::Scalar(scalar) => Ok(*scalar),
+            _ => Err(AsgError::InvalidSetLayout(type_.span)),
+        };
+        Ok(SetLayout {
+            tag: scalar(&layout.tag)?,
+            length: scalar(&layout.length)?,
+            preserve_order,
+        })
+    }
+
+    /// Checks a member of a `set` container, returning its tag value
+    pub(super) fn convert_set_member(
+        self_: &Arc<RefCell<Scope>>,
+        layout: &SetLayout,
+        tag: &ast::Expression,
+        name: &ast::Ident,
+        field: &ast::Field,
+        seen: &mut Vec<(String, ConstValue)>,
+    ) -> AsgResult<Expression> {
+        // fields of the generated struct holding records that are not members, and the order records were read in
+        if name.name == "unknown" || name.name == "order" {
+            return Err(AsgError::ReservedSetMember(name.name.clone(), name.span));
+        }
+        if field.condition.is_some()
+            || field.calculated.is_some()
+            || field.fixed.is_some()
+            || field.pointer.is_some()
+            || field.flags.iter().any(|x| x.name == "auto")
+        {
+            return Err(AsgError::InvalidSetMember(name.name.clone(), field.span));
+        }
+        let value = Scope::convert_expr(
+            self_,
+            tag,
+            PartialType::Scalar(PartialScalarType::Some(layout.tag.scalar)),
+        )?;
+        let constant = pattern_value(&value, *tag.span())
+            .map_err(|_| AsgError::SetTagNotConstant(*tag.span()))?;
+        if let Some((other, _)) = seen.iter().find(|(_, x)| *x == constant) {
+            return Err(AsgError::DuplicateSetTag(
+                name.name.clone(),
+                other.clone(),
+                *tag.span(),
+            ));
+        }
+        seen.push((name.name.clone(), constant));
+        Ok(value)
+    }
+}
@@ -65,6 +65,8 @@ pub enum AsgError {
     InvalidInt(String, Span),
     #[error("invalid number of arguments for ffi, expected {0} to {1} arguments, got {2} @ {3}")]
     InvalidFFIArgumentCount(usize, usize, usize, Span),
+    #[error("invalid arguments for function '{0}': {1} @ {2}")]
+    InvalidFunctionArguments(String, String, Span),
     #[error("invalid number of arguments for type, expected {0} to {1} arguments, got {2} @ {3}")]
     InvalidTypeArgumentCount(usize, usize, usize, Span),
     #[error("cannot have required arguments after optional arguments for type @ {0}")]
@@ -75,20 +77,155 @@ pub enum AsgError {
         "illegal repitition of type -- outline the interior as a top level type declaration @ {0}"
     )]
     InlineRepetition(Span),
-    #[error("enums, bitfields, and enum containers must be top level @ {0}")]
+    #[error("enums, bitfields, and enum or set containers must be top level @ {0}")]
     MustBeToplevel(Span),
     #[error("cannot have field after unconditional field in enum container @ {0}")]
     EnumContainerFieldAfterUnconditional(Span),
     #[error("cannot have pad in enum container @ {0}")]
     EnumContainerPad(Span),
+    #[error("match pattern must be a constant @ {0}")]
+    MatchPatternNotConstant(Span),
+    #[error("match arm overlaps a previous arm @ {0}")]
+    OverlappingMatchArms(Span),
+    #[error("only a match arm selected by `_` alone can be `+unknown`, as variants chosen by conditions have no discriminant to keep @ {0}")]
+    InvalidUnknownVariant(Span),
+    #[error("set record tags and lengths must be integer types @ {0}")]
+    InvalidSetLayout(Span),
+    #[error("set member '{0}' cannot be conditional, calculated, fixed, auto or a pointer @ {1}")]
+    InvalidSetMember(String, Span),
+    #[error("set member '{0}' is reserved for the records of the set @ {1}")]
+    ReservedSetMember(String, Span),
+    #[error("'{0}' and '{1}' are both named `{2}` in Rust @ {3}")]
+    DuplicateRustName(String, String, String, Span),
+    #[error("set member tag must be a constant @ {0}")]
+    SetTagNotConstant(Span),
+    #[error("set member '{0}' has the same tag as '{1}' @ {2}")]
+    DuplicateSetTag(String, String, Span),
+    #[error("cannot have pad in set container @ {0}")]
+    SetContainerPad(Span),
+    #[error("map field '{0}' must be an array of a struct container type, counted, unbounded or bounded in bytes, keyed as `+map(key)` or `+map(key, error | first | last)` @ {1}")]
+    InvalidMapField(String, Span),
+    #[error("map key '{0}' must be an unconditional integer, bool, integer array or string field of the elements of '{1}' @ {2}")]
+    InvalidMapKey(String, String, Span),
     #[error("type `{0}` does not implement auto receiving @ {1}")]
     TypeNotAutoCompatible(String, Span),
+    #[error("auto field '{0}' is not used as the length of any field @ {1}")]
+    AutoFieldUnused(String, Span),
+    #[error("auto field '{0}' cannot be derived from the variant of '{1}', as it must be passed to a single field as the type argument a `match` is over @ {2}")]
+    UnderivableTag(String, String, Span),
+    #[error("fixed value field '{0}' cannot be top level or calculated, and its value cannot reference fields @ {1}")]
+    InvalidFixedField(String, Span),
+    #[error("constraint on '{0}' must be on a non-container field of a container @ {1}")]
+    InvalidConstraint(String, Span),
+    #[error("invalid use of addr() in '{0}', {1} @ {2}")]
+    InvalidAddr(String, String, Span),
+    #[error("cannot use {0}() here, {1} @ {2}")]
+    InvalidStreamPosition(String, String, Span),
+    #[error("constraint on '{0}' cannot reference '{1}', which is conditional @ {2}")]
+    ConditionalConstraintReference(String, String, Span),
+    #[error("constraint on '{0}' cannot reference '{1}', which is decoded after it @ {2}")]
+    LaterConstraintReference(String, String, Span),
+    #[error("pointer field '{0}' must be a non-container, untransformed field of a struct container read directly from the stream @ {1}")]
+    InvalidPointerField(String, Span),
+    #[error("field '{0}' references type '{1}', which uses pointer fields and cannot be read from a length constrained, transformed or unbounded stream @ {2}")]
+    SeekableInStream(String, String, Span),
+    #[error("type '{0}' contains itself through fields which are neither conditional nor arrays, and so has infinite size @ {1}")]
+    InfiniteSizeType(String, Span),
+    #[error("sizeof takes the name of a single type @ {0}")]
+    InvalidSizeOf(Span),
+    #[error("cannot take sizeof type '{0}', which does not have a fixed size @ {1}")]
+    SizeOfUnsized(String, Span),
+    #[error("array terminators must not be prefixes of one another @ {0}")]
+    OverlappingTerminators(Span),
+    #[error("array '{0}' leaving its terminator in the stream must be an untransformed field of a container @ {1}")]
+    InvalidTerminatorPolicy(String, Span),
     #[error("referenced bitfield member `{0}` does not exist @ {1}")]
     BitfieldMemberUndefined(String, Span),
+    #[error("referenced container field `{0}` does not exist @ {1}")]
+    ContainerMemberUndefined(String, Span),
+    #[error("invalid attribute '@{0}': {1} @ {2}")]
+    InvalidAttribute(String, String, Span),
     #[error("unknown: {0}")]
     Unknown(#[from] crate::Error),
 }
 
+impl AsgError {
+    /// Location in the source of the error, if known
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            AsgError::FfiMissing(_, span)
+            | AsgError::ImportMissing(_, span)
+            | AsgError::ConstTypeDefinition(_, span)
+            | AsgError::EnumDefaultRedefinition(_, span)
+            | AsgError::EnumVariantRedefinition(_, span)
+            | AsgError::UnresolvedType(_, span)
+            | AsgError::UnresolvedVar(_, span)
+            | AsgError::UnresolvedTransform(_, span)
+            | AsgError::UnresolvedFunction(_, span)
+            | AsgError::InvalidInt(_, span)
+            | AsgError::InvalidFlag(_, span)
+            | AsgError::InvalidSetMember(_, span)
+            | AsgError::ReservedSetMember(_, span)
+            | AsgError::InvalidMapField(_, span)
+            | AsgError::TypeNotAutoCompatible(_, span)
+            | AsgError::AutoFieldUnused(_, span)
+            | AsgError::UnderivableTag(_, _, span)
+            | AsgError::InvalidFixedField(_, span)
+            | AsgError::InvalidConstraint(_, span)
+            | AsgError::InvalidPointerField(_, span)
+            | AsgError::InfiniteSizeType(_, span)
+            | AsgError::SizeOfUnsized(_, span)
+            | AsgError::InvalidTerminatorPolicy(_, span)
+            | AsgError::BitfieldMemberUndefined(_, span)
+            | AsgError::ContainerMemberUndefined(_, span) => Some(span),
+            AsgError::ImportUnresolved(_, _, span)
+            | AsgError::UnexpectedType(_, _, span)
+            | AsgError::IllegalCast(_, _, span)
+            | AsgError::ReferencedDefaultEnumVariant(_, _, span)
+            | AsgError::UnresolvedEnumVariant(_, _, span)
+            | AsgError::UnresolvedBitfieldVariant(_, _, span)
+            | AsgError::DuplicateSetTag(_, _, span)
+            | AsgError::InvalidMapKey(_, _, span)
+            | AsgError::SeekableInStream(_, _, span)
+            | AsgError::InvalidFunctionArguments(_, _, span)
+            | AsgError::ConditionalConstraintReference(_, _, span)
+            | AsgError::LaterConstraintReference(_, _, span)
+            | AsgError::InvalidStreamPosition(_, _, span)
+            | AsgError::InvalidAddr(_, _, span)
+            | AsgError::InvalidAttribute(_, _, span) => Some(span),
+            AsgError::ImportParse(_, span, _)
+            | AsgError::TypeRedefinition(_, span, _)
+            | AsgError::TransformRedefinition(_, span, _)
+            | AsgError::FunctionRedefinition(_, span, _)
+            | AsgError::ConstRedefinition(_, span, _)
+            | AsgError::BitfieldFlagRedefinition(_, span, _)
+            | AsgError::ContainerFieldRedefinition(_, span, _)
+            | AsgError::InvalidTransformInput(_, span, _) => Some(span),
+            AsgError::CastTypeDefinition(span)
+            | AsgError::IllegalComplexTypeDefinition(span)
+            | AsgError::UninferredType(span)
+            | AsgError::InvalidTypeArgumentOrder(span)
+            | AsgError::InlineRepetition(span)
+            | AsgError::MustBeToplevel(span)
+            | AsgError::EnumContainerFieldAfterUnconditional(span)
+            | AsgError::EnumContainerPad(span)
+            | AsgError::MatchPatternNotConstant(span)
+            | AsgError::OverlappingMatchArms(span)
+            | AsgError::InvalidUnknownVariant(span)
+            | AsgError::InvalidSetLayout(span)
+            | AsgError::SetTagNotConstant(span)
+            | AsgError::SetContainerPad(span)
+            | AsgError::InvalidSizeOf(span)
+            | AsgError::OverlappingTerminators(span) => Some(span),
+            AsgError::InvalidTransformCondition(_, span, _, _) => Some(span),
+            AsgError::DuplicateRustName(_, _, _, span) => Some(span),
+            AsgError::InvalidFFIArgumentCount(_, _, _, span)
+            | AsgError::InvalidTypeArgumentCount(_, _, _, span) => Some(span),
+            AsgError::Unknown(_) => None,
+        }
+    }
+}
+
 impl fmt::Debug for AsgError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
@@ -20,6 +20,7 @@ pub enum Token {
     Function,
     Const,
     DotDot,
+    DotDotEq,
     Dot,
     Elvis,
     U8,
@@ -46,6 +47,7 @@ pub enum Token {
     Lt,
     Gt,
     Arrow,
+    FatArrow,
     Container,
     LeftSquare,
     RightSquare,
@@ -62,6 +64,7 @@ pub enum Token {
     Colon,
     DoubleColon,
     Semicolon,
+    At,
     Plus,
     Minus,
     Mul,
@@ -82,6 +85,10 @@ pub enum Token {
     BitNot,
     True,
     False,
+    Where,
+    In,
+    Until,
+    Match,
 }
 
 impl fmt::Display for Token {
@@ -104,6 +111,7 @@ impl fmt::Display for Token {
             Function => write!(f, "function "),
             Const => write!(f, "const "),
             DotDot => write!(f, ".. "),
+            DotDotEq => write!(f, "..= "),
             Dot => write!(f, ". "),
             Elvis => write!(f, "?: "),
             U8 => write!(f, "u8 "),
@@ -130,6 +138,7 @@ impl fmt::Display for Token {
             Lt => write!(f, "< "),
             Gt => write!(f, "> "),
             Arrow => write!(f, "-> "),
+            FatArrow => write!(f, "=> "),
             Container => write!(f, "container "),
             LeftSquare => write!(f, "["),
             RightSquare => write!(f, "]"),
@@ -146,6 +155,7 @@ impl fmt::Display for Token {
             Colon => write!(f, ":"),
             DoubleColon => write!(f, "::"),
             Semicolon => write!(f, ";"),
+            At => write!(f, "@"),
             Plus => write!(f, "+"),
             Minus => write!(f, "-"),
             Mul => write!(f, "*"),
@@ -166,6 +176,10 @@ impl fmt::Display for Token {
             BitNot => write!(f, "~"),
             True => write!(f, "true "),
             False => write!(f, "false "),
+            Where => write!(f, "where "),
+            In => write!(f, "in "),
+            Until => write!(f, "until "),
+            Match => write!(f, "match "),
         }
     }
 }
@@ -211,27 +225,40 @@ impl Token {
                 while i < input.len() {
                     if input[i] == b'\\' && i < input.len() - 1 {
                         i += 1;
-                        if input[i].is_ascii_hexdigit() {
-                            if i < input.len() - 1 && input[i + 1].is_ascii_hexdigit() {
-                                i += 1;
+                        match input[i] {
+                            b'x' if i + 2 < input.len()
+                                && input[i + 1].is_ascii_hexdigit()
+                                && input[i + 2].is_ascii_hexdigit() =>
+                            {
                                 out.push(
                                     u8::from_str_radix(
-                                        std::str::from_utf8(&input[i..i + 2]).unwrap(),
+                                        std::str::from_utf8(&input[i + 1..i + 3]).unwrap(),
                                         16,
                                     )
                                     .unwrap(),
                                 );
-                            } else {
+                                i += 2;
+                            }
+                            b'n' => out.push(b'\n'),
+                            b'r' => out.push(b'\r'),
+                            b't' => out.push(b'\t'),
+                            x if x.is_ascii_hexdigit() => {
+                                let len = if i < input.len() - 1 && input[i + 1].is_ascii_hexdigit()
+                                {
+                                    2
+                                } else {
+                                    1
+                                };
                                 out.push(
                                     u8::from_str_radix(
-                                        std::str::from_utf8(&input[i..i + 1]).unwrap(),
+                                        std::str::from_utf8(&input[i..i + len]).unwrap(),
                                         16,
                                     )
                                     .unwrap(),
                                 );
+                                i += len - 1;
                             }
-                        } else {
-                            out.push(input[i]);
+                            x => out.push(x),
                         }
                         i += 1;
                         if i == input.len() {
@@ -280,12 +307,15 @@ impl Token {
             b'=' => {
                 if let Some(input) = eat(input, "==") {
                     return (input, Some(Token::Eq));
+                } else if let Some(input) = eat(input, "=>") {
+                    return (input, Some(Token::FatArrow));
                 } else {
                     return (&input[1..], Some(Token::Equal));
                 }
             }
             b',' => return (&input[1..], Some(Token::Comma)),
             b';' => return (&input[1..], Some(Token::Semicolon)),
+            b'@' => return (&input[1..], Some(Token::At)),
             b'?' => {
                 if let Some(input) = eat(input, "?:") {
                     return (input, Some(Token::Elvis));
@@ -353,7 +383,9 @@ impl Token {
                 }
             }
             b'.' => {
-                if let Some(input) = eat(input, "..") {
+                if let Some(input) = eat(input, "..=") {
+                    return (input, Some(Token::DotDotEq));
+                } else if let Some(input) = eat(input, "..") {
                     return (input, Some(Token::DotDot));
                 } else {
                     return (&input[1..], Some(Token::Dot));
@@ -444,6 +476,10 @@ impl Token {
                     "from" => Token::From,
                     "true" => Token::True,
                     "false" => Token::False,
+                    "where" => Token::Where,
+                    "in" => Token::In,
+                    "until" => Token::Until,
+                    "match" => Token::Match,
                     _ => Token::Ident(ident),
                 }),
             );
@@ -611,6 +647,19 @@ mod tests {
         assert_eq!(output, r#""test""test"test""#);
     }
 
+    #[test]
+    fn test_string_escapes() {
+        let tokens = tokenize(r#""\x89PNG\r\n\x1a\n" "\0\41\t\\""#, false).unwrap();
+        let strings: Vec<_> = tokens.into_iter().map(|x| x.token).collect();
+        assert_eq!(
+            strings,
+            vec![
+                Token::String(b"\x89PNG\r\n\x1a\n".to_vec()),
+                Token::String(b"\0\x41\t\\".to_vec()),
+            ]
+        );
+    }
+
     #[test]
     fn test_tokenizer() {
         let tokens = tokenize(
@@ -0,0 +1,344 @@
+use super::*;
+
+#[test]
+fn test_compiler_array_until() {
+    let asg = load_asg(
+        r#"
+        type Entry = container {
+            kind: u8,
+            value: u16 {kind != 0},
+        };
+
+        type Test = container {
+            entries: Entry[.. until |entry| entry.kind == 0],
+            kept: Entry[..= until |entry| entry.kind == 0],
+            bytes: u8[..= until |byte| byte >= 0x80],
+            trailer: u8,
+        };
+
+        type NoTerminator = container {
+            bytes: u8[.. until |byte| byte >= 0x80],
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Test {
+            entries: vec![Entry { kind: 1, value: Some(2) }],
+            kept: vec![Entry { kind: 3, value: Some(4) }, Entry { kind: 0, value: None }],
+            bytes: vec![1, 0x82],
+            trailer: 7,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 0, 2, 0, 3, 0, 4, 0, 1, 0x82, 7]);
+        let decoded = Test::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+
+        let appended = Test {
+            kept: vec![Entry { kind: 3, value: Some(4) }],
+            ..item.clone()
+        };
+        let mut out = vec![];
+        appended.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 0, 2, 0, 3, 0, 4, 0, 1, 0x82, 7]);
+
+        let unterminated = Test::decode_sync(&mut &[1, 2, 0][..]);
+        assert!(unterminated.is_err());
+
+        let no_terminator = NoTerminator { bytes: vec![1] };
+        assert!(no_terminator.encode_sync(&mut vec![]).is_err());
+
+        let supplied = NoTerminator { bytes: vec![1, 0xff] };
+        let mut out = vec![];
+        supplied.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 0xff]);
+        assert_eq!(NoTerminator::decode_sync(&mut &out[..]).expect("failed to decode").bytes, vec![1]);
+
+        let early = Test {
+            entries: vec![
+                Entry { kind: 1, value: Some(2) },
+                Entry { kind: 0, value: None },
+                Entry { kind: 2, value: Some(3) },
+            ],
+            ..item.clone()
+        };
+        assert!(early.encode_sync(&mut vec![]).is_err());
+        let early = NoTerminator { bytes: vec![0x80, 1, 0x81] };
+        assert!(early.encode_sync(&mut vec![]).is_err());
+    };
+
+    compile("array_until", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_array_bytes() {
+    let asg = load_asg(
+        r#"
+        type Item = container {
+            len: u8 +auto,
+            data: u8[len],
+        };
+
+        type Test = container {
+            list_len: u16 +auto,
+            items: Item[bytes: list_len],
+            raw_len: u8,
+            raw: u16[bytes: raw_len],
+            trailer: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Test {
+            list_len: 0,
+            items: vec![
+                Item { len: 0, data: vec![1, 2] },
+                Item { len: 0, data: vec![] },
+            ],
+            raw_len: 4,
+            raw: vec![3, 4],
+            trailer: 7,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![0, 4, 2, 1, 2, 0, 4, 0, 3, 0, 4, 7]);
+        let decoded = Test::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded.list_len, 4);
+        assert_eq!(decoded.items, vec![
+            Item { len: 2, data: vec![1, 2] },
+            Item { len: 0, data: vec![] },
+        ]);
+        assert_eq!(decoded.raw, vec![3, 4]);
+        assert_eq!(decoded.trailer, 7);
+
+        let overrun = Test::decode_sync(&mut &[0, 2, 2, 1, 2, 0, 7][..]);
+        assert!(overrun.is_err());
+
+        let mismatched = Test { raw_len: 3, ..item.clone() };
+        assert!(mismatched.encode_sync(&mut vec![]).is_err());
+    };
+
+    compile("array_bytes", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_array_terminators() {
+    let asg = load_asg(
+        r#"
+        type Lines = container {
+            first: u8[.. "\r\n", "\n"],
+            second: u8[.. "\r\n", "\n"; eof],
+        };
+
+        type Left = container {
+            text: u8[.. ";"; leave],
+            separator: u8,
+            rest: u16[.. "\0\0"],
+        };
+
+        type Overlap = container {
+            text: u8[.. "abc", "b"],
+            rest: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let lines = Lines {
+            first: b"ab".to_vec(),
+            second: b"cd".to_vec(),
+        };
+        let mut out = vec![];
+        lines.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, b"ab\r\ncd\r\n".to_vec());
+        let mut reader = std::io::BufReader::with_capacity(1, &out[..]);
+        assert_eq!(Lines::decode_sync(&mut reader).expect("failed to decode"), lines);
+
+        let mut reader = std::io::BufReader::with_capacity(1, &b"ab\ncd"[..]);
+        assert_eq!(Lines::decode_sync(&mut reader).expect("failed to decode"), lines);
+        assert!(Lines::decode_sync(&mut &b"ab\r"[..]).is_err());
+
+        let left = Left {
+            text: b"x".to_vec(),
+            separator: b';',
+            rest: vec![1],
+        };
+        let mut out = vec![];
+        left.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![b'x', b';', 0, 1, 0, 0]);
+        let mut reader = std::io::BufReader::with_capacity(1, &out[..]);
+        assert_eq!(Left::decode_sync(&mut reader).expect("failed to decode"), left);
+
+        let overlap = Overlap {
+            text: b"a".to_vec(),
+            rest: b'X',
+        };
+        let mut reader = std::io::BufReader::with_capacity(1, &b"abX"[..]);
+        assert_eq!(Overlap::decode_sync(&mut reader).expect("failed to decode"), overlap);
+        assert_eq!(Overlap::decode_sync(&mut &b"abX"[..]).expect("failed to decode"), overlap);
+    };
+
+    compile("array_terminators", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_array_map() {
+    let asg = load_asg(
+        r#"
+        import_ffi utf8 as type;
+
+        type Attributes = container {
+            count: u8 +auto,
+            attributes: Attribute[count] +map(name),
+        };
+        type Attribute = container {
+            name_len: u8 +auto,
+            name: u8[name_len],
+            value: u16,
+        };
+        type Entries = container {
+            entries: Entry[..] +map(id, last),
+        };
+        type Entry = container {
+            id: u8,
+            value: u8,
+        };
+        type Named = container {
+            count: u8 +auto,
+            attributes: NamedAttribute[count] +map(name, last),
+        };
+        type NamedAttribute = container {
+            name_len: u8 +auto,
+            name: utf8(name_len),
+            value: u16,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let bytes = [2, 1, 97, 0, 1, 1, 98, 0, 2];
+        let decoded = Attributes::decode_sync(&mut &bytes[..]).expect("failed to decode");
+        assert_eq!(decoded.attributes.len(), 2);
+        assert_eq!(decoded.attributes[&b"b".to_vec()].value, 2);
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, bytes);
+        Attributes::decode_sync(&mut &[2, 1, 97, 0, 1, 1, 97, 0, 2][..]).expect_err("decoded duplicate key");
+
+        let decoded = Entries::decode_sync(&mut &[1, 5, 2, 6, 1, 7][..]).expect("failed to decode");
+        assert_eq!(decoded.entries.values().map(|x| x.value).collect::<Vec<_>>(), vec![7, 6]);
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, [1, 7, 2, 6]);
+
+        let decoded = Named::decode_sync(&mut &[2, 1, 97, 0, 1, 1, 97, 0, 2][..]).expect("failed to decode");
+        assert_eq!(decoded.attributes["a"].value, 2);
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, [1, 1, 97, 0, 2]);
+    };
+
+    // generated tests build without dependencies, and ordered maps round-trip sorted keys
+    let options = CompileOptions {
+        map_type: "std::collections::BTreeMap".to_string(),
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "array_map",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+
+    let options = CompileOptions {
+        map_type: "not a path".to_string(),
+        runtime: None,
+        ..Default::default()
+    };
+    let compiled = compiler::compile_program(&asg, &options).to_string();
+    assert!(compiled.contains("compile_error"));
+}
+
+#[test]
+fn test_compiler_array_fixed() {
+    let asg = load_asg(
+        r#"
+        type Kind = enum u8 {
+            a = 1,
+            b,
+        };
+        type Pair = container {
+            id: u8,
+            value: u16,
+        };
+        type Header = container {
+            mac: u8[6],
+            words: u32le[4],
+            hash: u8[32 * 2],
+            kinds: Kind[2],
+            pairs: Pair[2],
+            count: u8,
+            rest: u8[count],
+        };
+        type Signature = u8[64];
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Header {
+            mac: [1, 2, 3, 4, 5, 6],
+            words: [1, 2, 3, 0xffffffff],
+            hash: [7; 64],
+            kinds: [Kind::b, Kind::a],
+            pairs: [Pair { id: 1, value: 2 }, Pair { id: 3, value: 4 }],
+            count: 1,
+            rest: vec![9],
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out.len(), 6 + 16 + 64 + 2 + 6 + 2);
+        assert_eq!(&out[6..10], &[1, 0, 0, 0]);
+        let decoded = Header::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        assert!(Header::decode_sync(&mut &out[..10]).is_err());
+
+        let default = Header::default();
+        assert_eq!(default.hash, [0; 64]);
+        assert_eq!(Signature::default().0, [0; 64]);
+    };
+
+    compile("array_fixed", &compile_test_program(&asg, test));
+
+    let test = quote! {
+        let item = Header {
+            mac: vec![1, 2, 3, 4, 5, 6],
+            words: vec![1, 2, 3, 4],
+            hash: vec![7; 64],
+            kinds: vec![Kind::b, Kind::a],
+            pairs: vec![Pair { id: 1, value: 2 }, Pair { id: 3, value: 4 }],
+            count: 0,
+            rest: vec![],
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        let decoded = Header::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        assert_eq!(Signature::default().0, vec![]);
+    };
+
+    let options = CompileOptions {
+        fixed_arrays: false,
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "array_fixed_vec",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
@@ -0,0 +1,73 @@
+use super::*;
+
+#[test]
+fn test_compiler_attributes() {
+    let asg = load_asg(
+        r#"
+        @derive(Hash, Eq) @rust_name("Kind")
+        type kind = enum u8 {
+            Small = 1,
+            Large,
+            Other = default,
+        };
+        @derive(Hash, Eq) @rust_name("Flags")
+        type flags = bitfield u8 {
+            A = 0x1,
+            B,
+        };
+        @derive(Hash, Eq) @rust_name("Id") @rust(doc = "An identifier")
+        type id = u16;
+        @derive(Hash, Eq) @rust_name("Body")
+        type body(k: kind) = container match k {
+            kind::Small => Small: u8,
+            kind::Large => Large: container {
+                @rust(doc = "The high half") high: u16,
+                low: u16,
+            },
+            _ => Unknown: u8[..],
+        };
+        @derive(Hash, Eq) @rust_name("Packet") @rust(must_use, doc = "A packet") @custom(1, "two")
+        type packet = container {
+            @rust(doc = "The kind of the body") kind: kind +auto,
+            flags: flags,
+            kinds: kind[2],
+            body: body(kind),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        fn roundtrip(item: &Packet) -> Vec<u8> {
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = Packet::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(item, &decoded);
+            out
+        }
+        let item = Packet {
+            kind: Kind::Large,
+            flags: Flags::A | Flags::B,
+            kinds: [Kind::Small, Kind::Large],
+            body: Body::Large { high: 1, low: 2 },
+        };
+        assert_eq!(roundtrip(&item), vec![2, 3, 1, 2, 0, 1, 0, 2]);
+        let item = Packet {
+            kind: Kind::Small,
+            flags: Flags::default(),
+            kinds: [Kind::Other(9), Kind::Small],
+            body: Body::Small(3),
+        };
+        roundtrip(&item);
+
+        let mut out = vec![];
+        Id(7).encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(Id::decode_sync(&mut &out[..]).expect("failed to decode"), Id(7));
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(item.clone());
+        assert!(set.contains(&item));
+    };
+
+    compile("attributes", &compile_test_program(&asg, test));
+}
@@ -23,7 +23,7 @@ fn test_compiler_calculated() {
         }
         roundtrip(Payload {
             child_count: 0,
-            reserved: vec![0u8],
+            reserved: [0u8],
             children_windows: vec![5u32],
         });
     };
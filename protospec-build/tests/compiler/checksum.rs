@@ -0,0 +1,182 @@
+use super::*;
+
+#[test]
+fn test_compiler_checksum_values() {
+    let asg = load_asg(
+        r#"
+    import_ffi crc32 as function;
+    import_ffi crc32c as function;
+    import_ffi crc16 as function;
+    import_ffi crc16_modbus as function;
+    import_ffi crc16_ccitt as function;
+    import_ffi crc16_xmodem as function;
+    import_ffi crc16_kermit as function;
+    import_ffi adler32 as function;
+    import_ffi fletcher16 as function;
+    import_ffi fletcher32 as function;
+    import_ffi xor8 as function;
+    import_ffi sum8 as function;
+
+    type test = container {
+        data: u8[9],
+        crc32: u32 = crc32(data),
+        crc32c: u32 = crc32c(data),
+        crc16: u16 = crc16(data),
+        crc16_modbus: u16 = crc16_modbus(data),
+        crc16_ccitt: u16 = crc16_ccitt(data),
+        crc16_xmodem: u16 = crc16_xmodem(data),
+        crc16_kermit: u16 = crc16_kermit(data),
+        adler32: u32 = adler32(data),
+        fletcher16: u16 = fletcher16(data),
+        fletcher32: u32 = fletcher32(data),
+        xor8: u8 = xor8(data),
+        sum8: u8 = sum8(data),
+    };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        {
+            let item = test {
+                data: *b"123456789",
+                ..Default::default()
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.crc32, 0xcbf43926);
+            assert_eq!(decoded.crc32c, 0xe3069283);
+            assert_eq!(decoded.crc16, 0xbb3d);
+            assert_eq!(decoded.crc16_modbus, 0x4b37);
+            assert_eq!(decoded.crc16_ccitt, 0x29b1);
+            assert_eq!(decoded.crc16_xmodem, 0x31c3);
+            assert_eq!(decoded.crc16_kermit, 0x2189);
+            assert_eq!(decoded.adler32, 0x091e01de);
+            assert_eq!(decoded.fletcher16, 0x1ede);
+            assert_eq!(decoded.fletcher32, 0xdf09d509);
+            assert_eq!(decoded.xor8, 0x31);
+            assert_eq!(decoded.sum8, 0xdd);
+        }
+    };
+
+    compile("checksum_values", &compile_test_program(&asg, test));
+}
+
+const CHUNK_SPEC: &str = r#"
+    import_ffi crc32 as function;
+
+    type chunk_header = container {
+        chunk_type: u32,
+        flags: u8,
+    };
+    type chunk = container {
+        length: u32 +auto,
+        header: chunk_header,
+        data: u8[length],
+        crc: u32 = crc32(header, data),
+    };
+"#;
+
+#[test]
+fn test_compiler_checksum_mismatch() {
+    let asg = load_asg(CHUNK_SPEC).unwrap();
+
+    let test = quote! {
+        {
+            let item = chunk {
+                header: chunk_header { chunk_type: 0x49454e44, flags: 0 },
+                data: vec![1, 2, 3],
+                ..Default::default()
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = chunk::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.data, item.data);
+            assert_eq!(decoded.crc, 0x771f_90eb);
+
+            let last = out.len() - 1;
+            out[last] ^= 1;
+            assert!(chunk::decode_sync(&mut &out[..]).is_err());
+        }
+    };
+
+    compile("checksum_mismatch", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_checksum_mismatch_warning() {
+    let asg = load_asg(CHUNK_SPEC).unwrap();
+
+    let test = quote! {
+        {
+            let item = chunk {
+                header: chunk_header { chunk_type: 0x49454e44, flags: 0 },
+                data: vec![1, 2, 3],
+                ..Default::default()
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let last = out.len() - 1;
+            out[last] ^= 1;
+            let decoded = chunk::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.data, item.data);
+        }
+    };
+
+    let options = CompileOptions {
+        warn_on_checksum_mismatch: true,
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "checksum_mismatch_warning",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
+
+#[test]
+fn test_compiler_checksum_conditional() {
+    let asg = load_asg(
+        r#"
+    import_ffi crc32 as function;
+
+    type test = container {
+        has_crc: bool,
+        data: u8[4],
+        crc: u32 = crc32(data) {has_crc},
+    };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        {
+            let item = test {
+                has_crc: true,
+                data: [1, 2, 3, 4],
+                ..Default::default()
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.crc, Some(0xb63cfbcd));
+
+            let last = out.len() - 1;
+            out[last] ^= 1;
+            assert!(test::decode_sync(&mut &out[..]).is_err());
+
+            let item = test {
+                has_crc: false,
+                data: [1, 2, 3, 4],
+                ..Default::default()
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.crc, None);
+        }
+    };
+
+    compile("checksum_conditional", &compile_test_program(&asg, test));
+}
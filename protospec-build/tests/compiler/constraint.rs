@@ -0,0 +1,95 @@
+use super::*;
+
+#[test]
+fn test_compiler_constraint() {
+    let asg = load_asg(
+        r#"
+        type Header = container {
+            width: u32 where width > 0,
+            version: u8 where version in 1..=3,
+            len: u16 +auto where len <= 4,
+            data: u8[len],
+            flags: u8,
+            extra: u16 where extra < 10 {flags == 1},
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Header {
+            width: 1,
+            version: 3,
+            len: 0,
+            data: vec![1, 2, 3],
+            flags: 1,
+            extra: Some(9),
+        };
+        item.validate().expect("failed to validate");
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        let decoded = Header::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded.data, item.data);
+
+        let mut invalid = out.clone();
+        invalid[3] = 0;
+        let error = Header::decode_sync(&mut &invalid[..]).unwrap_err().to_string();
+        assert!(error.contains("width"), "{}", error);
+
+        let mut invalid = out.clone();
+        invalid[4] = 4;
+        let error = Header::decode_sync(&mut &invalid[..]).unwrap_err().to_string();
+        assert!(error.contains("version"), "{}", error);
+
+        let mut invalid = out.clone();
+        invalid[out.len() - 1] = 10;
+        assert!(Header::decode_sync(&mut &invalid[..]).is_err());
+
+        for invalid in [
+            Header { width: 0, ..item.clone() },
+            Header { version: 0, ..item.clone() },
+            Header { data: vec![0; 5], ..item.clone() },
+            Header { extra: Some(10), ..item.clone() },
+        ] {
+            assert!(invalid.encode_sync(&mut vec![]).is_err());
+        }
+        for invalid in [
+            Header { width: 0, ..item.clone() },
+            Header { version: 4, ..item.clone() },
+            Header { extra: Some(10), ..item.clone() },
+        ] {
+            let error = invalid.validate().unwrap_err();
+            assert!(error.to_string().starts_with("constraint violated"), "{}", error);
+        }
+        Header { flags: 0, extra: None, ..item.clone() }.validate().expect("failed to validate");
+    };
+
+    compile("constraint", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_constraint_tagged_enum() {
+    let asg = load_asg(
+        r#"
+        type Message = container +tagged_enum {
+            small: u8 where small < 10 {true},
+            pair: container {
+                a: u8,
+                b: u8 where b > a,
+            },
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        Message::small(9).validate().expect("failed to validate");
+        assert!(Message::small(10).validate().is_err());
+        Message::pair { a: 1, b: 2 }.validate().expect("failed to validate");
+        assert!(Message::pair { a: 2, b: 2 }.validate().is_err());
+        assert!(Message::pair { a: 2, b: 2 }.encode_sync(&mut vec![]).is_err());
+        assert!(Message::decode_sync(&mut &[10u8][..]).is_err());
+    };
+
+    compile("constraint_tagged_enum", &compile_test_program(&asg, test));
+}
@@ -204,11 +204,12 @@ fn test_compiler_container_repeated() {
             let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
             assert_eq!(item, &decoded);
         }
-        roundtrip(&test(vec![tester {
+        let item = tester {
             len: 5,
             is_present: true,
             data: Some(vec![0u8, 3u8, 5u8, 1u8, 4u8]),
-        }; 3]));
+        };
+        roundtrip(&test([item.clone(), item.clone(), item]));
     };
 
     compile("container_repeated", &compile_test_program(&asg, test));
@@ -374,3 +375,97 @@ fn test_compiler_container_multi_nested() {
 
     compile("container_multi_nested", &compile_test_program(&asg, test));
 }
+
+#[test]
+fn test_compiler_container_recursive() {
+    let asg = load_asg(
+        r#"
+    type Node = container {
+        value: u8,
+        has_next: bool,
+        next: Node {has_next},
+    };
+
+    type Expr = container {
+        op: u8,
+        body: ExprBody(op),
+    };
+
+    type ExprBody(op: u8) = container match op {
+        0 => Literal: u8,
+        1 => Negate: Expr,
+        _ => Add: container {
+            left: Expr,
+            right: Expr,
+        },
+    };
+
+    type Outer = container {
+        has_inner: bool,
+        inner: Inner {has_inner},
+    };
+
+    type Inner = container {
+        value: u8,
+        outer: Outer,
+    };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Node {
+            value: 1,
+            has_next: true,
+            next: Some(Box::new(Node {
+                value: 2,
+                has_next: false,
+                next: None,
+            })),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 1, 2, 0]);
+        let decoded = Node::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+
+        let literal = |value| Expr {
+            op: 0,
+            body: ExprBody::Literal(value),
+        };
+        let item = Expr {
+            op: 2,
+            body: ExprBody::Add {
+                left: Box::new(literal(3)),
+                right: Box::new(Expr {
+                    op: 1,
+                    body: ExprBody::Negate(Box::new(literal(4))),
+                }),
+            },
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![2, 0, 3, 1, 0, 4]);
+        let decoded = Expr::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+
+        // the conditional field ends the cycle, so the field back to its type is held inline
+        let item = Outer {
+            has_inner: true,
+            inner: Some(Box::new(Inner {
+                value: 3,
+                outer: Outer {
+                    has_inner: false,
+                    inner: None,
+                },
+            })),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 3, 0]);
+        let decoded = Outer::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+    };
+
+    compile("container_recursive", &compile_test_program(&asg, test));
+}
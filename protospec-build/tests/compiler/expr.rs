@@ -18,9 +18,9 @@ fn test_compiler_expr() {
             let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
             assert_eq!(item, &decoded);
         }
-        roundtrip(&test(vec![0, 1, 2]));
-        roundtrip(&test(vec![2, 1, 0]));
-        roundtrip(&test(vec![2, 3, 0]));
+        roundtrip(&test([0, 1, 2]));
+        roundtrip(&test([2, 1, 0]));
+        roundtrip(&test([2, 3, 0]));
     };
 
     compile("expr", &compile_test_program(&asg, test));
@@ -51,7 +51,7 @@ fn test_compiler_expr_sum() {
             let decoded = Test::decode_sync(&mut &out[..]).expect("failed to decode");
             assert_eq!(item, &decoded);
         }
-        let mut levels = vec![0u8; 32];
+        let mut levels = [0u8; 32];
         levels[0] = 1;
         levels[1] = 3;
         roundtrip(&Test {
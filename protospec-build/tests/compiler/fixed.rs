@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn test_compiler_fixed() {
+    let asg = load_asg(
+        r#"
+        type Class = container {
+            magic: u32 == 0xCAFEBABE,
+            minor: u16,
+            body: container {
+                marker: u8[4] == "RIFF",
+                size: u32le,
+                version: u16 == 3 {size > 0},
+            },
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Class {
+            minor: 7,
+            size: 1,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(
+            out,
+            vec![0xca, 0xfe, 0xba, 0xbe, 0, 7, b'R', b'I', b'F', b'F', 1, 0, 0, 0, 0, 3]
+        );
+        let decoded = Class::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+
+        let mut invalid = out.clone();
+        invalid[0] = 0;
+        let error = Class::decode_sync(&mut &invalid[..]).unwrap_err().to_string();
+        assert!(error.contains("magic"), "{}", error);
+
+        let mut invalid = out.clone();
+        invalid[9] = b'X';
+        let error = Class::decode_sync(&mut &invalid[..]).unwrap_err().to_string();
+        assert!(error.contains("marker"), "{}", error);
+
+        let mut invalid = out.clone();
+        invalid[15] = 4;
+        let error = Class::decode_sync(&mut &invalid[..]).unwrap_err().to_string();
+        assert!(error.contains("expected 3, got 4"), "{}", error);
+
+        let item = Class {
+            minor: 7,
+            size: 0,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out.len(), 14);
+        let decoded = Class::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+    };
+
+    compile("fixed", &compile_test_program(&asg, test));
+}
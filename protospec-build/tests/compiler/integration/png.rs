@@ -4,14 +4,16 @@ use super::*;
 fn test_compiler_png() {
     let asg = load_asg(
         r#"
+        import_ffi crc32 as function;
+
         type PngChunk = container {
-            length: u32,
+            length: u32 +auto,
             chunk_type: u32,
             data: u8[length],
-            crc: u32,
+            crc: u32 = crc32(chunk_type, data),
         };
         type Png = container {
-            header: u8[8],
+            header: u8[8] == "\x89PNG\r\n\x1a\n",
             chunks: PngChunk[..]
         };
     "#,
@@ -19,6 +21,26 @@ fn test_compiler_png() {
     .unwrap();
 
     let test = quote! {
+        let iend = PngChunk {
+            chunk_type: 0x49454e44,
+            ..Default::default()
+        };
+        let mut out = vec![];
+        iend.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![0, 0, 0, 0, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82]);
+        let decoded = PngChunk::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded.crc, 0xae426082);
+
+        let png = Png {
+            chunks: vec![iend],
+        };
+        let mut out = vec![];
+        png.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(&out[..8], b"\x89PNG\r\n\x1a\n");
+        let decoded = Png::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded.chunks.len(), 1);
+        out[1] = b'p';
+        assert!(Png::decode_sync(&mut &out[..]).is_err());
         // fn roundtrip(item: &test) {
         //     let mut out = vec![];
         //     item.encode_sync(&mut out).expect("failed to encode");
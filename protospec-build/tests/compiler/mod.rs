@@ -5,16 +5,28 @@ use quote::quote;
 use std::io::Write;
 use std::process::Command;
 
+mod array;
+mod attribute;
 mod bitfield;
 mod calculated;
+mod checksum;
+mod constraint;
 mod container;
 mod enum_;
 mod expr;
+mod fixed;
 mod foreign_type;
 mod integration;
+mod naming;
+mod pointer;
+mod position;
 mod primitive;
+mod runtime;
+mod set;
+mod size;
 mod tagged_enum;
 mod transform;
+mod type_map;
 
 pub fn rustfmt(input: &str) -> String {
     let mut proc = Command::new("rustfmt")
@@ -38,12 +50,46 @@ pub fn lineify(input: &str) -> String {
 }
 
 pub fn compile(name: &str, input: &str) {
+    compile_with_crates(name, input, &[])
+}
+
+/// Compiles and runs `input`, linked against each of `crates` built from `(crate name, source)`
+pub fn compile_with_crates(name: &str, input: &str, crates: &[(&str, &str)]) {
+    let mut externs = vec![];
+    for (crate_name, source) in crates {
+        let srcname = format!("{}_{}_test.rs", name, crate_name);
+        let libname = format!("lib{}_{}_test.rlib", name, crate_name);
+        std::fs::write(&srcname, source).expect("failed to write test crate file");
+        let status = Command::new("rustc")
+            .arg(&srcname)
+            .arg("--crate-name")
+            .arg(crate_name)
+            .arg("--crate-type")
+            .arg("rlib")
+            .arg("--edition")
+            .arg("2018")
+            .arg("-o")
+            .arg(&libname)
+            .status()
+            .expect("rustc failed");
+        std::fs::remove_file(&srcname).expect("failed to delete test crate file");
+        if !status.success() {
+            panic!("compile of test crate {} failed", crate_name);
+        }
+        externs.push((crate_name.to_string(), libname));
+    }
     let input = rustfmt(input);
     println!("{}", lineify(&input));
     let inname = format!("{}_test.rs", name);
     let outname = format!("{}_test.out", name);
     std::fs::write(&inname, &input).expect("failed to write test input file");
-    let mut proc = Command::new("rustc")
+    let mut command = Command::new("rustc");
+    for (crate_name, libname) in externs.iter() {
+        command
+            .arg("--extern")
+            .arg(format!("{}={}", crate_name, libname));
+    }
+    let mut proc = command
         .arg(&inname)
         .arg("--crate-name")
         .arg(name)
@@ -60,6 +106,9 @@ pub fn compile(name: &str, input: &str) {
         .expect("rustc failed");
     let rustc_status = proc.wait().unwrap();
     std::fs::remove_file(&inname).expect("failed to delete input file");
+    for (_, libname) in externs.iter() {
+        std::fs::remove_file(libname).expect("failed to delete test crate");
+    }
     if !rustc_status.success() {
         std::fs::remove_file(&outname).expect("failed to delete output file");
         panic!("compile failed");
@@ -78,8 +127,19 @@ pub fn compile(name: &str, input: &str) {
 }
 
 fn compile_test_program(program: &Program, test: TokenStream) -> String {
-    let options = CompileOptions::default();
-    let compiled = compiler::compile_program(&program, &options);
+    let options = CompileOptions {
+        runtime: None,
+        ..Default::default()
+    };
+    compile_test_program_with_options(program, test, &options)
+}
+
+fn compile_test_program_with_options(
+    program: &Program,
+    test: TokenStream,
+    options: &CompileOptions,
+) -> String {
+    let compiled = compiler::compile_program(&program, options);
     let compiled_test = quote! {
         #compiled
         fn main() {
@@ -0,0 +1,236 @@
+use super::*;
+
+#[test]
+fn test_compiler_reserved_names() {
+    let asg = load_asg(
+        r#"
+        type Result = enum u8 {
+            Self = 1,
+            loop,
+        };
+        type Cursor = container {
+            fn: u8,
+            Self: u16,
+            ref: Result,
+        };
+        type DecodeError(ref: Result) = container match ref {
+            Result::Self => self: u8,
+            Result::loop => crate: u16,
+        };
+        type Map = container {
+            cursor: Cursor,
+            entries: Cursor[1] +map(fn),
+            error: DecodeError(cursor.ref),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Map {
+            cursor: Cursor { r#fn: 1, Self_: 2, r#ref: Result::r#loop },
+            entries: vec![(3, Cursor { r#fn: 3, Self_: 4, r#ref: Result::r#loop })].into_iter().collect(),
+            error: DecodeError::crate_(5),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 0, 2, 2, 3, 0, 4, 2, 0, 5]);
+        let decoded = Map::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        assert!(Result::from_repr(3).is_err());
+    };
+
+    let options = CompileOptions {
+        map_type: "std::collections::BTreeMap".to_string(),
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "reserved_names",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
+
+#[test]
+fn test_compiler_rust_case() {
+    let asg = load_asg(
+        r#"
+        type frame_kind = enum u8 {
+            data_frame = 1,
+            CTRL_FRAME,
+        };
+        type frame_body(bodyKind: frame_kind) = container match bodyKind {
+            frame_kind::data_frame => data_frame: container {
+                payloadLen: u8 +auto,
+                payload: u8[payloadLen],
+            },
+            frame_kind::CTRL_FRAME => CTRL_FRAME: u16,
+        };
+        type http_frame = container {
+            frameKind: frame_kind +auto,
+            HTTPBody: frame_body(frameKind),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = HttpFrame {
+            frame_kind: FrameKind::DataFrame,
+            http_body: FrameBody::DataFrame { payload_len: 2, payload: vec![7, 8] },
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 2, 7, 8]);
+        let decoded = HttpFrame::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        let body = FrameBody::CtrlFrame(3);
+        assert_eq!(body.encoded_len(FrameKind::CtrlFrame).unwrap(), 2);
+    };
+
+    let options = CompileOptions {
+        rust_case: true,
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "rust_case",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
+
+#[test]
+fn test_compiler_renamed_fields() {
+    let asg = load_asg(
+        r#"
+        type entry = container {
+            @rust_name("id") key: u8,
+            value: u8 where value > key,
+        };
+        type body(bodyKind: u8) = container match bodyKind {
+            @rust_name("Short") 1 => small: u8,
+            @rust_name("HTTPLong") 2 => large: container {
+                @rust_name("high_half") high: u8,
+                low: u8 where low < high,
+            },
+        };
+        type packet = container {
+            @rust_name("kind") bodyKind: u8 +auto,
+            @rust_name("count") entryCount: u8 +auto,
+            @rust_name("entry_map") entries: entry[entryCount] +map(key),
+            body: body(bodyKind),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Packet {
+            kind: 2,
+            count: 1,
+            entry_map: vec![(3, Entry { id: 3, value: 4 })].into_iter().collect(),
+            body: Body::HTTPLong { high_half: 6, low: 5 },
+        };
+        assert!(item.validate().is_ok());
+        assert_eq!(item.encoded_len().unwrap(), 6);
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![2, 1, 3, 4, 6, 5]);
+        let decoded = Packet::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        let body = Body::HTTPLong { high_half: 5, low: 6 };
+        assert!(body.validate(2).is_err());
+        assert_eq!(Body::Short(1).encoded_len(1).unwrap(), 1);
+    };
+
+    let options = CompileOptions {
+        rust_case: true,
+        map_type: "std::collections::BTreeMap".to_string(),
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "renamed_fields",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
+
+#[test]
+fn test_compiler_generated_names() {
+    let asg = load_asg(
+        r#"
+        import_ffi utf8 as type;
+        type Vec = container { value: u8 };
+        type Option = container { value: u8 };
+        type Box = container {
+            value: u8,
+            next: Box {value > 0},
+        };
+        type String = container {
+            len: u8 +auto,
+            text: utf8(len),
+        };
+        type Entry(reader: u8, writer: u8) = container {
+            _ps_reader: u8,
+            items: Vec[reader],
+            flag: Option {writer > 0},
+            name: String,
+            boxed: Box,
+        };
+        type Table(layout: u8, region: u8) = container {
+            stream_base: u8,
+            offset: u8 +auto,
+            data: u8[layout] @ (offset),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Entry {
+            _ps_reader_: 1,
+            items: vec![Vec { value: 2 }, Vec { value: 3 }],
+            flag: Some(Option { value: 4 }),
+            name: String { len: 0, text: "ab".to_string() },
+            boxed: Box { value: 1, next: Some(std::boxed::Box::new(Box { value: 0, next: None })) },
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out, 2, 1).expect("failed to encode");
+        assert_eq!(out, vec![1, 2, 3, 4, 2, b'a', b'b', 1, 0]);
+        let decoded = Entry::decode_sync(&mut &out[..], 2, 1).expect("failed to decode");
+        assert_eq!(decoded.name.text, "ab");
+        assert_eq!(decoded.items, item.items);
+
+        let table = Table { stream_base: 5, offset: 0, data: vec![6, 7] };
+        let mut out = vec![];
+        table.encode_sync(&mut out, 2, 0).expect("failed to encode");
+        assert_eq!(out, vec![5, 2, 6, 7]);
+        let decoded = Table::decode_sync(&mut std::io::Cursor::new(&out[..]), 2, 0).expect("failed to decode");
+        assert_eq!(decoded.data, vec![6, 7]);
+    };
+
+    compile("generated_names", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_rust_case_collision() {
+    let asg = load_asg(
+        r#"
+        type packet = container {
+            payloadLen: u8,
+            payload_len: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let options = CompileOptions {
+        rust_case: true,
+        runtime: None,
+        ..Default::default()
+    };
+    let compiled = compiler::compile_program(&asg, &options).to_string();
+    assert!(compiled.contains("compile_error"));
+    let compiled = compiler::compile_program(&asg, &CompileOptions::default()).to_string();
+    assert!(!compiled.contains("compile_error"));
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+#[test]
+fn test_compiler_pointer() {
+    let asg = load_asg(
+        r#"
+        type Table = container {
+            count: u8 +auto,
+            entries: u16[count],
+        };
+
+        type Header = container {
+            magic: u8,
+            table_offset: u32 +auto,
+            name_offset: u8,
+            table: Table @ (table_offset),
+            name: u8[2] @ (name_offset),
+            trailer: u8,
+        };
+
+        type File = container {
+            version: u8,
+            header: Header,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = File {
+            version: 2,
+            header: Header {
+                magic: 7,
+                table_offset: 0,
+                name_offset: 9,
+                table: Table {
+                    count: 0,
+                    entries: vec![1, 2],
+                },
+                name: *b"hi",
+                trailer: 9,
+            },
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(
+            out,
+            vec![2, 7, 0, 0, 0, 11, 9, 9, 0, b'h', b'i', 2, 0, 1, 0, 2]
+        );
+
+        let mut stream = std::io::Cursor::new(vec![0xff, 0xff]);
+        stream.set_position(2);
+        stream.get_mut().extend_from_slice(&out[..]);
+        stream.get_mut().push(0xee);
+        let decoded = File::decode_sync(&mut stream).expect("failed to decode");
+        assert_eq!(stream.position(), 10);
+        assert_eq!(decoded.header.table.entries, vec![1, 2]);
+        assert_eq!(decoded.header.table.count, 2);
+        assert_eq!(decoded.header.table_offset, 11);
+        assert_eq!(decoded.header.name, *b"hi");
+        assert_eq!(decoded.header.trailer, 9);
+
+        let mut overlapping = item.clone();
+        overlapping.header.name_offset = 3;
+        let error = overlapping
+            .encode_sync(&mut vec![])
+            .expect_err("encoded overlapping regions");
+        assert!(error.to_string().contains("overlaps"));
+    };
+
+    compile("pointer", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_pointer_after_terminated_array() {
+    let asg = load_asg(
+        r#"
+        type Data = container {
+            offset: u32 +auto,
+            data: u8[4] @ (offset),
+        };
+
+        type Test = container {
+            name: u8[.. "xy"],
+            data: Data,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Test {
+            name: vec![b'a', b'x'],
+            data: Data {
+                offset: 0,
+                data: [1, 2, 3, 4],
+            },
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![b'a', b'x', b'x', b'y', 0, 0, 0, 8, 1, 2, 3, 4]);
+
+        let decoded = Test::decode_sync(&mut std::io::Cursor::new(&out[..])).expect("failed to decode");
+        assert_eq!(decoded.name, vec![b'a', b'x']);
+        assert_eq!(decoded.data.data, [1, 2, 3, 4]);
+
+        // a reader buffering single bytes leaves the terminator's bytes read ahead when seeking
+        let mut reader = std::io::BufReader::with_capacity(1, std::io::Cursor::new(&out[..]));
+        let decoded = Test::decode_sync(&mut reader).expect("failed to decode");
+        assert_eq!(decoded.name, vec![b'a', b'x']);
+        assert_eq!(decoded.data.data, [1, 2, 3, 4]);
+    };
+
+    compile(
+        "pointer_after_terminated_array",
+        &compile_test_program(&asg, test),
+    );
+}
@@ -0,0 +1,219 @@
+use super::*;
+
+#[test]
+fn test_compiler_align() {
+    let asg = load_asg(
+        r#"
+        type Aligned = container {
+            tag: u8,
+            .align(4),
+            value: u32,
+            body_len: u16 +auto,
+            .align(4),
+            body: container [body_len] {
+                kind: u8,
+                .align(2),
+                data: u8[remaining()],
+            },
+            end: u8 = offset() :> u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let mut item = Aligned {
+            tag: 7,
+            value: 42,
+            body_len: 0,
+            kind: 9,
+            data: vec![1, 2, 3],
+            end: 0,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(
+            out,
+            vec![7, 0, 0, 0, 0, 0, 0, 42, 0, 5, 0, 0, 9, 0, 1, 2, 3, 17]
+        );
+        let decoded = Aligned::decode_sync(&mut &out[..]).expect("failed to decode");
+        item.body_len = 5;
+        item.end = 17;
+        assert_eq!(decoded, item);
+    };
+
+    compile("align", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_eof() {
+    let asg = load_asg(
+        r#"
+        type Trailer = container {
+            version: u8,
+            flags: u16 {!eof()},
+        };
+
+        type Framed = container {
+            len: u8,
+            frame: container [len] {
+                header: u8,
+                extra: u8 {!eof()},
+                payload: u8[remaining()],
+            },
+            tail: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Trailer {
+            version: 1,
+            flags: Some(3),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 0, 3]);
+        assert_eq!(Trailer::decode_sync(&mut &out[..]).expect("failed to decode"), item);
+
+        let item = Trailer {
+            version: 1,
+            flags: None,
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1]);
+        assert_eq!(Trailer::decode_sync(&mut &out[..]).expect("failed to decode"), item);
+
+        let decoded = Framed::decode_sync(&mut &[4u8, 5, 6, 7, 8, 9][..]).expect("failed to decode");
+        assert_eq!(
+            decoded,
+            Framed {
+                len: 4,
+                header: 5,
+                extra: Some(6),
+                payload: vec![7, 8],
+                tail: 9,
+            }
+        );
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![4, 5, 6, 7, 8, 9]);
+
+        let decoded = Framed::decode_sync(&mut &[1u8, 5, 9][..]).expect("failed to decode");
+        assert_eq!(
+            decoded,
+            Framed {
+                len: 1,
+                header: 5,
+                extra: None,
+                payload: vec![],
+                tail: 9,
+            }
+        );
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![1, 5, 9]);
+    };
+
+    compile("eof", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_peek() {
+    let asg = load_asg(
+        r#"
+        type Small = container {
+            value: u8 where value < 0x80,
+        };
+        type Big = container {
+            marker: u8 where marker == 0xff,
+            version: u8,
+            value: u32,
+        };
+        type Value = container +tagged_enum {
+            Small: Small {peek() < 0x80},
+            Big: Big {peek() == 0xff && peek(1) == 1},
+            Other: u8[2],
+        };
+        type Record = container {
+            kind: u8,
+            extension: u8 where extension == 0xee {peek() == 0xee},
+            value: Value,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        fn roundtrip(item: &Record, expected: &[u8]) {
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, expected);
+            let mut reader = std::io::BufReader::with_capacity(1, &out[..]);
+            let decoded = Record::decode_sync(&mut reader).expect("failed to decode");
+            assert_eq!(item, &decoded);
+        }
+        roundtrip(&Record {
+            kind: 1,
+            extension: None,
+            value: Value::Small(Small { value: 5 }),
+        }, &[1, 5]);
+        roundtrip(&Record {
+            kind: 1,
+            extension: Some(0xee),
+            value: Value::Big(Big { marker: 0xff, version: 1, value: 7 }),
+        }, &[1, 0xee, 0xff, 1, 0, 0, 0, 7]);
+        roundtrip(&Record {
+            kind: 2,
+            extension: None,
+            value: Value::Other([0xff, 2]),
+        }, &[2, 0xff, 2]);
+        assert!(Record::decode_sync(&mut &[1u8][..]).is_err());
+    };
+
+    compile("peek", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_peek_without_fallback() {
+    let asg = load_asg(
+        r#"
+        type Value = container +tagged_enum {
+            Small: u8 {peek() < 0x80},
+            Big: container { marker: u8, value: u32 } {peek() == 0xff},
+        };
+        type Optional = container {
+            marker: u8 {peek() == 0xff},
+            value: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        for (item, expected) in [
+            (Value::Small(5), vec![5]),
+            (Value::Big { marker: 0xff, value: 7 }, vec![0xff, 0, 0, 0, 7]),
+        ] {
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, expected);
+            let mut reader = std::io::BufReader::with_capacity(1, &out[..]);
+            assert_eq!(Value::decode_sync(&mut reader).expect("failed to decode"), item);
+        }
+        assert!(Value::decode_sync(&mut &[0x90u8][..]).is_err());
+        assert!(Value::Small(0x90).encode_sync(&mut vec![]).is_err());
+        assert!(Value::Big { marker: 1, value: 7 }.encode_sync(&mut vec![]).is_err());
+
+        let item = Optional { marker: Some(0xff), value: 5 };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![0xff, 5]);
+        assert_eq!(Optional::decode_sync(&mut &out[..]).expect("failed to decode"), item);
+        assert!(Optional { marker: Some(5), value: 9 }.encode_sync(&mut vec![]).is_err());
+    };
+
+    compile("peek_without_fallback", &compile_test_program(&asg, test));
+}
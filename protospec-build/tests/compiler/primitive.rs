@@ -81,3 +81,175 @@ fn test_compiler_var_primitive() {
 
     compile("var_primitive", &compile_test_program(&asg, test));
 }
+
+#[test]
+fn test_compiler_var_encodings() {
+    let asg = load_asg(
+        r#"
+    import_ffi v32 as type;
+    import_ffi zigzag32 as type;
+    import_ffi zigzag64 as type;
+    import_ffi sleb32 as type;
+    import_ffi sleb64 as type;
+    import_ffi vlq32 as type;
+    import_ffi quic_var as type;
+    import_ffi compact_size as type;
+
+    type encodings = container {
+        leb: v32,
+        zigzag: zigzag32,
+        zigzag_wide: zigzag64,
+        sleb: sleb32,
+        sleb_wide: sleb64,
+        vlq: vlq32,
+        quic: quic_var,
+        compact: compact_size,
+    };
+    type x_leb = v32;
+    type x_zigzag = zigzag32;
+    type x_sleb = sleb32;
+    type x_vlq = vlq32;
+    type x_quic = quic_var;
+    type x_compact = compact_size;
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        {
+            let item = encodings {
+                leb: -1,
+                zigzag: -64,
+                zigzag_wide: i64::MIN,
+                sleb: -129,
+                sleb_wide: i64::MAX,
+                vlq: 0x3fff,
+                quic: 151288809941952652,
+                compact: 0x1_0000,
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            let decoded = encodings::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(item, decoded);
+
+            let mut out = vec![];
+            x_leb(-1).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0xff, 0xff, 0xff, 0xff, 0x0f]);
+            let mut out = vec![];
+            x_zigzag(-1).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0x01]);
+            let mut out = vec![];
+            x_zigzag(-65).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0x81, 0x01]);
+            let mut out = vec![];
+            x_sleb(-123456).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0xc0, 0xbb, 0x78]);
+            let mut out = vec![];
+            x_vlq(0x4000).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0x81, 0x80, 0x00]);
+            let mut out = vec![];
+            x_quic(15293).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0x7b, 0xbd]);
+            let mut out = vec![];
+            x_compact(0xfd).encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, vec![0xfd, 0xfd, 0x00]);
+
+            assert_eq!(x_sleb::decode_sync(&mut &[0x7f][..]).expect("failed to decode"), x_sleb(-1));
+            assert_eq!(x_quic::decode_sync(&mut &[0x40, 0x25][..]).expect("failed to decode"), x_quic(37));
+            assert_eq!(x_vlq::decode_sync(&mut &[0x87, 0x68][..]).expect("failed to decode"), x_vlq(1000));
+        }
+    };
+
+    compile("var_encodings", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_var_rejects_invalid() {
+    let asg = load_asg(
+        r#"
+    import_ffi v32 as type;
+    import_ffi zigzag32 as type;
+    import_ffi sleb32 as type;
+    import_ffi vlq32 as type;
+    import_ffi compact_size as type;
+
+    type x_leb = v32;
+    type x_zigzag = zigzag32;
+    type x_sleb = sleb32;
+    type x_vlq = vlq32;
+    type x_compact = compact_size;
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        {
+            // overlong
+            assert!(x_leb::decode_sync(&mut &[0x81, 0x00][..]).is_err());
+            assert!(x_zigzag::decode_sync(&mut &[0x80, 0x00][..]).is_err());
+            assert!(x_sleb::decode_sync(&mut &[0xff, 0x7f][..]).is_err());
+            assert_eq!(x_sleb::decode_sync(&mut &[0xc0, 0x00][..]).expect("failed to decode"), x_sleb(64));
+            assert!(x_vlq::decode_sync(&mut &[0x80, 0x01][..]).is_err());
+            assert!(x_compact::decode_sync(&mut &[0xfd, 0x10, 0x00][..]).is_err());
+            // overflowing
+            assert!(x_leb::decode_sync(&mut &[0xff, 0xff, 0xff, 0xff, 0x1f][..]).is_err());
+            assert!(x_leb::decode_sync(&mut &[0xff, 0xff, 0xff, 0xff, 0x8f, 0x01][..]).is_err());
+            assert!(x_sleb::decode_sync(&mut &[0xff, 0xff, 0xff, 0xff, 0x4f][..]).is_err());
+            assert_eq!(x_sleb::decode_sync(&mut &[0x80, 0x80, 0x80, 0x80, 0x78][..]).expect("failed to decode"), x_sleb(i32::MIN));
+            assert!(x_vlq::decode_sync(&mut &[0x90, 0x80, 0x80, 0x80, 0x00][..]).is_err());
+            assert_eq!(x_vlq::decode_sync(&mut &[0x8f, 0xff, 0xff, 0xff, 0x7f][..]).expect("failed to decode"), x_vlq(u32::MAX));
+        }
+    };
+
+    compile("var_rejects_invalid", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_var_length() {
+    let asg = load_asg(
+        r#"
+    import_ffi vlq32 as type;
+    import_ffi zigzag32 as type;
+    import_ffi compact_size as type;
+
+    type lengths = container {
+        count: compact_size +auto,
+        items: u16[count],
+        size: vlq32 +auto,
+        body: container [size] {
+            tag: u8,
+            data: u8[..],
+        },
+        explicit: zigzag32,
+        rest: u8[explicit :> u64],
+    };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        {
+            let item = lengths {
+                count: 0,
+                items: vec![1, 2, 3],
+                size: 0,
+                tag: 4,
+                data: vec![5; 200],
+                explicit: 2,
+                rest: vec![6, 7],
+            };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(&out[..1], &[3]);
+            assert_eq!(&out[7..9], &[0x81, 0x49]);
+            let decoded = lengths::decode_sync(&mut &out[..]).expect("failed to decode");
+            assert_eq!(decoded.count, 3);
+            assert_eq!(decoded.size, 201);
+            assert_eq!(decoded.items, item.items);
+            assert_eq!(decoded.data, item.data);
+            assert_eq!(decoded.rest, item.rest);
+        }
+    };
+
+    compile("var_length", &compile_test_program(&asg, test));
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+#[test]
+fn test_compiler_runtime() {
+    let asg = load_asg(
+        r#"
+        import_ffi v32 as type;
+        import_ffi zigzag64 as type;
+        import_ffi utf8 as type;
+
+        type Header(version: u8) = container {
+            kind: u8 where kind < 10,
+            extra: u16 {version > 1},
+        };
+
+        type Frame = container {
+            len: u8 +auto,
+            data: u8[len],
+        };
+
+        type Record = container {
+            id: v32,
+            delta: zigzag64,
+            name: utf8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let options = CompileOptions {
+        runtime: Some("crate::protospec".to_string()),
+        ..Default::default()
+    };
+
+    let test = quote! {
+        use protospec::{Decode, Encode};
+
+        fn roundtrip<T>(item: &T, args: impl Fn() -> <T as Decode>::Args)
+        where
+            T: Decode + Encode<Args = <T as Decode>::Args> + PartialEq + std::fmt::Debug,
+        {
+            let mut out = vec![];
+            Encode::encode_sync(item, &mut out, args()).expect("failed to encode");
+            assert_eq!(Encode::encoded_len(item, args()).expect("failed to measure"), out.len());
+            let decoded = <T as Decode>::decode_sync(&mut &out[..], args()).expect("failed to decode");
+            assert_eq!(&decoded, item);
+        }
+
+        fn read_frames<T: Decode<Args = ()>>(mut bytes: &[u8]) -> protospec::Result<Vec<T>> {
+            let mut out = vec![];
+            while !bytes.is_empty() {
+                out.push(T::decode_sync(&mut bytes, ())?);
+            }
+            Ok(out)
+        }
+
+        roundtrip(&Header { kind: 1, extra: Some(7) }, || (2,));
+        roundtrip(&Header { kind: 1, extra: None }, || (1,));
+        roundtrip(&Frame { len: 2, data: vec![1, 2] }, || ());
+
+        let frames = read_frames::<Frame>(&[1, 9, 0, 2, 3, 4][..]).expect("failed to decode");
+        assert_eq!(frames, vec![
+            Frame { len: 1, data: vec![9] },
+            Frame { len: 0, data: vec![] },
+            Frame { len: 2, data: vec![3, 4] },
+        ]);
+
+        let error = <Header as Decode>::decode_sync(&mut &[12u8][..], (1,)).unwrap_err();
+        assert!(error.downcast_ref::<protospec::DecodeError>().is_some());
+
+        let record = Record { id: 300, delta: -3, name: "abc".to_string() };
+        roundtrip(&record, || ());
+        let mut out = vec![];
+        record.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![0xac, 0x02, 5, b'a', b'b', b'c', 0]);
+        let error = Record::decode_sync(&mut &[0xff, 0xff, 0xff, 0xff, 0x7f, 0, 0][..]).unwrap_err();
+        assert_eq!(error.to_string(), "LEB128 varint overflows i32");
+
+        let mut out = vec![];
+        protospec::varint::write_leb128(&mut out, 300).expect("failed to encode");
+        assert_eq!(out.len(), protospec::varint::leb128_len(300));
+        assert_eq!(protospec::varint::read_leb128(&mut &out[..]).expect("failed to decode"), 300);
+        assert_eq!(protospec::varint::zigzag_decode(protospec::varint::zigzag_encode(-3)), -3);
+    };
+
+    // the prelude varint and string types are coded by the runtime's codecs rather than inline ones
+    let compiled = compiler::compile_program(&asg, &options).to_string();
+    assert!(compiled.contains("pub (super) use crate :: protospec :: { string , varint }"));
+    assert!(!compiled.contains("fn read_leb128"));
+    assert!(compiled.contains("varint :: read_leb128"));
+    assert!(compiled.contains("string :: write_cstring"));
+
+    let runtime = concat!(env!("CARGO_MANIFEST_DIR"), "/../protospec/src/lib.rs");
+    compile(
+        "runtime",
+        &format!(
+            "#[path = {:?}] mod protospec;\n{}",
+            runtime,
+            compile_test_program_with_options(&asg, test, &options)
+        ),
+    );
+}
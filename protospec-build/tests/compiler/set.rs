@@ -0,0 +1,91 @@
+use super::*;
+
+#[test]
+fn test_compiler_set() {
+    let asg = load_asg(
+        r#"
+        type Name = container {
+            len: u8 +auto,
+            name: u8[len],
+        };
+        type Extensions = container set(u8, u16) {
+            1 => name: Name,
+            16 => alpn: u8[..],
+            43 => version: u16 where version >= 0x300,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let bytes = [16, 0, 2, 104, 50, 99, 0, 1, 7, 1, 0, 3, 2, 97, 98];
+        let decoded = Extensions::decode_sync(&mut &bytes[..]).expect("failed to decode");
+        assert_eq!(decoded, Extensions {
+            name: Some(Name { len: 2, name: vec![97, 98] }),
+            alpn: Some(vec![104, 50]),
+            version: None,
+            unknown: vec![(99, vec![7])],
+        });
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, [1, 0, 3, 2, 97, 98, 16, 0, 2, 104, 50, 99, 0, 1, 7]);
+
+        Extensions::decode_sync(&mut &[16, 0, 0, 16, 0, 0][..]).expect_err("decoded duplicate member");
+        Extensions::decode_sync(&mut &[43, 0, 2, 0, 1][..]).expect_err("decoded constraint violation");
+        Extensions::decode_sync(&mut &[43, 0, 3, 3, 4, 5][..]).expect_err("decoded record with bytes left over");
+        let decoded = Extensions::decode_sync(&mut &[43, 0, 2, 3, 4][..]).expect("failed to decode");
+        assert_eq!(decoded.version, Some(0x304));
+    };
+
+    compile("set", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_set_preserve_order() {
+    let asg = load_asg(
+        r#"
+        type Extensions = container +preserve_order set(u16le, u8) {
+            1 => a: u32,
+            2 => b: u8,
+        };
+        type Message = container {
+            len: u16 +auto,
+            body: container [len] {
+                extensions: Extensions,
+            },
+            trailer: u8,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        fn roundtrip(bytes: &[u8]) -> Message {
+            let decoded = Message::decode_sync(&mut &bytes[..]).expect("failed to decode");
+            let mut out = vec![];
+            decoded.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, bytes);
+            decoded
+        }
+        let decoded = roundtrip(&[0, 14, 2, 0, 1, 5, 9, 0, 0, 1, 0, 4, 0, 0, 0, 6, 0xff]);
+        assert_eq!(decoded.extensions, Extensions {
+            a: Some(6),
+            b: Some(5),
+            unknown: vec![(9, vec![])],
+            order: vec![2, 9, 1],
+        });
+        assert_eq!(decoded.trailer, 0xff);
+        roundtrip(&[0, 0, 0xff]);
+
+        let mut extensions = decoded.extensions;
+        extensions.order = vec![];
+        let mut out = vec![];
+        extensions.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, [1, 0, 4, 0, 0, 0, 6, 2, 0, 1, 5, 9, 0, 0]);
+
+        extensions.unknown = vec![(9, vec![0; 256])];
+        extensions.encode_sync(&mut vec![]).expect_err("encoded record longer than its length type");
+    };
+
+    compile("set_preserve_order", &compile_test_program(&asg, test));
+}
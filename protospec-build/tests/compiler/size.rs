@@ -0,0 +1,177 @@
+use super::*;
+
+#[test]
+fn test_compiler_size() {
+    let asg = load_asg(
+        r#"
+        type Kind = enum u16 {
+            Data = 1,
+            Ack,
+        };
+
+        type Header = container {
+            version: u8,
+            kind: Kind,
+            length: u32,
+        };
+
+        type Optional = container {
+            flag: u8,
+            extra: u32 {flag == 1},
+            tail: u8[3],
+        };
+
+        type Blob = container {
+            len: u8,
+            data: u8[len],
+        };
+
+        type Text = container {
+            data: u8[..],
+        };
+
+        type Record = container {
+            header: Header,
+            .pad: 16 - sizeof(Header),
+            body: u8[sizeof(Header)],
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        assert_eq!(Kind::ENCODED_SIZE, 2);
+        assert_eq!(Header::ENCODED_SIZE, 7);
+        assert_eq!(Optional::MAX_ENCODED_SIZE, 8);
+        assert_eq!(Blob::MAX_ENCODED_SIZE, 256);
+        assert_eq!(Record::ENCODED_SIZE, 23);
+
+        let item = Record {
+            header: Header {
+                version: 1,
+                kind: Kind::Ack,
+                length: 7,
+            },
+            body: [1, 2, 3, 4, 5, 6, 7],
+        };
+        let mut out = Vec::with_capacity(Record::ENCODED_SIZE);
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out.len(), Record::ENCODED_SIZE);
+        let decoded = Record::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+    };
+
+    compile("size", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_encoded_len() {
+    let asg = load_asg(
+        r#"
+        import_ffi v32 as type;
+        import_ffi zigzag64 as type;
+        import_ffi sleb32 as type;
+        import_ffi vlq32 as type;
+        import_ffi quic_var as type;
+        import_ffi compact_size as type;
+        import_ffi utf8 as type;
+
+        type Varints = container {
+            leb: v32,
+            zigzag: zigzag64,
+            sleb: sleb32,
+            vlq: vlq32,
+            quic: quic_var,
+            compact: compact_size,
+        };
+
+        type Entry = container {
+            name: utf8,
+            flags: u8,
+            extra: v32 {flags == 1},
+            label: u8[.."\0\0"],
+            values: Varints[2],
+        };
+
+        type Payload(t: u8) = container match t {
+            1 => Byte: i8,
+            2 => Text: container {
+                len: u8,
+                text: utf8(len),
+            },
+            _ => Unknown: u8[..] +unknown,
+        };
+
+        type Message = container {
+            count: u16,
+            entries: Entry[count],
+            kind: u8,
+            payload: Payload(kind),
+        };
+
+        type Auto = container {
+            kind: u8 +auto,
+            .align(4),
+            payload: Payload(kind),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        fn varints(value: i32) -> Varints {
+            Varints {
+                leb: value,
+                zigzag: value as i64 * 1000,
+                sleb: -value,
+                vlq: value as u32,
+                quic: value.unsigned_abs() as u64 * 100,
+                compact: value.unsigned_abs() as u64 * 10000,
+            }
+        }
+
+        let message = Message {
+            count: 2,
+            entries: vec![
+                Entry {
+                    name: "first".to_string(),
+                    flags: 1,
+                    extra: Some(300),
+                    label: vec![1, 2, 3],
+                    values: [varints(0), varints(-1)],
+                },
+                Entry {
+                    name: String::new(),
+                    flags: 0,
+                    extra: None,
+                    label: vec![],
+                    values: [varints(70000), varints(i32::MAX)],
+                },
+            ],
+            kind: 2,
+            payload: Payload::Text {
+                len: 5,
+                text: "hello".to_string(),
+            },
+        };
+        let mut out = vec![];
+        message.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(message.encoded_len().expect("failed to measure"), out.len());
+
+        for payload in vec![
+            Payload::Byte(-3),
+            Payload::Unknown { tag: 9, raw: vec![4, 5, 6] },
+        ] {
+            let mut out = vec![];
+            payload.encode_sync(&mut out, 0).expect("failed to encode");
+            assert_eq!(payload.encoded_len(0).expect("failed to measure"), out.len());
+
+            let item = Auto { kind: 0, payload };
+            let mut out = vec![];
+            item.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(item.encoded_len().expect("failed to measure"), out.len());
+        }
+    };
+
+    compile("encoded_len", &compile_test_program(&asg, test));
+}
@@ -171,3 +171,115 @@ fn test_compiler_tagged_nested_enum_struct() {
         &compile_test_program(&asg, test),
     );
 }
+
+#[test]
+fn test_compiler_match() {
+    let asg = load_asg(
+        r#"
+        type Outer = container {
+            tag: Tag +auto,
+            payload: Payload(tag),
+        };
+        type Tag = enum u8 {
+            Byte = 1,
+            Short,
+            Long = 5,
+            Other = default,
+        };
+        type Payload(t: Tag) = container match t {
+            Tag::Byte => Byte: i8,
+            Tag::Short | Tag::Long => Short: i16,
+            _ => Unknown: u8[..],
+        };
+        type Ranged(t: u8) = container match t {
+            0..10 => Small: u8,
+            10..=20 | 30 => Medium: u16,
+            _ => Large: u32,
+        };
+        type Wrapped = container {
+            kind: u8 +auto,
+            value: Ranged(kind),
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = Outer {
+            tag: Tag::Other(0),
+            payload: Payload::Short(7),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![2, 0, 7]);
+        let decoded = Outer::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, Outer { tag: Tag::Short, payload: Payload::Short(7) });
+
+        let decoded = Outer::decode_sync(&mut &[5u8, 0, 9][..]).expect("failed to decode");
+        assert_eq!(decoded, Outer { tag: Tag::Long, payload: Payload::Short(9) });
+        let decoded = Outer::decode_sync(&mut &[9u8, 1, 2][..]).expect("failed to decode");
+        assert_eq!(decoded.payload, Payload::Unknown(vec![1, 2]));
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).err().expect("derived the tag of an unknown variant");
+
+        let item = Wrapped {
+            kind: 0,
+            value: Ranged::Medium(3),
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![10, 0, 3]);
+        let decoded = Wrapped::decode_sync(&mut &[30u8, 0, 4][..]).expect("failed to decode");
+        assert_eq!(decoded.value, Ranged::Medium(4));
+        let decoded = Wrapped::decode_sync(&mut &[21u8, 0, 0, 0, 4][..]).expect("failed to decode");
+        assert_eq!(decoded.value, Ranged::Large(4));
+    };
+
+    compile("match", &compile_test_program(&asg, test));
+}
+
+#[test]
+fn test_compiler_match_unknown() {
+    let asg = load_asg(
+        r#"
+        type Message = container {
+            kind: u8 +auto,
+            len: u16 +auto,
+            body: container [len] {
+                payload: Payload(kind),
+            },
+        };
+        type Payload(t: u8) = container match t {
+            1 => Byte: i8,
+            2 => Short: i16,
+            _ => Unknown: u8[..] +unknown,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        fn roundtrip(bytes: &[u8]) -> Message {
+            let decoded = Message::decode_sync(&mut &bytes[..]).expect("failed to decode");
+            let mut out = vec![];
+            decoded.encode_sync(&mut out).expect("failed to encode");
+            assert_eq!(out, bytes);
+            decoded
+        }
+        let decoded = roundtrip(&[7, 0, 3, 4, 5, 6]);
+        assert_eq!(decoded.payload, Payload::Unknown { tag: 7, raw: vec![4, 5, 6] });
+        let decoded = roundtrip(&[2, 0, 2, 1, 2]);
+        assert_eq!(decoded.payload, Payload::Short(0x102));
+        let decoded = roundtrip(&[9, 0, 0]);
+        assert_eq!(decoded.payload, Payload::Unknown { tag: 9, raw: vec![] });
+
+        let known = Message {
+            kind: 0,
+            len: 0,
+            payload: Payload::Unknown { tag: 1, raw: vec![1, 2] },
+        };
+        assert!(known.encode_sync(&mut vec![]).is_err());
+    };
+
+    compile("match_unknown", &compile_test_program(&asg, test));
+}
@@ -154,3 +154,83 @@ fn test_compiler_transform_conditional_arg() {
         &compile_test_program(&asg, test),
     );
 }
+
+/// A stand-in for the `snap` crate, "compressing" by copying bytes through
+const SNAP_STUB: &str = r#"
+pub mod read {
+    pub struct FrameDecoder<R>(R);
+
+    impl<R> FrameDecoder<R> {
+        pub fn new(inner: R) -> Self {
+            FrameDecoder(inner)
+        }
+    }
+
+    impl<R: std::io::Read> std::io::Read for FrameDecoder<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+}
+
+pub mod write {
+    pub struct FrameEncoder<W>(W);
+
+    impl<W> FrameEncoder<W> {
+        pub fn new(inner: W) -> Self {
+            FrameEncoder(inner)
+        }
+    }
+
+    impl<W: std::io::Write> std::io::Write for FrameEncoder<W> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.0.flush()
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_compiler_transform_expected_size() {
+    let asg = load_asg(
+        r#"
+    import_ffi snappy as transform;
+
+    type test = container {
+        len: u32,
+        data: u8[..] -> snappy(0, len),
+    };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        let item = test {
+            len: 4,
+            data: vec![1, 2, 3, 4],
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        let decoded = test::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(item, decoded);
+
+        let item = test {
+            len: 3,
+            data: vec![1, 2, 3, 4],
+        };
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        let error = test::decode_sync(&mut &out[..]).expect_err("decoded past the expected size");
+        assert!(error.to_string().contains("exceeds expected size"));
+    };
+
+    compile_with_crates(
+        "transform_expected_size",
+        &compile_test_program(&asg, test),
+        &[("snap", SNAP_STUB)],
+    );
+}
@@ -0,0 +1,115 @@
+use super::*;
+
+#[test]
+fn test_compiler_type_map() {
+    let asg = load_asg(
+        r#"
+        import_ffi utf8 as type;
+
+        type Body(kind: u8) = container match kind {
+            1 => Data: container {
+                len: u8 +auto,
+                payload: u8[len],
+                @rust_type("std::net::Ipv4Addr") addr: u32,
+            },
+            _ => Empty: u8,
+        };
+
+        type Packet = container {
+            name: utf8,
+            flags: u8,
+            @rust_type("std::net::Ipv4Addr") addr: u32 where addr > 0,
+            @rust_type("std::net::Ipv4Addr") extra: u32 {flags == 1},
+            note: utf8 {flags == 1},
+            @rust_type("std::sync::Arc<[u8]>") tag: u8[2] {flags == 1},
+            kind: u8 +auto,
+            body: Body(kind),
+            rest: u8[..],
+        };
+
+        type Extensions = container set(u8, u16) {
+            16 => alpn: u8[..],
+            43 => version: u16,
+        };
+    "#,
+    )
+    .unwrap();
+
+    let test = quote! {
+        use std::net::Ipv4Addr;
+
+        let item = Packet {
+            name: std::borrow::Cow::Borrowed("ab"),
+            flags: 1,
+            addr: Ipv4Addr::new(10, 0, 0, 2),
+            extra: Some(Ipv4Addr::new(10, 0, 0, 3)),
+            note: Some(std::borrow::Cow::Borrowed("c")),
+            tag: Some(std::sync::Arc::from([8, 9])),
+            kind: 1,
+            body: Body::Data {
+                len: 2,
+                payload: vec![4, 5].into_boxed_slice(),
+                addr: Ipv4Addr::LOCALHOST,
+            },
+            rest: vec![7].into_boxed_slice(),
+        };
+        item.validate().expect("failed to validate");
+        let mut out = vec![];
+        item.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, vec![b'a', b'b', 0, 1, 10, 0, 0, 2, 10, 0, 0, 3, b'c', 0, 8, 9, 1, 2, 4, 5, 127, 0, 0, 1, 7]);
+        assert_eq!(item.encoded_len().unwrap(), out.len());
+        let decoded = Packet::decode_sync(&mut &out[..]).expect("failed to decode");
+        assert_eq!(decoded, item);
+        assert!(Packet { addr: Ipv4Addr::UNSPECIFIED, ..item }.validate().is_err());
+
+        let bytes = [16, 0, 2, 104, 50];
+        let decoded = Extensions::decode_sync(&mut &bytes[..]).expect("failed to decode");
+        assert_eq!(decoded.alpn.as_deref(), Some(&[104, 50][..]));
+        let mut out = vec![];
+        decoded.encode_sync(&mut out).expect("failed to encode");
+        assert_eq!(out, bytes);
+    };
+
+    let derives = vec![
+        "PartialEq".to_string(),
+        "Debug".to_string(),
+        "Clone".to_string(),
+    ];
+    let options = CompileOptions {
+        enum_derives: derives.clone(),
+        struct_derives: derives,
+        type_map: vec![
+            (
+                "String".to_string(),
+                "std::borrow::Cow<'static, str>".to_string(),
+            ),
+            ("Vec<u8>".to_string(), "Box<[u8]>".to_string()),
+        ],
+        runtime: None,
+        ..Default::default()
+    };
+    compile(
+        "type_map",
+        &compile_test_program_with_options(&asg, test, &options),
+    );
+}
+
+#[test]
+fn test_compiler_invalid_type_map() {
+    let asg = load_asg(
+        r#"
+        type Packet = container {
+            payload: u8[..],
+        };
+    "#,
+    )
+    .unwrap();
+
+    let options = CompileOptions {
+        type_map: vec![("Vec<u8>".to_string(), "Box<[u8]".to_string())],
+        runtime: None,
+        ..Default::default()
+    };
+    let compiled = compiler::compile_program(&asg, &options).to_string();
+    assert!(compiled.contains("compile_error"));
+}
@@ -168,3 +168,26 @@ fn test_transform_conditional_transform_empty() {
     .err()
     .unwrap();
 }
+
+#[test]
+fn test_attribute_not_on_type() {
+    parse(
+        r#"
+    @derive(Hash) const test: u32 = 1;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_attribute_value_not_literal() {
+    parse(
+        r#"
+    @rust(serde(rename = other))
+    type test = u32;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
@@ -370,3 +370,17 @@ fn test_transform_conditional_transform_array() {
     )
     .unwrap();
 }
+
+#[test]
+fn test_attributes() {
+    parse(
+        r#"
+    @derive(Hash, Eq) @rust_name("Header") @rust(serde(rename_all = "camelCase"), repr(u8))
+    type test = container {
+        @rust(doc = "The length", serde(default)) len: u32,
+        @other_backend(1, true, kind = "x") data: u8[len],
+    };
+    "#,
+    )
+    .unwrap();
+}
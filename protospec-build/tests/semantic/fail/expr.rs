@@ -142,3 +142,50 @@ fn test_ternary() {
     .err()
     .unwrap();
 }
+
+#[test]
+fn test_until_missing_member() {
+    load_asg(
+        r#"
+    type Entry = container {
+        kind: u8,
+    };
+    type Test = container {
+        entries: Entry[.. until |entry| entry.value == 0],
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_sizeof_unsized() {
+    load_asg(
+        r#"
+    type Blob = container {
+        len: u8,
+        data: u8[len],
+    };
+    type Test = container {
+        data: u8[sizeof(Blob)],
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_sizeof_not_type() {
+    load_asg(
+        r#"
+    type Test = container {
+        len: u8,
+        data: u8[sizeof(len)],
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
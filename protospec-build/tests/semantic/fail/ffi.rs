@@ -69,3 +69,39 @@ fn test_ffi_type_transform() {
     .err()
     .unwrap();
 }
+
+#[test]
+fn test_ffi_checksum_container() {
+    load_asg(
+        r#"
+    import_ffi crc32 as function;
+
+    type test = container {
+        header: container {
+            kind: u8,
+        },
+        crc: u32 = crc32(header),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_ffi_checksum_type_arguments() {
+    load_asg(
+        r#"
+    import_ffi crc32 as function;
+
+    type body(len: u8) = u8[len];
+
+    type test = container {
+        data: body(4),
+        crc: u32 = crc32(data),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
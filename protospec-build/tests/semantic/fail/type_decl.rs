@@ -102,3 +102,624 @@ fn test_conditional_inverted() {
     .err()
     .unwrap();
 }
+
+#[test]
+fn test_fixed_toplevel() {
+    load_asg(
+        r#"
+    type test = u32 == 5;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_fixed_calculated() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u32 == 5 = 6,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_fixed_type_mismatch() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u32 == "RIFF",
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_fixed_field_reference() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u32,
+        b: u32 == a,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_constraint_toplevel() {
+    load_asg(
+        r#"
+    type test = u32 where test > 5;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_constraint_not_bool() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u32 where a + 1,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_constraint_conditional_reference() {
+    load_asg(
+        r#"
+    type test = container {
+        flags: u8,
+        extra: u8 {flags == 1},
+        b: u8 where b < extra,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_constraint_later_reference() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u8 where a < b,
+        b: u8,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_constraint_conditional_container_reference() {
+    load_asg(
+        r#"
+    type test = container {
+        flags: u8,
+        body: container {
+            extra: u8,
+        } {flags == 1},
+        b: u8 where b < extra,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_position_outside_container() {
+    load_asg(
+        r#"
+    type test = u8[remaining()];
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_remaining_unconstrained() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u8 = remaining() :> u8,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_eof_in_constraint() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u8 where eof(),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_peek_offset_not_literal() {
+    load_asg(
+        r#"
+    type test = container {
+        x: u8,
+        a: u8 {peek(x) == 1},
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_peek_outside_condition() {
+    load_asg(
+        r#"
+    type test = container {
+        a: u8 = peek(0),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_position_after_unsized_auto() {
+    load_asg(
+        r#"
+    import_ffi v32 as type;
+
+    type test = container {
+        len: v32 +auto,
+        .align(4),
+        body: container [len] {
+            a: u8,
+        },
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_addr_not_pointer() {
+    load_asg(
+        r#"
+    type test = container {
+        x: u8,
+        off: u64 = addr(x),
+        data: u8[2] @ (off),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_addr_in_expression() {
+    load_asg(
+        r#"
+    type test = container {
+        off: u64 = addr(data) + 1,
+        data: u8[2] @ (off - 1),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_pointer_in_length_container() {
+    load_asg(
+        r#"
+    type test = container {
+        len: u8,
+        offset: u8,
+        body: container [len] {
+            a: u32 @ (offset),
+        },
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_seekable_in_transformed_stream() {
+    load_asg(
+        r#"
+    import_ffi gzip as transform;
+    type inner = container {
+        offset: u8,
+        a: u32 @ (offset),
+    };
+    type test = container {
+        a: inner -> gzip,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_overlapping_terminators() {
+    load_asg(
+        r#"
+    type Test = container {
+        line: u8[.. "\r", "\r\n"],
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_terminator_left_by_type() {
+    load_asg(
+        r#"
+    type Test = u8[.. "\n"; leave];
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_overlapping_match_arms() {
+    load_asg(
+        r#"
+    type Test(t: u8) = container match t {
+        1 => A: u8,
+        0..=3 => B: u16,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_match_pattern_not_constant() {
+    load_asg(
+        r#"
+    type Test(t: u8, u: u8) = container match t {
+        u => A: u8,
+        _ => B: u16,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_auto_tag_of_match_expression() {
+    load_asg(
+        r#"
+    type Test(t: u8) = container match t & 0x0f {
+        1 => A: u8,
+        _ => B: u16,
+    };
+    type Message = container {
+        t: u8 +auto,
+        payload: Test(t),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_auto_tag_through_expression() {
+    load_asg(
+        r#"
+    type Test(t: u8) = container match t {
+        1 => A: u8,
+        _ => B: u16,
+    };
+    type Message = container {
+        t: u8 +auto,
+        payload: Test(t + 1),
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_unknown_variant_with_value() {
+    load_asg(
+        r#"
+    type Test(t: u8) = container match t {
+        1 => A: u8,
+        2 | _ => B: u8[..] +unknown,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_unknown_variant_of_conditions() {
+    load_asg(
+        r#"
+    type Test(t: u8) = container +tagged_enum {
+        A: u8 {t == 1},
+        B: u8[..] +unknown,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_duplicate_set_tag() {
+    load_asg(
+        r#"
+    type Test = container set(u8, u8) {
+        1 => a: u8,
+        0 + 1 => b: u16,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_conditional_set_member() {
+    load_asg(
+        r#"
+    type Test = container set(u8, u8) {
+        1 => a: u8,
+        2 => b: u16 {true},
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_map_key() {
+    load_asg(
+        r#"
+    type Test = container {
+        entries: Entry[..] +map(missing),
+    };
+    type Entry = container {
+        id: u8,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_infinite_size_type() {
+    load_asg(
+        r#"
+    type A = container {
+        value: u8,
+        b: B,
+    };
+    type B = container {
+        a: A,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_infinite_size_fixed_array() {
+    load_asg(
+        r#"
+    type T = container {
+        value: u8,
+        children: T[2],
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_rust_name() {
+    load_asg(
+        r#"
+    @rust_name("not an identifier")
+    type test = u32;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_duplicate_field_rust_name() {
+    load_asg(
+        r#"
+    type test = container {
+        @rust_name("length") len: u32,
+        length: u32,
+    };
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(false)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_duplicate_nested_field_name() {
+    load_asg(
+        r#"
+    type test = container {
+        east: u8[..],
+        west: container {
+            east: u32,
+        },
+    };
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(false)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_duplicate_variant_rust_name() {
+    load_asg(
+        r#"
+    type test = container +tagged_enum {
+        @rust_name("B") a: u8 {false},
+        B: u16,
+    };
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(false)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_duplicate_type_rust_name() {
+    load_asg(
+        r#"
+    @rust_name("other")
+    type test = u32;
+    type other = u16;
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(false)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_rust_case_name_collision() {
+    let program = load_asg(
+        r#"
+    type test = container {
+        fooBar: u8,
+        foo_bar: u8,
+    };
+    "#,
+    )
+    .unwrap();
+    program.check_rust_names(false).unwrap();
+    program.check_rust_names(true).err().unwrap();
+}
+
+#[test]
+fn test_rust_case_variable_collision() {
+    load_asg(
+        r#"
+    type test(fooBar: u8) = container {
+        foo_bar: u8[fooBar],
+    };
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(true)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_escaped_name_collision() {
+    load_asg(
+        r#"
+    type test = container {
+        Self: u8,
+        Self_: u8,
+    };
+    "#,
+    )
+    .unwrap()
+    .check_rust_names(false)
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_rust_type() {
+    load_asg(
+        r#"
+    type test = container {
+        @rust_type("not a type") len: u32,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_variant_rust_type() {
+    load_asg(
+        r#"
+    type test(kind: u8) = container match kind {
+        @rust_type("std::net::Ipv4Addr") 1 => Addr: u32,
+        _ => Other: u8,
+    };
+    "#,
+    )
+    .err()
+    .unwrap();
+}
+
+#[test]
+fn test_invalid_derive() {
+    load_asg(
+        r#"
+    @derive("Hash")
+    type test = u32;
+    "#,
+    )
+    .err()
+    .unwrap();
+}
@@ -28,3 +28,42 @@ fn test_ffi_transform() {
     )
     .unwrap();
 }
+
+#[test]
+fn test_ffi_digest() {
+    load_asg(
+        r#"
+    import_ffi sha256 as function;
+
+    type x = container {
+        data: u8[..],
+        digest: u8[32] = sha256(data),
+    };
+    "#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_ffi_compression() {
+    load_asg(
+        r#"
+    import_ffi zlib as transform;
+    import_ffi deflate as transform;
+    import_ffi zstd as transform;
+    import_ffi lz4 as transform;
+    import_ffi brotli as transform;
+    import_ffi snappy as transform;
+
+    type x = container {
+        len: u32 +auto,
+        data: container [len] {
+            a: u8[..] -> zlib(9, 4096),
+            b: u8[..] -> zstd(-3) -> deflate -> lz4,
+            c: u8[..] -> brotli(5) -> snappy,
+        },
+    };
+    "#,
+    )
+    .unwrap();
+}
@@ -55,7 +55,7 @@ fn test_nested_container_scoping() {
     type test = container {
         east: u8[..],
         west: container {
-            east: u32,
+            @rust_name("west_east") east: u32,
             west: u8[east]
         },
     };
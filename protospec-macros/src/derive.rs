@@ -0,0 +1,371 @@
+use crate::*;
+use proc_macro2::Span;
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::{
+    Attribute, Data, DataEnum, DataStruct, DeriveInput, Fields, GenericArgument, Item,
+    PathArguments, Type,
+};
+
+const SCALARS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128",
+];
+
+/// Options of `#[ps(..)]` attributes
+#[derive(Default)]
+struct Attributes {
+    len: Option<String>,
+    cond: Option<String>,
+    little_endian: Option<bool>,
+}
+
+impl Attributes {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut out = Attributes::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("ps")) {
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(syn::Error::new_spanned(meta, "expected `#[ps(..)]`")),
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("le") => {
+                        out.little_endian = Some(true)
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident("be") => {
+                        out.little_endian = Some(false)
+                    }
+                    NestedMeta::Meta(Meta::NameValue(value))
+                        if value.path.is_ident("len") || value.path.is_ident("cond") =>
+                    {
+                        let expr = match &value.lit {
+                            Lit::Str(lit) => lit.value(),
+                            lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                        };
+                        if value.path.is_ident("len") {
+                            out.len = Some(expr);
+                        } else {
+                            out.cond = Some(expr);
+                        }
+                    }
+                    nested => {
+                        return Err(syn::Error::new_spanned(
+                            nested,
+                            "expected `len = \"..\"`, `cond = \"..\"`, `le` or `be`",
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The spec of a derived type
+struct DeriveSpec {
+    runtime: TokenStream2,
+    /// Imports of the Rust types referenced, which are foreign types to the spec
+    rust_imports: Vec<(String, Span)>,
+    rust_types: HashMap<String, RustType>,
+    uses_utf8: bool,
+    lines: Vec<(String, Span)>,
+}
+
+impl DeriveSpec {
+    fn scalar(name: &str, little_endian: bool) -> String {
+        if little_endian && name != "u8" && name != "i8" {
+            format!("{}le", name)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Spec of a field type, with its array length or string length if it is one
+    fn type_(
+        &mut self,
+        type_: &Type,
+        little_endian: bool,
+        len: Option<&str>,
+    ) -> syn::Result<String> {
+        let path = match type_ {
+            Type::Array(_) if len.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    type_,
+                    "`len` only applies to `Vec` and `String` fields",
+                ))
+            }
+            Type::Array(array) => {
+                let element = self.type_(&array.elem, little_endian, None)?;
+                return Ok(format!("{}[{}]", element, array.len.to_token_stream()));
+            }
+            Type::Path(path) if path.qself.is_none() => &path.path,
+            type_ => return Err(syn::Error::new_spanned(type_, "unsupported field type")),
+        };
+        let segment = path.segments.last().unwrap();
+        let name = segment.ident.to_string();
+        let arguments = match &segment.arguments {
+            PathArguments::None => vec![],
+            PathArguments::AngleBracketed(arguments) => arguments.args.iter().collect(),
+            PathArguments::Parenthesized(_) => {
+                return Err(syn::Error::new_spanned(type_, "unsupported field type"))
+            }
+        };
+        match (&*name, &arguments[..]) {
+            ("Vec", [GenericArgument::Type(element)]) => {
+                let element = self.type_(element, little_endian, None)?;
+                Ok(format!("{}[{}]", element, len.unwrap_or("..")))
+            }
+            ("String", []) => {
+                self.uses_utf8 = true;
+                Ok(format!("utf8({})", len.unwrap_or_default()))
+            }
+            _ if len.is_some() => Err(syn::Error::new_spanned(
+                type_,
+                "`len` only applies to `Vec` and `String` fields",
+            )),
+            (name, []) if SCALARS.contains(&name) => Ok(Self::scalar(name, little_endian)),
+            ("f32", []) | ("f64", []) if little_endian => Err(syn::Error::new_spanned(
+                type_,
+                "floats are always big endian, mark the field `#[ps(be)]`",
+            )),
+            ("f32", []) | ("f64", []) | ("bool", []) => Ok(name),
+            (_, []) => {
+                if !self.rust_types.contains_key(&name) {
+                    self.rust_imports.push((
+                        format!("import_ffi {} as type;", name),
+                        segment.ident.span(),
+                    ));
+                    self.rust_types.insert(
+                        name.clone(),
+                        RustType {
+                            path: type_.to_token_stream(),
+                            runtime: self.runtime.clone(),
+                        },
+                    );
+                }
+                Ok(name)
+            }
+            _ => Err(syn::Error::new_spanned(type_, "unsupported field type")),
+        }
+    }
+
+    fn container(&mut self, name: &str, data: &DataStruct, attrs: &Attributes) -> syn::Result<()> {
+        let fields = match &data.fields {
+            Fields::Named(fields) => fields,
+            fields => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "expected a struct with named fields",
+                ))
+            }
+        };
+        self.lines
+            .push((format!("type {} = container {{", name), Span::call_site()));
+        for field in fields.named.iter() {
+            let field_attrs = Attributes::parse(&field.attrs)?;
+            let little_endian = field_attrs
+                .little_endian
+                .or(attrs.little_endian)
+                .unwrap_or_default();
+            let (type_, optional) = match &field.ty {
+                Type::Path(path) if path.qself.is_none() => {
+                    let segment = path.path.segments.last().unwrap();
+                    match &segment.arguments {
+                        PathArguments::AngleBracketed(arguments) if segment.ident == "Option" => {
+                            match arguments.args.first() {
+                                Some(GenericArgument::Type(type_)) => (type_, true),
+                                _ => (&field.ty, false),
+                            }
+                        }
+                        _ => (&field.ty, false),
+                    }
+                }
+                type_ => (type_, false),
+            };
+            let condition = match (&field_attrs.cond, optional) {
+                (Some(cond), true) => format!(" {{{}}}", cond),
+                (None, false) => String::new(),
+                (None, true) => {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "`Option` fields need a `#[ps(cond = \"..\")]` condition",
+                    ))
+                }
+                (Some(_), false) => {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "conditional fields must be `Option`s",
+                    ))
+                }
+            };
+            let type_ = self.type_(type_, little_endian, field_attrs.len.as_deref())?;
+            let ident = field.ident.as_ref().unwrap();
+            self.lines.push((
+                format!("    {}: {}{},", ident, type_, condition),
+                ident.span(),
+            ));
+        }
+        self.lines.push(("};".to_string(), Span::call_site()));
+        Ok(())
+    }
+
+    fn enum_(
+        &mut self,
+        name: &str,
+        data: &DataEnum,
+        attrs: &[Attribute],
+        little_endian: bool,
+    ) -> syn::Result<()> {
+        let mut rep = None;
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+            if let Meta::List(list) = attr.parse_meta()? {
+                for nested in list.nested {
+                    if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                        rep = path
+                            .get_ident()
+                            .map(|ident| ident.to_string())
+                            .filter(|ident| SCALARS.contains(&&**ident));
+                    }
+                }
+            }
+        }
+        let rep = rep.ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                "enums need an integer representation, i.e. `#[repr(u8)]`",
+            )
+        })?;
+        self.lines.push((
+            format!(
+                "type {} = enum {} {{",
+                name,
+                Self::scalar(&rep, little_endian)
+            ),
+            Span::call_site(),
+        ));
+        for variant in data.variants.iter() {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new_spanned(
+                    &variant.fields,
+                    "enum variants cannot have fields",
+                ));
+            }
+            let line = match &variant.discriminant {
+                Some((_, value)) => {
+                    format!("    {} = {},", variant.ident, value.to_token_stream())
+                }
+                None => format!("    {},", variant.ident),
+            };
+            self.lines.push((line, variant.ident.span()));
+        }
+        self.lines.push(("};".to_string(), Span::call_site()));
+        Ok(())
+    }
+
+    /// Lines of the printed spec of the type
+    fn spec_lines(&self) -> Vec<(String, Span)> {
+        let mut out = self.rust_imports.clone();
+        if self.uses_utf8 {
+            out.push(("import_ffi utf8 as type;".to_string(), Span::call_site()));
+        }
+        if !out.is_empty() {
+            out.push((String::new(), Span::call_site()));
+        }
+        out.extend(self.lines.iter().cloned());
+        out
+    }
+
+    /// The printed spec of the type
+    fn text(&self) -> String {
+        let mut out = String::new();
+        for (line, _) in self.spec_lines() {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    fn source(&self) -> SpecSource {
+        let mut source = SpecSource::default();
+        for (line, span) in self.spec_lines() {
+            source.push_line(&line, span);
+        }
+        source
+    }
+}
+
+pub fn expand_derive(input: DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "generic types are not supported",
+        ));
+    }
+    let options = Options {
+        enum_derives: vec![],
+        struct_derives: vec![],
+        ..options()
+    };
+    let runtime: syn::Path = syn::parse_str(options.runtime.as_deref().unwrap_or("protospec"))?;
+    let mut spec = DeriveSpec {
+        runtime: runtime.to_token_stream(),
+        rust_imports: vec![],
+        rust_types: HashMap::new(),
+        uses_utf8: false,
+        lines: vec![],
+    };
+    let attrs = Attributes::parse(&input.attrs)?;
+    let name = input.ident.to_string();
+    match &input.data {
+        Data::Struct(data) => spec.container(&name, data, &attrs)?,
+        Data::Enum(data) => spec.enum_(
+            &name,
+            data,
+            &input.attrs,
+            attrs.little_endian.unwrap_or_default(),
+        )?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "unions are not supported",
+            ))
+        }
+    }
+
+    let source = spec.source();
+    let resolver = DeriveImportResolver {
+        rust_types: std::mem::take(&mut spec.rust_types),
+    };
+    let compiled = compile(&source.text, &resolver, &options)
+        .map_err(|(message, span)| syn::Error::new(source.span(span), message))?;
+
+    // the items are placed in an anonymous scope instead of the `_ps` module, to see the input and other derived types
+    let compiled: syn::File = syn::parse2(compiled)?;
+    let (attrs, items) = compiled
+        .items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::Mod(item) if item.ident == "_ps" => Some((item.attrs, item.content?.1)),
+            _ => None,
+        })
+        .expect("missing generated module");
+    // the type itself is declared by the input
+    let items = items.into_iter().filter(|item| match item {
+        Item::Struct(item) => item.ident != input.ident,
+        Item::Enum(item) => item.ident != input.ident,
+        _ => true,
+    });
+    let ident = &input.ident;
+    let text = spec.text();
+    Ok(quote! {
+        impl #ident {
+            /// The spec this type is encoded by
+            pub const SPEC: &'static str = #text;
+        }
+
+        #(#attrs)*
+        const _: () = {
+            #(#items)*
+        };
+    })
+}
@@ -0,0 +1,200 @@
+//! Procedural macros compiling specs in place of a `build.rs`, re-exported by `protospec` with its `macros` feature
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use protospec_build::{
+    asg, compile_program, parse, ImportResolver, NullImportResolver, Options, PreludeImportResolver,
+};
+use quote::{quote, quote_spanned};
+use std::path::PathBuf;
+use syn::{parse_macro_input, AttributeArgs, DeriveInput, ItemMod, Lit, Meta, NestedMeta};
+
+mod source;
+use source::*;
+
+mod rust_type;
+use rust_type::*;
+
+mod derive;
+use derive::*;
+
+fn options() -> Options {
+    Options {
+        include_async: cfg!(feature = "async"),
+        ..Default::default()
+    }
+}
+
+/// Compiles a spec, or returns the error message and its location in `spec`
+fn compile<T: ImportResolver + 'static>(
+    spec: &str,
+    resolver: &T,
+    options: &Options,
+) -> Result<TokenStream2, (String, Option<protospec_build::Span>)> {
+    let program = parse(spec).map_err(|e| (e.to_string(), e.span().copied()))?;
+    let program = asg::Program::from_ast(&program, resolver)
+        .map_err(|e| (e.to_string(), e.span().copied()))?;
+    program
+        .check_rust_names(options.rust_case)
+        .map_err(|e| (e.to_string(), e.span().copied()))?;
+    Ok(compile_program(&program, &options.compile_options()))
+}
+
+fn compile_prelude(spec: &str) -> Result<TokenStream2, (String, Option<protospec_build::Span>)> {
+    compile(spec, &PreludeImportResolver(NullImportResolver), &options())
+}
+
+fn expand_spec(input: TokenStream2) -> TokenStream2 {
+    let source = SpecSource::new(input);
+    match compile_prelude(&source.text) {
+        Ok(compiled) => compiled,
+        Err((message, span)) => {
+            let span = source.span(span);
+            quote_spanned! { span=> compile_error!(#message); }
+        }
+    }
+}
+
+/// Compiles the spec given as the macro input into the surrounding module
+#[proc_macro]
+pub fn spec(input: TokenStream) -> TokenStream {
+    expand_spec(input.into()).into()
+}
+
+fn expand_spec_file(args: AttributeArgs, item: ItemMod) -> syn::Result<TokenStream2> {
+    let mut path = None;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(value)) if value.path.is_ident("path") => {
+                match value.lit {
+                    Lit::Str(lit) => path = Some(lit),
+                    lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                }
+            }
+            arg => return Err(syn::Error::new_spanned(arg, "expected `path = \"...\"`")),
+        }
+    }
+    let path = path.ok_or_else(|| {
+        syn::Error::new(Span::call_site(), "missing `path = \"...\"` of the spec")
+    })?;
+    let ItemMod {
+        attrs,
+        vis,
+        ident,
+        content,
+        ..
+    } = item;
+    let (_, items) = content.ok_or_else(|| {
+        syn::Error::new_spanned(&ident, "expected an inline module `mod name {}`")
+    })?;
+
+    let mut full_path: PathBuf = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(Span::call_site(), "CARGO_MANIFEST_DIR env var not set"))?
+        .into();
+    full_path.push(path.value());
+    let spec = std::fs::read_to_string(&full_path).map_err(|e| {
+        syn::Error::new_spanned(
+            &path,
+            format!("failed to read {}: {}", full_path.display(), e),
+        )
+    })?;
+    let compiled = compile_prelude(&spec).map_err(|(message, _)| {
+        syn::Error::new_spanned(&path, format!("{}: {}", path.value(), message))
+    })?;
+
+    let full_path = full_path.to_string_lossy();
+    Ok(quote! {
+        #(#attrs)*
+        #vis mod #ident {
+            // rebuilds when the spec changes
+            const _: &[u8] = include_bytes!(#full_path);
+
+            #(#items)*
+
+            #compiled
+        }
+    })
+}
+
+/// Compiles the spec at `path`, relative to the crate root, into the annotated inline module
+#[proc_macro_attribute]
+pub fn spec_file(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item = parse_macro_input!(item as ItemMod);
+    expand_spec_file(args, item)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Implements the encoding and decoding of a struct of scalar, array, `String`, `Option` and other derived fields, or of a fieldless enum with an integer `#[repr]`, through the spec it describes
+#[proc_macro_derive(ProtoSpec, attributes(ps))]
+pub fn derive_protospec(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_derive(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_source() {
+        let source = SpecSource::new(quote! {
+            import_ffi gzip as transform;
+
+            type header = container {
+                len: u32 +auto,
+                data: u8[len] -> gzip,
+            };
+        });
+        assert_eq!(source.text.lines().count(), source.lines.len());
+        assert!(source.text.contains("\n->\n"));
+        compile_prelude(&source.text).unwrap();
+    }
+
+    #[test]
+    fn test_spec_error() {
+        let source = SpecSource::new(quote! {
+            type header = container {
+                data: u8[missing],
+            };
+        });
+        let (message, span) = compile_prelude(&source.text).err().unwrap();
+        assert!(message.contains("missing"));
+        assert_eq!(span.unwrap().line_start, 10);
+        assert!(expand_spec(quote! { type header = ; })
+            .to_string()
+            .starts_with("compile_error !"));
+    }
+
+    #[test]
+    fn test_derive_error() {
+        let error = expand_derive(syn::parse_quote! {
+            struct Header {
+                extra: Option<u32>,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("cond"));
+        let error = expand_derive(syn::parse_quote! {
+            enum Kind {
+                A = 1,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("repr"));
+        let error = expand_derive(syn::parse_quote! {
+            struct Header {
+                #[ps(len = "missing")]
+                data: Vec<u8>,
+            }
+        })
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("missing"));
+    }
+}
@@ -0,0 +1,126 @@
+use proc_macro2::TokenStream;
+use protospec_build::{
+    asg::{Type, TypeArgument},
+    ffi::{ForeignFunctionObj, ForeignTransformObj, ForeignType, ForeignTypeObj},
+    ImportResolver, NullImportResolver, PreludeImportResolver, Result,
+};
+use quote::quote;
+use std::collections::HashMap;
+
+/// A Rust type referenced by a derived type, encoded through its implementations of the runtime traits
+pub struct RustType {
+    pub path: TokenStream,
+    pub runtime: TokenStream,
+}
+
+impl ForeignType for RustType {
+    fn assignable_from(&self, _type_: &Type) -> bool {
+        false
+    }
+
+    fn assignable_to(&self, _type_: &Type) -> bool {
+        false
+    }
+
+    fn type_ref(&self) -> TokenStream {
+        self.path.clone()
+    }
+
+    fn decoding_gen(
+        &self,
+        source: TokenStream,
+        output_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+        is_async: bool,
+    ) -> TokenStream {
+        let Self { path, runtime } = self;
+        if is_async {
+            quote! {
+                let #output_ref = <#path as #runtime::AsyncDecode>::decode_async(#source, ()).await?;
+            }
+        } else {
+            quote! {
+                let #output_ref = <#path as #runtime::Decode>::decode_sync(#source, ())?;
+            }
+        }
+    }
+
+    fn encoding_gen(
+        &self,
+        target: TokenStream,
+        field_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+        is_async: bool,
+    ) -> TokenStream {
+        let Self { path, runtime } = self;
+        if is_async {
+            quote! {
+                <#path as #runtime::AsyncEncode>::encode_async(#field_ref, #target, ()).await?;
+            }
+        } else {
+            quote! {
+                <#path as #runtime::Encode>::encode_sync(#field_ref, #target, ())?;
+            }
+        }
+    }
+
+    fn encoded_len_gen(
+        &self,
+        field_ref: TokenStream,
+        _arguments: Vec<TokenStream>,
+    ) -> Option<TokenStream> {
+        let Self { path, runtime } = self;
+        Some(quote! { <#path as #runtime::Encode>::encoded_len(#field_ref, ())? })
+    }
+
+    fn arguments(&self) -> Vec<TypeArgument> {
+        vec![]
+    }
+
+    fn copyable(&self) -> bool {
+        false
+    }
+}
+
+/// Resolves the prelude, and the Rust types referenced by a derived type by their spec names
+pub struct DeriveImportResolver {
+    pub rust_types: HashMap<String, RustType>,
+}
+
+impl DeriveImportResolver {
+    fn prelude(&self) -> PreludeImportResolver<NullImportResolver> {
+        PreludeImportResolver(NullImportResolver)
+    }
+}
+
+impl ImportResolver for DeriveImportResolver {
+    fn normalize_import(&self, import: &str) -> Result<String> {
+        self.prelude().normalize_import(import)
+    }
+
+    fn resolve_import(&self, import: &str) -> Result<Option<String>> {
+        self.prelude().resolve_import(import)
+    }
+
+    fn resolve_ffi_transform(&self, name: &str) -> Result<Option<ForeignTransformObj>> {
+        self.prelude().resolve_ffi_transform(name)
+    }
+
+    fn resolve_ffi_type(&self, name: &str) -> Result<Option<ForeignTypeObj>> {
+        match self.rust_types.get(name) {
+            Some(RustType { path, runtime }) => Ok(Some(Box::new(RustType {
+                path: path.clone(),
+                runtime: runtime.clone(),
+            }))),
+            None => self.prelude().resolve_ffi_type(name),
+        }
+    }
+
+    fn resolve_ffi_function(&self, name: &str) -> Result<Option<ForeignFunctionObj>> {
+        self.prelude().resolve_ffi_function(name)
+    }
+
+    fn prelude_ffi_functions(&self) -> Result<HashMap<String, ForeignFunctionObj>> {
+        self.prelude().prelude_ffi_functions()
+    }
+}
@@ -0,0 +1,71 @@
+use proc_macro2::{Delimiter, Spacing, Span, TokenStream, TokenTree};
+
+/// Source text of a spec with the span of the Rust tokens each line originates from, mapping errors back to the tokens
+#[derive(Default)]
+pub struct SpecSource {
+    pub text: String,
+    pub lines: Vec<Span>,
+}
+
+impl SpecSource {
+    /// Places each token on its own line
+    pub fn new(tokens: TokenStream) -> Self {
+        let mut source = SpecSource::default();
+        source.push_stream(tokens);
+        source
+    }
+
+    pub fn push_line(&mut self, text: &str, span: Span) {
+        // string literals may span several lines themselves
+        for _ in 0..=text.matches('\n').count() {
+            self.lines.push(span);
+        }
+        self.text.push_str(text);
+        self.text.push('\n');
+    }
+
+    fn push_stream(&mut self, tokens: TokenStream) {
+        // joint punctuation such as `->` or `::` stays on one line
+        let mut punct: Option<(String, Span)> = None;
+        for token in tokens {
+            if let TokenTree::Punct(token) = &token {
+                let (mut text, span) = punct.take().unwrap_or((String::new(), token.span()));
+                text.push(token.as_char());
+                if token.spacing() == Spacing::Joint {
+                    punct = Some((text, span));
+                } else {
+                    self.push_line(&text, span);
+                }
+                continue;
+            }
+            if let Some((text, span)) = punct.take() {
+                self.push_line(&text, span);
+            }
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.push_line(open, group.span_open());
+                    self.push_stream(group.stream());
+                    self.push_line(close, group.span_close());
+                }
+                TokenTree::Ident(ident) => self.push_line(&ident.to_string(), ident.span()),
+                TokenTree::Literal(literal) => self.push_line(&literal.to_string(), literal.span()),
+                TokenTree::Punct(_) => unreachable!(),
+            }
+        }
+        if let Some((text, span)) = punct {
+            self.push_line(&text, span);
+        }
+    }
+
+    pub fn span(&self, span: Option<protospec_build::Span>) -> Span {
+        span.and_then(|span| self.lines.get((span.line_start as usize).checked_sub(1)?))
+            .copied()
+            .unwrap_or_else(Span::call_site)
+    }
+}
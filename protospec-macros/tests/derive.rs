@@ -0,0 +1,114 @@
+use protospec_macros::ProtoSpec;
+
+#[derive(ProtoSpec, Debug, PartialEq, Clone, Copy)]
+#[repr(u8)]
+enum Kind {
+    Data = 1,
+    Ack = 2,
+    Close = 7,
+}
+
+#[derive(ProtoSpec, Debug, PartialEq)]
+#[ps(le)]
+struct Header {
+    kind: Kind,
+    flags: u8,
+    count: u16,
+    #[ps(be)]
+    sequence: u32,
+    #[ps(len = "count")]
+    data: Vec<u8>,
+    #[ps(cond = "(flags & 1) != 0")]
+    extra: Option<u32>,
+    name: String,
+    tag: [u8; 2],
+}
+
+#[derive(ProtoSpec, Debug, PartialEq)]
+struct Packet {
+    header: Header,
+    count: u8,
+    #[ps(len = "count")]
+    trailer: Vec<i16>,
+}
+
+fn header() -> Header {
+    Header {
+        kind: Kind::Ack,
+        flags: 1,
+        count: 2,
+        sequence: 3,
+        data: vec![4, 5],
+        extra: Some(6),
+        name: "n".to_string(),
+        tag: [8, 9],
+    }
+}
+
+#[test]
+fn test_derive() {
+    let header = header();
+    let mut out = vec![];
+    header.encode_sync(&mut out).unwrap();
+    assert_eq!(
+        out,
+        [2, 1, 2, 0, 0, 0, 0, 3, 4, 5, 6, 0, 0, 0, b'n', 0, 8, 9]
+    );
+    assert_eq!(header.encoded_len().unwrap(), out.len());
+    assert_eq!(Header::decode_sync(&mut &out[..]).unwrap(), header);
+
+    let header = Header {
+        flags: 0,
+        extra: None,
+        ..header
+    };
+    let mut out = vec![];
+    header.encode_sync(&mut out).unwrap();
+    assert_eq!(Header::decode_sync(&mut &out[..]).unwrap(), header);
+
+    assert!(Kind::decode_sync(&mut &[3u8][..]).is_err());
+}
+
+#[test]
+fn test_derive_nested() {
+    let packet = Packet {
+        header: header(),
+        count: 2,
+        trailer: vec![-1, 2],
+    };
+    let mut out = vec![];
+    protospec::Encode::encode_sync(&packet, &mut out, ()).unwrap();
+    assert_eq!(&out[out.len() - 4..], [0xff, 0xff, 0, 2]);
+    let decoded = <Packet as protospec::Decode>::decode_sync(&mut &out[..], ()).unwrap();
+    assert_eq!(decoded, packet);
+}
+
+#[test]
+fn test_derive_spec() {
+    assert_eq!(
+        Header::SPEC,
+        "import_ffi Kind as type;
+import_ffi utf8 as type;
+
+type Header = container {
+    kind: Kind,
+    flags: u8,
+    count: u16le,
+    sequence: u32,
+    data: u8[count],
+    extra: u32le {(flags & 1) != 0},
+    name: utf8(),
+    tag: u8[2],
+};
+"
+    );
+    assert_eq!(
+        Kind::SPEC,
+        "type Kind = enum u8 {
+    Data = 1,
+    Ack = 2,
+    Close = 7,
+};
+"
+    );
+}
@@ -0,0 +1,52 @@
+use protospec_macros::{spec, spec_file};
+
+mod inline {
+    use super::*;
+
+    spec! {
+        import_ffi utf8 as type;
+
+        type header(version: u8) = container {
+            kind: u8,
+            name: utf8(),
+            extra: u32 {version > 1},
+        };
+    }
+}
+
+#[spec_file(path = "tests/spec/frame.pspec")]
+mod file {
+    pub fn empty() -> frame {
+        frame::default()
+    }
+}
+
+#[test]
+fn test_spec_inline() {
+    let header = inline::header {
+        kind: 3,
+        name: "test".to_string(),
+        extra: Some(7),
+    };
+    let mut out = vec![];
+    header.encode_sync(&mut out, 2).unwrap();
+    assert_eq!(out, b"\x03test\0\0\0\0\x07");
+    assert_eq!(
+        inline::header::decode_sync(&mut &out[..], 2).unwrap(),
+        header
+    );
+}
+
+#[test]
+fn test_spec_file() {
+    let frame = file::frame {
+        len: 0,
+        data: vec![1, 2, 3],
+    };
+    let mut out = vec![];
+    protospec::Encode::encode_sync(&frame, &mut out, ()).unwrap();
+    assert_eq!(out, [0, 3, 1, 2, 3]);
+    let decoded = <file::frame as protospec::Decode>::decode_sync(&mut &out[..], ()).unwrap();
+    assert_eq!(decoded.data, frame.data);
+    assert_eq!(file::empty().data, Vec::<u8>::new());
+}
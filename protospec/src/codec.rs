@@ -0,0 +1,38 @@
+use super::Result;
+use std::io::{BufRead, Read, Write};
+
+/// A type which can be decoded from a stream
+pub trait Decode: Sized {
+    /// The arguments of the type, as a tuple in declaration order, where optional arguments are `Option`s
+    type Args;
+
+    fn decode_sync<R: Read + BufRead>(reader: &mut R, args: Self::Args) -> Result<Self>;
+}
+
+/// A type which can be encoded to a stream
+pub trait Encode {
+    /// The arguments of the type, as a tuple in declaration order, where optional arguments are `Option`s
+    type Args;
+
+    fn encode_sync<W: Write>(&self, writer: &mut W, args: Self::Args) -> Result<()>;
+
+    /// The number of bytes `encode_sync` writes
+    fn encoded_len(&self, args: Self::Args) -> Result<usize>;
+}
+
+#[cfg(feature = "async")]
+pub trait AsyncDecode: Decode {
+    fn decode_async<R: tokio::io::AsyncBufRead + Send + Sync + Unpin>(
+        reader: &mut R,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self>> + Send;
+}
+
+#[cfg(feature = "async")]
+pub trait AsyncEncode: Encode {
+    fn encode_async<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        writer: &mut W,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+}
@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync + 'static>>;
+
+#[derive(Debug)]
+pub struct DecodeError(pub String);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for DecodeError {}
+
+#[derive(Debug)]
+pub struct EncodeError(pub String);
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for EncodeError {}
+
+pub fn encode_error<S: AsRef<str>>(value: S) -> EncodeError {
+    EncodeError(value.as_ref().to_string())
+}
+
+pub fn decode_error<S: AsRef<str>>(value: S) -> DecodeError {
+    DecodeError(value.as_ref().to_string())
+}
@@ -1,3 +1,17 @@
+//! Runtime support for code generated by `protospec-build`
+
+mod error;
+pub use error::*;
+
+mod codec;
+pub use codec::*;
+
+pub mod string;
+pub mod varint;
+
+#[cfg(feature = "macros")]
+pub use protospec_macros::{spec, spec_file, ProtoSpec};
+
 #[macro_export]
 macro_rules! include_spec {
     ($package: tt) => {
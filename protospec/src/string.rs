@@ -0,0 +1,20 @@
+//! Null terminated UTF-8 strings, as encoded by the `utf8` prelude type without a length
+
+use super::Result;
+use std::io::{BufRead, Write};
+
+/// Reads a string up to a null byte or the end of the stream, consuming the null byte
+pub fn read_cstring<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut buf = vec![];
+    reader.read_until(0u8, &mut buf)?;
+    if buf.last() == Some(&0u8) {
+        buf.pop();
+    }
+    Ok(String::from_utf8(buf)?)
+}
+
+pub fn write_cstring<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    writer.write_all(value.as_bytes())?;
+    writer.write_all(&[0u8])?;
+    Ok(())
+}
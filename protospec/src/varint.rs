@@ -0,0 +1,52 @@
+//! Unsigned LEB128 and zigzag varints, as encoded by the `v*` and `zigzag*` prelude types
+
+use super::{decode_error, Result};
+use std::io::{Read, Write};
+
+pub fn read_leb128<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 1];
+    let mut output = 0u64;
+    let mut shift = 0u32;
+    loop {
+        reader.read_exact(&mut buf[..])?;
+        let bits = (buf[0] & 127) as u64;
+        if shift >= 64 || (bits << shift) >> shift != bits {
+            return Err(decode_error("LEB128 varint overflows u64").into());
+        }
+        output |= bits << shift;
+        if buf[0] & 128 == 0 {
+            if buf[0] == 0 && shift > 0 {
+                return Err(decode_error("overlong LEB128 varint").into());
+            }
+            return Ok(output);
+        }
+        shift += 7;
+    }
+}
+
+pub fn write_leb128<W: Write>(writer: &mut W, mut value: u64) -> Result<()> {
+    while (value & !0b1111111) != 0 {
+        writer.write_all(&[(value as u8 & 127) | 128])?;
+        value >>= 7;
+    }
+    writer.write_all(&[value as u8])?;
+    Ok(())
+}
+
+/// The number of bytes `write_leb128` writes for `value`
+pub fn leb128_len(mut value: u64) -> usize {
+    let mut len = 1;
+    while (value & !0b1111111) != 0 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+pub fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}